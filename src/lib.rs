@@ -0,0 +1 @@
+pub mod osu_format;
@@ -1,61 +1,143 @@
-mod osu_format;
 mod osu_detect;
+mod osu_clean;
+mod osu_cli;
+mod osu_plan;
+mod osu_policy;
+mod osu_report;
+#[cfg(test)]
+mod test_support;
 
 use std::{fs, io};
+use std::io::{BufRead, IsTerminal, Write};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Instant};
 
-use osu_format::data::OsuFile;
-use osu_format::data::OsuFileConfig;
-
-/*
-    General todo's for this application:
-    - Specify modes for "cleaning"...
-        "Destructive" => Delete the files directly from your Songs folder.
-        "Copy" => Create a new directory with the files.
-        "Zip" => Same as copy, but outputs a zip.
-    - Handle when no Osu! installation was found.
-        Perhaps even offer an manual way of configuring Osu! installation path.
-    - Multi-threaded beatmap processing.
-    - Filter out non-Osu! gamemodes (taiko, ctb).
-*/
-#[derive(Default)]
-pub struct ShadowTransaction
+use structopt::StructOpt;
+use tokio::sync::Semaphore;
+
+use osu_song_minifier::osu_format::data::OsuFile;
+use osu_song_minifier::osu_format::data::OsuFileConfig;
+use osu_song_minifier::osu_format::data::OsuFileGamemode;
+use osu_song_minifier::osu_format::data::OsuFileStoryboard;
+use osu_song_minifier::osu_format::error::{ParseDiagnostic, ParseSeverity};
+use osu_clean::CleanMode;
+use osu_cli::Options;
+use osu_policy::{HitsoundPolicy, StoryboardPolicy, VideoPolicy};
+use osu_plan::{format_bytes, Plan, PlanFile, SongPlan};
+use osu_report::Report;
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_PARTIAL_FAILURE: i32 = 2;
+const EXIT_NOT_FOUND: i32 = 3;
+
+fn main()
 {
-    from: PathBuf,
-    to: PathBuf
+    std::process::exit(run());
 }
 
 #[tokio::main]
-async fn main() 
+async fn run() -> i32
 {
     let start = Instant::now(); 
-    let mut root: String = String::new();
+    let options: Options = Options::from_args();
 
-    match osu_detect::where_is_osu() 
+    let songs_path: PathBuf = match locate_songs(&options)
     {
-        Ok(v) => { root = v; },
-        Err(_) => { println!("Unable to locate Osu! install path."); }
+        Ok(path) => path,
+        Err(err) => 
+        { 
+            eprintln!("{}", err); 
+            return EXIT_NOT_FOUND; 
+        }
+    };
+
+    let output_path: PathBuf = match &options.output
+    {
+        Some(output) => output.clone(),
+        None => 
+        {
+            let parent = songs_path.parent().unwrap_or(&songs_path);
+            match options.mode
+            {
+                CleanMode::Zip => parent.join("Shadow.zip"),
+                _ => parent.join("Shadow")
+            }
+        }
     };
-    
-    let osu_path: PathBuf = Path::new(&root).to_path_buf();
-    let songs_path: PathBuf = osu_path.join("Songs");
 
-    if songs_path.exists() 
+    let exit_code = match iterate_songs(&options, songs_path, output_path).await
     {
-        match iterate_songs(osu_path, songs_path).await
+        Ok(0) => 
+        { 
+            if options.verbosity() >= 0 { status(&options, "Successfully parsed Osu! directory."); }
+            EXIT_SUCCESS
+        },
+        Ok(failed) =>
         {
-            Ok(_) => { println!("Successfully parsed Osu! directory.")},
-            Err(err) => { panic!("Failed to parse you Osu! directory, error: {}", err)}
+            eprintln!("Parsed your Osu! directory, but {} beatmap sets could not be evaluated and were left alone.", failed);
+            EXIT_PARTIAL_FAILURE
+        },
+        Err(err) => 
+        { 
+            eprintln!("Failed to parse your Osu! directory, error: {}", err); 
+            EXIT_FAILURE
         }
+    };
+
+    if options.verbosity() >= 0
+    {
+        let time = Instant::now().saturating_duration_since(start).as_secs_f32();
+        status(&options, &format!("Execution time: {} seconds", time));
+    }
+
+    exit_code
+}
+
+/// Where the human readable output goes, stderr when the report is written to stdout so it stays machine readable.
+fn status_output(options: &Options) -> Box<dyn Write>
+{
+    if options.report_to_stdout()
+    {
+        Box::new(io::stderr())
     }
     else
     {
-        panic!("We found an Osu! directory, but it does not contain a Songs folder. Do you have a partial installation?");
+        Box::new(io::stdout())
     }
+}
 
-    let time = Instant::now().saturating_duration_since(start).as_secs_f32();
-    println!("Execution time: {} seconds", time);
+fn status(options: &Options, message: &str)
+{
+    //NOTE: A closed stdout or stderr is not worth failing the run over.
+    let _ = writeln!(status_output(options), "{}", message);
+}
+
+fn locate_songs(options: &Options) -> Result<PathBuf, String>
+{
+    if let Some(songs) = &options.songs
+    {
+        if !songs.is_dir()
+        {
+            return Err(format!("The given Songs folder {:?} is not a directory.", songs));
+        }
+
+        return Ok(songs.clone());
+    }
+
+    let root: PathBuf = osu_detect::where_is_osu()
+        .ok_or_else(|| "Unable to locate Osu! install path, pass the Songs folder with --songs.".to_owned())?;
+
+    let songs_path: PathBuf = osu_detect::songs_folder(&root);
+
+    if !songs_path.is_dir()
+    {
+        return Err(format!("We found an Osu! directory at {:?}, but the Songs folder {:?} does not exist. Do you have a partial installation?", root, songs_path));
+    }
+
+    Ok(songs_path)
 }
 
 fn recurse_directory(path: PathBuf, predicate: impl Fn(PathBuf) -> bool) -> Vec<PathBuf>
@@ -64,166 +146,521 @@ fn recurse_directory(path: PathBuf, predicate: impl Fn(PathBuf) -> bool) -> Vec<
 
     if let Ok(entries) = fs::read_dir(path) 
     {
-        for entry in entries 
+        for entry in entries.flatten()
         {
-            if let Ok(entry) = entry 
+            //NOTE: Links are never followed, a loop would recurse forever and a destructive clean could reach outside the Songs folder.
+            if entry.file_type().map(|file_type| file_type.is_symlink()).unwrap_or(true)
             {
-                let path = entry.path();
+                continue;
+            }
 
-                if predicate(path.clone()) 
-                {
-                    found.push(path.clone());
-                }
+            let path = entry.path();
+
+            if predicate(path.clone()) 
+            {
+                found.push(path.clone());
             }
         }
     }
 
-    return found;
+    found
 }
 
-async fn iterate_songs(osu_path: PathBuf, songs_folder: PathBuf) -> Result<(), io::Error>
+fn walk_directory(path: PathBuf) -> Vec<PathBuf>
 {
-    let songs = recurse_directory(songs_folder, | path | { path.exists() && path.is_dir() });
-    let mut transactions: Vec<ShadowTransaction> = Vec::new();
+    let mut found: Vec<PathBuf> = Vec::new();
+
+    for entry in recurse_directory(path, |path| { path.exists() })
+    {
+        if entry.is_dir()
+        {
+            found.extend(walk_directory(entry));
+        }
+        else if entry.is_file()
+        {
+            found.push(entry);
+        }
+    }
+
+    found
+}
+
+#[derive(Default)]
+struct SongFileEvaluation
+{
+    keep: Vec<PathBuf>,
+    samples: Vec<String>,
+    storyboard: Vec<PathBuf>,
+    video: Option<PathBuf>,
+    is_storyboard_file: bool,
+    gamemode: Option<OsuFileGamemode>,
+    /// The file could not be parsed, so we can't tell what it references. It is kept as is.
+    unparsed: Option<PathBuf>,
+    warnings: Vec<String>
+}
+
+/// Evaluates and cleans every set, returns how many sets could not be evaluated.
+async fn iterate_songs(options: &Options, songs_folder: PathBuf, output: PathBuf) -> Result<usize, io::Error>
+{
+    let mut songs = recurse_directory(songs_folder.clone(), | path | { path.exists() && path.is_dir() });
+    let mut plan: Plan = Plan::default();
+
+    songs.sort();
+
+    //NOTE: Parsing is blocking work, so every set is evaluated on the blocking pool.
+    //      The semaphore bounds how many sets are in flight, handles are awaited in folder order to keep the plan deterministic.
+    let shared_options: Arc<Options> = Arc::new(options.clone());
+    let semaphore: Arc<Semaphore> = Arc::new(Semaphore::new(options.jobs()));
+    let mut evaluations = Vec::new();
 
     for song in songs 
     {
-        evaluate_song(&mut transactions, osu_path.clone(), song)?;
+        let folder_name: String = song.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+        if !options.accepts_folder(&folder_name)
+        {
+            if options.verbosity() >= 2 { status(options, &format!("Skipping song: {:?}", song)); }
+            continue;
+        }
+
+        let permit = semaphore.clone().acquire_owned().await.map_err(io::Error::other)?;
+        let song_options: Arc<Options> = shared_options.clone();
+        let song_songs_folder: PathBuf = songs_folder.clone();
+        let song_folder: PathBuf = song.clone();
+
+        evaluations.push((song_folder, tokio::task::spawn_blocking(move ||
+        {
+            let song_plan = evaluate_song(&song_options, song_songs_folder, song);
+            drop(permit);
+            song_plan
+        })));
+    }
+
+    //NOTE: A set that fails is left alone, the others are still cleaned.
+    for (song_folder, evaluation) in evaluations
+    {
+        let failure: String = match evaluation.await
+        {
+            Ok(Ok(Some(song_plan))) => { plan.songs.push(song_plan); continue; },
+            Ok(Ok(None)) => continue,
+            Ok(Err(err)) => format!("evaluating the set failed: {}", err),
+            Err(err) => format!("evaluating the set panicked: {}", err)
+        };
+
+        plan.songs.push(SongPlan { folder: song_folder, warnings: vec![failure], failed: true, ..Default::default() });
+    }
+
+    if options.dry_run
+    {
+        plan.print(options.verbosity(), &mut status_output(options))?;
+    }
+    else
+    {
+        if options.mode == CleanMode::Destructive && !options.yes && !confirm_destructive(&plan)?
+        {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "the destructive clean was not confirmed, nothing was deleted"));
+        }
+
+        osu_clean::perform_plan(&options.mode, &plan, &output).await?;
+
+        if options.verbosity() >= 0
+        {
+            plan.print_totals(&mut status_output(options))?;
+        }
     }
 
-    //perform_transactions(&transactions).await;
-    Ok(())
+    if let (Some(report_path), Some(report_format)) = (&options.report, options.report_format())
+    {
+        let report = Report::from_plan(&plan, &songs_folder, options.mode.to_string(), options.dry_run);
+        report.write(report_path, &report_format)?;
+    }
+
+    Ok(plan.failed_count())
+}
+
+/// Asks on stdin before deleting anything, without a terminal to ask on --yes is required.
+fn confirm_destructive(plan: &Plan) -> Result<bool, io::Error>
+{
+    let stdin = io::stdin();
+
+    if !stdin.is_terminal()
+    {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "destructive mode deletes files and needs confirmation, pass --yes when not running in a terminal"));
+    }
+
+    let removed: usize = plan.songs.iter().map(|song| song.removed.len()).sum();
+    let removed_folders: usize = plan.songs.iter().filter(|song| song.remove_folder).count();
+
+    eprint!("Destructive mode deletes {} files ({}) and {} whole sets from the Songs folder, continue? [y/N] ",
+        removed, format_bytes(plan.removed_bytes()), removed_folders);
+    io::stderr().flush()?;
+
+    let mut answer: String = String::new();
+    stdin.lock().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
 }
 
-fn evaluate_song(transactions: &mut Vec<ShadowTransaction>, osu_path: PathBuf, song_path: PathBuf) -> Result<(), io::Error>
+fn evaluate_song(options: &Options, songs_folder: PathBuf, song_path: PathBuf) -> Result<Option<SongPlan>, io::Error>
 {
-    //println!("Parsing song: {:?}", song_path);
-    Ok(iterate_song_files(transactions, osu_path, song_path)?)
+    if options.verbosity() >= 1 { status(options, &format!("Parsing song: {:?}", song_path)); }
+    iterate_song_files(options, songs_folder, song_path)
 }
 
-fn iterate_song_files(transactions: &mut Vec<ShadowTransaction>, osu_path: PathBuf, song_path: PathBuf) -> Result<(), io::Error>
+/// Builds the plan of a single beatmap set, None when the set has to be left alone.
+fn iterate_song_files(options: &Options, songs_folder: PathBuf, song_path: PathBuf) -> Result<Option<SongPlan>, io::Error>
 {
     let path = song_path.clone();
+    let mut evaluations: Vec<SongFileEvaluation> = Vec::new();
+    let mut gamemodes: Vec<OsuFileGamemode> = Vec::new();
     let mut keep: Vec<PathBuf> = Vec::new();
+    let mut samples: Vec<String> = Vec::new();
+    let mut videos: Vec<PathBuf> = Vec::new();
+    let mut unparsed: Vec<PathBuf> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
 
-    let files: Vec<PathBuf> = recurse_directory(song_path, |path| { path.exists() && path.is_file() });
+    let mut files: Vec<PathBuf> = walk_directory(song_path);
+    files.sort();
 
-    for file in files 
+    for file in files.iter() 
+    {
+        evaluations.push(evaluate_song_files(options, path.clone(), file.clone()));
+    }
+
+    for evaluation in evaluations.iter()
+    {
+        if let Some(gamemode) = &evaluation.gamemode
+        {
+            if !gamemodes.contains(gamemode) { gamemodes.push(gamemode.clone()); }
+        }
+    }
+
+    //NOTE: Without a single valid .osu file we can't tell what is referenced, leave the folder alone.
+    if gamemodes.is_empty()
     {
-        keep.extend(evaluate_song_files(path.clone(), file));
+        return Ok(None);
     }
 
+    let any_survivors: bool = gamemodes.iter().any(|gamemode| options.modes.contains(gamemode));
+
+    for evaluation in evaluations
+    {
+        //NOTE: A standalone storyboard is shown on every difficulty, so it survives as long as the set does.
+        let survives: bool = match &evaluation.gamemode
+        {
+            Some(gamemode) => options.modes.contains(gamemode),
+            None => evaluation.is_storyboard_file && any_survivors
+        };
+
+        if survives
+        {
+            keep.extend(evaluation.keep);
+            samples.extend(evaluation.samples);
+            keep.extend(evaluation.video.iter().filter(|video| keep_video(&options.video, video)).cloned());
+        }
+
+        videos.extend(evaluation.video);
+
+        let keep_storyboard: bool = match options.storyboard
+        {
+            StoryboardPolicy::Keep => true,
+            StoryboardPolicy::Used => survives,
+            StoryboardPolicy::Drop => false
+        };
+
+        if keep_storyboard
+        {
+            keep.extend(evaluation.storyboard);
+        }
+
+        unparsed.extend(evaluation.unparsed);
+        warnings.extend(evaluation.warnings);
+    }
+
+    keep.extend(resolve_samples(&path, &files, &samples));
     keep.sort();
     keep.dedup();
 
-    save_transaction(transactions, osu_path, path, keep);
-    Ok(())
+    //NOTE: Nothing is left to play once every difficulty is filtered out, so drop the set as a whole.
+    if !any_survivors
+    {
+        keep.clear();
+    }
+
+    //NOTE: Deleting a file we could not read could lose a difficulty, so those always stay.
+    let remove_folder: bool = !any_survivors && unparsed.is_empty();
+    keep.extend(unparsed);
+
+    let mut song_plan: SongPlan = save_plan(songs_folder, path, files, keep)?;
+    song_plan.gamemodes = gamemodes;
+    song_plan.warnings = warnings;
+    song_plan.remove_folder = remove_folder;
+    song_plan.removed_video_bytes = removed_video_bytes(&song_plan, &videos);
+
+    Ok(Some(song_plan))
 }
     
-fn evaluate_song_files(song_path: PathBuf, song_file_path: PathBuf) -> Vec<PathBuf>
+fn evaluate_song_files(options: &Options, song_path: PathBuf, song_file_path: PathBuf) -> SongFileEvaluation
 {
     let song_file_clone = song_file_path.clone();
     let song_path_clone = song_path.clone();
     let extension = song_file_clone.extension();
+    let mut evaluation: SongFileEvaluation = SongFileEvaluation::default();
     
     if let Some(ext) = extension 
     {
-        let ext_str: &str = ext.to_str().unwrap();
+        let ext_lossy = ext.to_string_lossy();
+        let ext_str: &str = &ext_lossy;
+        let parse_storyboard: bool = options.storyboard != StoryboardPolicy::Drop;
+        let relative_name = song_file_clone.strip_prefix(&song_path_clone).unwrap_or(&song_file_clone).to_string_lossy().into_owned();
+
+        if ext_str.eq_ignore_ascii_case("osb")
+        {
+            evaluation.is_storyboard_file = true;
+
+            if parse_storyboard
+            {
+                let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+                match storyboard.parse(song_file_path)
+                {
+                    Ok(diagnostics) => 
+                    {
+                        evaluation.storyboard.push(song_file_clone);
+                        evaluation.storyboard.extend(storyboard_assets(options, &storyboard).iter().map(|name| song_path_clone.join(name)));
+                        evaluation.warnings.extend(diagnostic_warnings(options, &relative_name, &diagnostics));
+                    },
+                    Err(err) =>
+                    {
+                        evaluation.warnings.push(format!("{} could not be parsed, keeping it: {}", relative_name, err.kind));
+                        evaluation.unparsed = Some(song_file_clone);
+                    }
+                }
+            }
+
+            return evaluation;
+        }
 
         if !ext_str.contains("osu")
         {
-            return Vec::new();
+            return evaluation;
         }
         
-        let mut paths_to_keep: Vec<PathBuf> = Vec::new();
+        let keep_hitsounds: bool = options.hitsounds == HitsoundPolicy::Keep;
         let mut osu_file: OsuFile = OsuFile::new();
-        osu_file.parse(song_file_path, OsuFileConfig {
+        let parsed = osu_file.parse(song_file_path, OsuFileConfig {
             parse_colours: false,
-            parse_difficulty: false,
+            parse_difficulty: keep_hitsounds,
             parse_editor: true,
             parse_metadata: true,
+            parse_storyboard,
+            parse_timing_points: keep_hitsounds,
+            parse_hit_objects: keep_hitsounds,
             ..Default::default()
         });
 
-        if !osu_file.is_valid 
+        if let Err(err) = parsed
         {
-            return Vec::new();
+            evaluation.warnings.push(format!("{} is not a valid .osu file, keeping it: {}", relative_name, err.kind));
+            evaluation.unparsed = Some(song_file_clone);
+            return evaluation;
         }
 
-        paths_to_keep.push(song_file_clone);
+        evaluation.warnings.extend(diagnostic_warnings(options, &relative_name, &osu_file.diagnostics));
+
+        evaluation.keep.push(song_file_clone);
+        evaluation.gamemode = Some(osu_file.general_section.mode.clone());
 
         if osu_file.events_section.background.exists 
         {
-            let background = Path::new(&song_path_clone).join(osu_file.events_section.background.file_name).clone();
-            paths_to_keep.push(background);
+            let background = Path::new(&song_path_clone).join(&osu_file.events_section.background.file_name);
+            evaluation.keep.push(background);
         }
 
-        let audio = Path::new(&song_path_clone).join(osu_file.general_section.audio_file_name);
-        paths_to_keep.push(audio);
+        let audio = Path::new(&song_path_clone).join(&osu_file.general_section.audio_file_name);
+        evaluation.keep.push(audio);
+
+        if osu_file.events_section.video.exists
+        {
+            evaluation.video = Some(song_path_clone.join(&osu_file.events_section.video.file_name));
+        }
+
+        if keep_hitsounds
+        {
+            evaluation.samples = osu_file.hitsound_samples();
+        }
 
-        return paths_to_keep;
+        if parse_storyboard
+        {
+            let assets = storyboard_assets(options, &osu_file.events_section.storyboard);
+            evaluation.storyboard.extend(assets.iter().map(|name| song_path_clone.join(name)));
+        }
     }
 
-    return Vec::new();
+    evaluation
 }
 
-fn strip_option(opt: Option<&str>) -> String
+/// Skipped lines become warnings of the set, notices are only printed when asked for.
+fn diagnostic_warnings(options: &Options, file_name: &str, diagnostics: &[ParseDiagnostic]) -> Vec<String>
 {
-    if let Some(stripped) = opt 
+    let mut warnings: Vec<String> = Vec::new();
+
+    for diagnostic in diagnostics
     {
-        return stripped.to_owned();
+        //NOTE: The set folder is already known to whoever reads the warning, so only the file name is kept.
+        let mut error = diagnostic.error.clone();
+        error.path = None;
+
+        let message = format!("{}:{}", file_name, error);
+
+        match diagnostic.severity
+        {
+            ParseSeverity::Notice => if options.verbosity() >= 2 { status(options, &message); },
+            _ => warnings.push(message)
+        }
     }
 
-    return String::new();
+    warnings
 }
 
-fn save_transaction(transactions: &mut Vec<ShadowTransaction>, osu_path: PathBuf, song_folder: PathBuf, keep: Vec<PathBuf>)
+fn storyboard_assets(options: &Options, storyboard: &OsuFileStoryboard) -> Vec<String>
 {
-    let shadow = Path::new(&osu_path).join("Shadow");
-    let osu = Path::new(&osu_path).join("Songs");
+    match options.storyboard
+    {
+        StoryboardPolicy::Used => storyboard.used_asset_file_names(),
+        _ => storyboard.asset_file_names()
+    }
+}
 
-    let shadow_str: String = strip_option(shadow.to_str());
-    let osu_str: String = strip_option(osu.to_str());
-    let song_str: String = strip_option(song_folder.to_str());
+fn keep_video(policy: &VideoPolicy, video: &Path) -> bool
+{
+    //NOTE: A video that is not on disk is kept, so it ends up in the missing files of the plan.
+    match fs::metadata(video)
+    {
+        Ok(metadata) => policy.keeps(metadata.len()),
+        Err(_) => *policy != VideoPolicy::Drop
+    }
+}
 
-    let new_str = song_str.replace(&osu_str, &shadow_str);
-    let new_path = Path::new(&new_str);
+fn removed_video_bytes(song_plan: &SongPlan, videos: &[PathBuf]) -> u64
+{
+    let video_keys: HashSet<String> = videos
+        .iter()
+        .map(|video| song_file_key(&song_plan.folder, video))
+        .collect();
+
+    song_plan.removed
+        .iter()
+        .filter(|file| video_keys.contains(&song_file_key(&song_plan.folder, &file.from)))
+        .map(|file| file.size)
+        .sum()
+}
 
-    if !new_path.exists() 
+fn resolve_samples(song_folder: &Path, files: &[PathBuf], samples: &[String]) -> Vec<PathBuf>
+{
+    //NOTE: Samples without an extension are looked up as .wav, .ogg or .mp3 by osu!.
+    let sample_keys: HashSet<&str> = samples.iter().map(|sample| sample.as_str()).collect();
+    let mut found: Vec<PathBuf> = Vec::new();
+
+    for file in files
     {
-        match fs::create_dir_all(new_path)
+        let key: String = song_file_key(song_folder, file);
+        let stem: &str = key
+            .strip_suffix(".wav")
+            .or_else(|| key.strip_suffix(".ogg"))
+            .or_else(|| key.strip_suffix(".mp3"))
+            .unwrap_or(&key);
+
+        if sample_keys.contains(key.as_str()) || sample_keys.contains(stem)
         {
-            Ok(_) => {},
-            Err(e) => { println!("Failed to create shadow directory: {}", e)}
+            found.push(file.clone());
         }
     }
 
-    for file in keep 
+    found
+}
+
+fn song_file_key(song_folder: &Path, file: &Path) -> String
+{
+    //NOTE: Osu! resolves file names case-insensitively and maps may use either slash.
+    let relative = file.strip_prefix(song_folder).unwrap_or(file);
+    
+    relative
+        .to_string_lossy()
+        .replace("\\", "/")
+        .to_lowercase()
+}
+
+fn save_plan(songs_folder: PathBuf, song_folder: PathBuf, files: Vec<PathBuf>, keep: Vec<PathBuf>) -> Result<SongPlan, io::Error>
+{
+    let keep_keys: HashSet<String> = keep
+        .iter()
+        .map(|file| song_file_key(&song_folder, file))
+        .collect();
+
+    let mut song_plan: SongPlan = SongPlan { folder: song_folder.clone(), ..Default::default() };
+    let mut found_keys: HashSet<String> = HashSet::new();
+
+    for file in files 
     {
-        let mut file_str = strip_option(file.to_str());
-        file_str = file_str.replace(&osu_str, &shadow_str);
-        let file_path = Path::new(&file_str);
+        let key: String = song_file_key(&song_folder, &file);
+        let plan_file = PlanFile {
+            to: file.strip_prefix(&songs_folder).unwrap_or(&file).to_path_buf(),
+            size: fs::metadata(&file)?.len(),
+            from: file
+        };
+
+        if keep_keys.contains(&key)
+        {
+            song_plan.kept.push(plan_file);
+            found_keys.insert(key);
+        }
+        else
+        {
+            song_plan.removed.push(plan_file);
+        }
+    }
 
-        transactions.push(ShadowTransaction {
-            from: file,
-            to: file_path.to_path_buf()
-        });
+    for file in keep
+    {
+        if !found_keys.contains(&song_file_key(&song_folder, &file))
+        {
+            let relative = file.strip_prefix(&song_folder).unwrap_or(&file);
+            song_plan.missing.push(relative.to_string_lossy().into_owned());
+        }
     }
+
+    Ok(song_plan)
 }
 
-async fn perform_transactions(transactions: &Vec<ShadowTransaction>)
+#[cfg(test)]
+mod tests
 {
-    for transaction in transactions
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::walk_directory;
+    use crate::test_support::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn walking_skips_symlinks()
     {
-        let mut to_path: PathBuf = Path::new(&transaction.to).to_path_buf();
-        to_path.pop();
+        let dir = TempDir::new();
+        let root: &PathBuf = &dir.path;
+        let song: PathBuf = root.join("Songs/1 Song");
+        let outside: PathBuf = root.join("outside.txt");
 
-        tokio::fs::create_dir_all(&to_path).await.unwrap();
+        fs::create_dir_all(&song).unwrap();
+        fs::write(song.join("map.osu"), "").unwrap();
+        fs::write(&outside, "").unwrap();
 
-        if Path::new(&transaction.from).exists()
-        {
-            println!("from {:?} to {:?}", transaction.from, transaction.to);
-            tokio::fs::copy(&transaction.from, &transaction.to).await.unwrap();
-        }
+        //NOTE: A link back to the parent would loop forever, a link to a file outside the set must never be planned.
+        std::os::unix::fs::symlink(root.join("Songs"), song.join("loop")).unwrap();
+        std::os::unix::fs::symlink(&outside, song.join("outside.txt")).unwrap();
+
+        let found: Vec<PathBuf> = walk_directory(root.join("Songs"));
+
+        assert_eq!(found, vec![song.join("map.osu")]);
     }
-}
\ No newline at end of file
+}
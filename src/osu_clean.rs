@@ -0,0 +1,266 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::osu_plan::Plan;
+
+///
+/// General todo's for this file:
+/// - Remove directories that became empty after a destructive clean.
+///
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CleanMode
+{
+    /// Delete the unreferenced files directly from the Songs folder.
+    Destructive,
+    /// Copy the referenced files into a new directory.
+    #[default]
+    Copy,
+    /// Same as copy, but writes the referenced files into a zip archive.
+    Zip
+}
+
+impl FromStr for CleanMode
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<CleanMode, Self::Err>
+    {
+        match input.to_ascii_lowercase().as_str()
+        {
+            "destructive" => Ok(CleanMode::Destructive),
+            "copy" => Ok(CleanMode::Copy),
+            "zip" => Ok(CleanMode::Zip),
+            _ => Err(format!("Unknown cleaning mode {}, expected destructive, copy or zip.", input))
+        }
+    }
+}
+
+impl fmt::Display for CleanMode
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        let name = match self
+        {
+            CleanMode::Destructive => "destructive",
+            CleanMode::Copy => "copy",
+            CleanMode::Zip => "zip"
+        };
+
+        write!(formatter, "{}", name)
+    }
+}
+
+pub async fn perform_plan(mode: &CleanMode, plan: &Plan, output: &Path) -> Result<(), io::Error>
+{
+    match mode
+    {
+        CleanMode::Destructive => perform_destructive(plan).await,
+        CleanMode::Copy => perform_copy(plan, output).await,
+        CleanMode::Zip =>
+        {
+            let owned_plan = plan.clone();
+            let owned_output = output.to_path_buf();
+
+            //NOTE: The zip writer is synchronous, keep it off the async worker threads.
+            tokio::task::spawn_blocking(move || perform_zip(&owned_plan, &owned_output))
+                .await
+                .map_err(io::Error::other)?
+        }
+    }
+}
+
+async fn perform_destructive(plan: &Plan) -> Result<(), io::Error>
+{
+    for song in plan.songs.iter()
+    {
+        if song.remove_folder
+        {
+            tokio::fs::remove_dir_all(&song.folder).await?;
+            continue;
+        }
+
+        for file in song.removed.iter()
+        {
+            tokio::fs::remove_file(&file.from).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn perform_copy(plan: &Plan, output: &Path) -> Result<(), io::Error>
+{
+    for file in plan.songs.iter().flat_map(|song| song.kept.iter())
+    {
+        let to_path: PathBuf = output.join(&file.to);
+
+        if let Some(parent) = to_path.parent()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::copy(&file.from, &to_path).await?;
+    }
+
+    Ok(())
+}
+
+fn perform_zip(plan: &Plan, output: &Path) -> Result<(), io::Error>
+{
+    if let Some(parent) = output.parent()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let archive = File::create(output)?;
+    let mut writer = ZipWriter::new(archive);
+
+    for file in plan.songs.iter().flat_map(|song| song.kept.iter())
+    {
+        //NOTE: Zip entries always use forward slashes, regardless of the platform.
+        let entry_name: String = file.to
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<String>>()
+            .join("/");
+
+        let options = FileOptions::default()
+            .compression_method(compression_for(&file.from));
+
+        writer.start_file(entry_name, options)?;
+
+        let mut source = File::open(&file.from)?;
+        io::copy(&mut source, &mut writer)?;
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+fn compression_for(path: &Path) -> CompressionMethod
+{
+    //NOTE: Audio, images and video are already compressed, deflating them again only costs time.
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str()
+    {
+        "osu" | "osb" | "txt" | "wav" => CompressionMethod::Deflated,
+        _ => CompressionMethod::Stored
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::fs;
+    use std::io::Read;
+    use std::path::{Path, PathBuf};
+
+    use zip::ZipArchive;
+
+    use super::{perform_plan, CleanMode};
+    use crate::osu_plan::{Plan, PlanFile, SongPlan};
+    use crate::test_support::{write, TempDir};
+
+    fn plan_file(songs: &Path, relative: &str) -> PlanFile
+    {
+        PlanFile { from: songs.join(relative), to: PathBuf::from(relative), size: 0 }
+    }
+
+    /// Two sets: one keeps a beatmap and background and drops a video, the other is removed entirely.
+    fn fixture(songs: &Path) -> Plan
+    {
+        for relative in ["1 Kept/map.osu", "1 Kept/sb/bg.jpg", "1 Kept/video.avi", "2 Gone/map.osu", "3 Untouched/map.osu"]
+        {
+            write(&songs.join(relative), relative);
+        }
+
+        let kept_song = SongPlan {
+            folder: songs.join("1 Kept"),
+            kept: vec![plan_file(songs, "1 Kept/map.osu"), plan_file(songs, "1 Kept/sb/bg.jpg")],
+            removed: vec![plan_file(songs, "1 Kept/video.avi")],
+            ..Default::default()
+        };
+
+        let removed_song = SongPlan {
+            folder: songs.join("2 Gone"),
+            removed: vec![plan_file(songs, "2 Gone/map.osu")],
+            remove_folder: true,
+            ..Default::default()
+        };
+
+        Plan { songs: vec![kept_song, removed_song] }
+    }
+
+    #[tokio::test]
+    async fn destructive_removes_only_the_planned_files()
+    {
+        let dir = TempDir::new();
+        let songs: PathBuf = dir.path.join("Songs");
+        let plan: Plan = fixture(&songs);
+
+        perform_plan(&CleanMode::Destructive, &plan, &dir.path.join("unused")).await.unwrap();
+
+        assert!(songs.join("1 Kept/map.osu").is_file());
+        assert!(songs.join("1 Kept/sb/bg.jpg").is_file());
+        assert!(!songs.join("1 Kept/video.avi").exists());
+        assert!(!songs.join("2 Gone").exists());
+        assert!(songs.join("3 Untouched/map.osu").is_file());
+        assert!(!dir.path.join("unused").exists());
+    }
+
+    #[tokio::test]
+    async fn copy_writes_only_the_kept_files()
+    {
+        let dir = TempDir::new();
+        let songs: PathBuf = dir.path.join("Songs");
+        let output: PathBuf = dir.path.join("Output");
+        let plan: Plan = fixture(&songs);
+
+        perform_plan(&CleanMode::Copy, &plan, &output).await.unwrap();
+
+        assert_eq!(fs::read_to_string(output.join("1 Kept/map.osu")).unwrap(), "1 Kept/map.osu");
+        assert_eq!(fs::read_to_string(output.join("1 Kept/sb/bg.jpg")).unwrap(), "1 Kept/sb/bg.jpg");
+        assert!(!output.join("1 Kept/video.avi").exists());
+        assert!(!output.join("2 Gone").exists());
+        assert!(!output.join("3 Untouched").exists());
+
+        //NOTE: Copying never touches the source folder.
+        assert!(songs.join("1 Kept/video.avi").is_file());
+        assert!(songs.join("2 Gone/map.osu").is_file());
+    }
+
+    #[tokio::test]
+    async fn zip_archives_only_the_kept_files()
+    {
+        let dir = TempDir::new();
+        let songs: PathBuf = dir.path.join("Songs");
+        let output: PathBuf = dir.path.join("Output/songs.zip");
+        let plan: Plan = fixture(&songs);
+
+        perform_plan(&CleanMode::Zip, &plan, &output).await.unwrap();
+
+        let mut archive = ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(String::from).collect();
+        names.sort();
+
+        assert_eq!(names, vec!["1 Kept/map.osu", "1 Kept/sb/bg.jpg"]);
+
+        let mut contents: String = String::new();
+        archive.by_name("1 Kept/sb/bg.jpg").unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "1 Kept/sb/bg.jpg");
+
+        assert!(songs.join("1 Kept/video.avi").is_file());
+        assert!(songs.join("2 Gone/map.osu").is_file());
+    }
+}
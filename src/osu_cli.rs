@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use structopt::StructOpt;
+
+use crate::osu_clean::CleanMode;
+use osu_song_minifier::osu_format::data::OsuFileGamemode;
+use crate::osu_policy::{HitsoundPolicy, StoryboardPolicy, VideoPolicy};
+use crate::osu_report::ReportFormat;
+
+#[derive(Clone, Debug, StructOpt)]
+#[structopt(name = "osu-song-minifier", about = "Strips unreferenced files from an Osu! Songs folder.")]
+pub struct Options
+{
+    /// Path to the Songs folder, skips detecting the Osu! installation when given.
+    #[structopt(short = "s", long = "songs", parse(from_os_str))]
+    pub songs: Option<PathBuf>,
+
+    /// Output directory for copy mode or archive path for zip mode, defaults to "Shadow" next to the Songs folder.
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// How to clean the Songs folder: destructive, copy or zip.
+    #[structopt(short = "m", long = "mode", default_value = "copy")]
+    pub mode: CleanMode,
+
+    /// Amount of beatmap sets to evaluate at the same time, defaults to the amount of CPU cores.
+    #[structopt(short = "j", long = "jobs")]
+    pub jobs: Option<usize>,
+
+    /// Skip the confirmation before a destructive clean deletes files.
+    #[structopt(short = "y", long = "yes")]
+    pub yes: bool,
+
+    /// Evaluate every beatmap set, but do not touch the filesystem.
+    #[structopt(short = "n", long = "dry-run")]
+    pub dry_run: bool,
+
+    /// Print more output, can be repeated.
+    #[structopt(short = "v", long = "verbose", parse(from_occurrences))]
+    pub verbose: u8,
+
+    /// Only print errors.
+    #[structopt(short = "q", long = "quiet")]
+    pub quiet: bool,
+
+    /// Only process beatmap set folders matching this regex, can be repeated.
+    #[structopt(long = "include", number_of_values = 1, parse(try_from_str = Regex::new))]
+    pub include: Vec<Regex>,
+
+    /// Skip beatmap set folders matching this regex, can be repeated.
+    #[structopt(long = "exclude", number_of_values = 1, parse(try_from_str = Regex::new))]
+    pub exclude: Vec<Regex>,
+
+    /// Gamemodes to keep difficulties of, comma separated: osu, taiko, catch and mania.
+    #[structopt(long = "modes", use_delimiter = true, default_value = "osu,taiko,catch,mania")]
+    pub modes: Vec<OsuFileGamemode>,
+
+    /// What to do with custom hitsound samples: keep or drop.
+    #[structopt(long = "hitsounds", default_value = "keep")]
+    pub hitsounds: HitsoundPolicy,
+
+    /// What to do with storyboards and their assets: keep, used or drop.
+    #[structopt(long = "storyboard", default_value = "keep")]
+    pub storyboard: StoryboardPolicy,
+
+    /// What to do with background videos: keep, drop or keep those under a size, like 50MB.
+    #[structopt(long = "video", default_value = "drop")]
+    pub video: VideoPolicy,
+
+    /// Write a report of the run to this path, "-" writes it to stdout.
+    #[structopt(long = "report", parse(from_os_str))]
+    pub report: Option<PathBuf>,
+
+    /// Format of the report: json or csv, defaults to the extension of the report path.
+    #[structopt(long = "report-format")]
+    pub report_format: Option<ReportFormat>,
+}
+
+impl Options
+{
+    /// -1 when quiet, 0 by default and higher for every -v flag.
+    pub fn verbosity(&self) -> i8
+    {
+        if self.quiet
+        {
+            return -1;
+        }
+
+        self.verbose.min(i8::MAX as u8) as i8
+    }
+
+    pub fn jobs(&self) -> usize
+    {
+        match self.jobs
+        {
+            Some(jobs) => jobs.max(1),
+            None => std::thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1)
+        }
+    }
+
+    pub fn report_format(&self) -> Option<ReportFormat>
+    {
+        match (&self.report, &self.report_format)
+        {
+            (Some(_), Some(format)) => Some(format.clone()),
+            (Some(path), None) => Some(ReportFormat::from_path(path)),
+            _ => None
+        }
+    }
+
+    /// The report takes stdout when its path is "-", everything else is printed to stderr then.
+    pub fn report_to_stdout(&self) -> bool
+    {
+        self.report.as_deref() == Some(Path::new("-"))
+    }
+
+    pub fn accepts_folder(&self, folder_name: &str) -> bool
+    {
+        let included = self.include.is_empty() || self.include.iter().any(|regex| regex.is_match(folder_name));
+        let excluded = self.exclude.iter().any(|regex| regex.is_match(folder_name));
+
+        included && !excluded
+    }
+}
@@ -0,0 +1,170 @@
+use std::cmp::Reverse;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The songs folder configured through BeatmapDirectory in the osu!.<username>.cfg files of the installation.
+pub fn beatmap_directory(install_dir: &Path) -> Option<PathBuf>
+{
+    for config in user_configs(install_dir)
+    {
+        let configured = read_setting(&config, "BeatmapDirectory")
+            .and_then(|value| resolve_directory(install_dir, &value));
+
+        if let Some(directory) = configured.filter(|directory| directory.is_dir())
+        {
+            return Some(directory);
+        }
+    }
+
+    None
+}
+
+/// Every osu!.<username>.cfg file, the current user first and then the most recently modified.
+fn user_configs(install_dir: &Path) -> Vec<PathBuf>
+{
+    let mut configs: Vec<(bool, SystemTime, PathBuf)> = Vec::new();
+    let current_user: Option<String> = env::var("USERNAME").or_else(|_| env::var("USER")).ok();
+
+    let entries = match fs::read_dir(install_dir)
+    {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new()
+    };
+
+    for entry in entries.flatten()
+    {
+        let file_name: String = entry.file_name().to_string_lossy().into_owned();
+
+        //NOTE: osu!.cfg holds the global settings, the per-user file is named after the OS user.
+        let username = match file_name.strip_prefix("osu!.").and_then(|name| name.strip_suffix(".cfg"))
+        {
+            Some(username) if !username.is_empty() => username.to_owned(),
+            _ => continue
+        };
+
+        let modified: SystemTime = entry.metadata().and_then(|metadata| metadata.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+        let is_current_user: bool = current_user.as_deref().map(|user| user.eq_ignore_ascii_case(&username)).unwrap_or(false);
+
+        configs.push((is_current_user, modified, entry.path()));
+    }
+
+    configs.sort_by_key(|(is_current_user, modified, _)| Reverse((*is_current_user, *modified)));
+    configs.into_iter().map(|(_, _, path)| path).collect()
+}
+
+/// Reads a "Key = Value" line out of an osu! cfg file.
+fn read_setting(config: &Path, key: &str) -> Option<String>
+{
+    let bytes = fs::read(config).ok()?;
+    let contents = String::from_utf8_lossy(&bytes);
+
+    for line in contents.lines()
+    {
+        let line = line.trim();
+
+        if line.starts_with('#')
+        {
+            continue;
+        }
+
+        if let Some((name, value)) = line.split_once('=')
+        {
+            if name.trim() == key && !value.trim().is_empty()
+            {
+                return Some(value.trim().to_owned());
+            }
+        }
+    }
+
+    None
+}
+
+/// BeatmapDirectory is either relative to the installation or an absolute Windows path.
+fn resolve_directory(install_dir: &Path, value: &str) -> Option<PathBuf>
+{
+    let is_windows_absolute: bool = value.starts_with("\\\\") || value.get(1..3).map(|root| root == ":\\" || root == ":/").unwrap_or(false);
+
+    if is_windows_absolute
+    {
+        #[cfg(windows)]
+        return Some(PathBuf::from(value));
+        #[cfg(not(windows))]
+        return super::wine::host_path(install_dir, value);
+    }
+
+    if Path::new(value).is_absolute()
+    {
+        return Some(PathBuf::from(value));
+    }
+
+    let mut directory: PathBuf = install_dir.to_path_buf();
+    directory.extend(value.split(['\\', '/']).filter(|component| !component.is_empty()));
+    Some(directory)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{beatmap_directory, read_setting, resolve_directory};
+    use crate::test_support::{write, TempDir};
+
+    #[test]
+    fn settings_are_read_from_the_config()
+    {
+        let dir = TempDir::new();
+        let config: PathBuf = dir.path.join("osu!.me.cfg");
+        write(&config, "# osu! configuration for me\r\n# BeatmapDirectory = Commented\r\nVolumeMusic = 80\r\nBeatmapDirectory = D:\\Maps\r\nSkin = \r\n");
+
+        assert_eq!(read_setting(&config, "BeatmapDirectory").as_deref(), Some("D:\\Maps"));
+        assert_eq!(read_setting(&config, "VolumeMusic").as_deref(), Some("80"));
+        assert_eq!(read_setting(&config, "Skin"), None);
+        assert_eq!(read_setting(&config, "Missing"), None);
+    }
+
+    #[test]
+    fn relative_directories_resolve_against_the_install()
+    {
+        let install_dir: PathBuf = PathBuf::from("/games/osu!");
+
+        assert_eq!(resolve_directory(&install_dir, "Songs"), Some(install_dir.join("Songs")));
+        assert_eq!(resolve_directory(&install_dir, "Maps\\Ranked"), Some(install_dir.join("Maps").join("Ranked")));
+        assert_eq!(resolve_directory(&install_dir, "/mnt/maps"), Some(PathBuf::from("/mnt/maps")));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn windows_directories_resolve_through_wine()
+    {
+        let dir = TempDir::new();
+        let prefix: PathBuf = dir.path.join("prefix");
+        let install_dir: PathBuf = prefix.join("drive_c").join("osu!");
+        write(&prefix.join("system.reg"), "WINE REGISTRY Version 2\n");
+        fs::create_dir_all(&install_dir).unwrap();
+
+        assert_eq!(resolve_directory(&install_dir, "C:\\Maps"), Some(prefix.join("drive_c").join("Maps")));
+        assert_eq!(resolve_directory(&install_dir, "C:/Maps"), Some(prefix.join("drive_c").join("Maps")));
+    }
+
+    #[test]
+    fn beatmap_directory_must_exist()
+    {
+        let dir = TempDir::new();
+        let install_dir: PathBuf = dir.path.join("osu!");
+        fs::create_dir_all(install_dir.join("Maps")).unwrap();
+
+        //NOTE: osu!.cfg holds the global settings and is never read for the beatmap directory.
+        write(&install_dir.join("osu!.cfg"), "BeatmapDirectory = Maps\n");
+        assert_eq!(beatmap_directory(&install_dir), None);
+
+        write(&install_dir.join("osu!.me.cfg"), "BeatmapDirectory = Gone\n");
+        assert_eq!(beatmap_directory(&install_dir), None);
+
+        write(&install_dir.join("osu!.me.cfg"), "BeatmapDirectory = Maps\n");
+        assert_eq!(beatmap_directory(&install_dir), Some(install_dir.join("Maps")));
+    }
+}
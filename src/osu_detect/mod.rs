@@ -0,0 +1,72 @@
+mod config;
+#[cfg(windows)]
+mod windows;
+#[cfg(not(windows))]
+mod wine;
+
+use std::path::{Path, PathBuf};
+
+///
+/// General todo, fixes and pain points for this file.
+/// - Detect osu! installed through Proton in a Steam library.
+///
+pub fn where_is_osu() -> Option<PathBuf>
+{
+    #[cfg(windows)]
+    let registered: Option<PathBuf> = windows::registered_install_dir();
+    #[cfg(not(windows))]
+    let registered: Option<PathBuf> = wine::registered_install_dir();
+
+    #[cfg(windows)]
+    let well_known: Vec<PathBuf> = windows::well_known_install_dirs();
+    #[cfg(not(windows))]
+    let well_known: Vec<PathBuf> = wine::well_known_install_dirs();
+
+    registered
+        .filter(|path| is_install_dir(path))
+        .or_else(|| well_known.into_iter().find(|path| is_install_dir(path)))
+}
+
+/// The songs folder of an installation, honouring a BeatmapDirectory moved away from the default "Songs".
+pub fn songs_folder(install_dir: &Path) -> PathBuf
+{
+    config::beatmap_directory(install_dir).unwrap_or_else(|| install_dir.join("Songs"))
+}
+
+fn is_install_dir(path: &Path) -> bool
+{
+    path.join("osu!.exe").is_file()
+}
+
+/// Takes the directory of osu!.exe out of the `osu\shell\open\command` value,
+/// which looks like `"C:\...\osu!.exe" "%1"`.
+fn install_dir_from_command(command: &str) -> Option<String>
+{
+    let command = command.trim();
+
+    //NOTE: The executable is quoted when its path contains spaces, otherwise it ends at the first space.
+    let executable: &str = match command.strip_prefix('"')
+    {
+        Some(quoted) => quoted.split('"').next()?,
+        None => command.split(' ').next()?
+    };
+
+    let separator = executable.rfind('\\')?;
+    Some(executable[..separator].to_owned())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::install_dir_from_command;
+
+    #[test]
+    fn install_dir_is_taken_from_the_command()
+    {
+        assert_eq!(install_dir_from_command(r#""C:\Program Files\osu!\osu!.exe" "%1""#).unwrap(), r"C:\Program Files\osu!");
+        assert_eq!(install_dir_from_command(r"C:\osu!\osu!.exe %1").unwrap(), r"C:\osu!");
+        assert_eq!(install_dir_from_command(r#"  "C:\osu!\osu!.exe"  "#).unwrap(), r"C:\osu!");
+        assert_eq!(install_dir_from_command("osu!.exe %1"), None);
+        assert_eq!(install_dir_from_command(""), None);
+    }
+}
@@ -0,0 +1,38 @@
+use std::env;
+use std::path::PathBuf;
+
+use winreg::enums::{HKEY_CLASSES_ROOT, KEY_READ};
+use winreg::RegKey;
+
+use super::install_dir_from_command;
+
+pub fn registered_install_dir() -> Option<PathBuf>
+{
+    //NOTE: Basically we're fetching the path that Osu! uses to handle .osz/.osu and etc. files.
+    let classes_root = RegKey::predef(HKEY_CLASSES_ROOT);
+    let registry_key: RegKey = classes_root.open_subkey_with_flags(r#"osu\shell\open\command"#, KEY_READ).ok()?;
+    let value: String = registry_key.get_value("").ok()?;
+
+    install_dir_from_command(&value).map(PathBuf::from)
+}
+
+pub fn well_known_install_dirs() -> Vec<PathBuf>
+{
+    let mut directories: Vec<PathBuf> = Vec::new();
+
+    if let Some(local_app_data) = env::var_os("LOCALAPPDATA")
+    {
+        directories.push(PathBuf::from(local_app_data).join("osu!"));
+    }
+
+    for program_files in ["ProgramFiles", "ProgramFiles(x86)"]
+    {
+        if let Some(path) = env::var_os(program_files)
+        {
+            directories.push(PathBuf::from(path).join("osu!"));
+        }
+    }
+
+    directories.push(PathBuf::from(r#"C:\osu!"#));
+    directories
+}
@@ -0,0 +1,356 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::install_dir_from_command;
+
+//NOTE: HKEY_CLASSES_ROOT is a merged view of Software\Classes in HKEY_LOCAL_MACHINE (system.reg) and HKEY_CURRENT_USER (user.reg).
+const REGISTRY_FILES: [&str; 2] = ["user.reg", "system.reg"];
+const COMMAND_KEY: &str = r#"software\\classes\\osu\\shell\\open\\command"#;
+
+pub fn registered_install_dir() -> Option<PathBuf>
+{
+    for prefix in wine_prefixes()
+    {
+        for registry_file in REGISTRY_FILES.iter()
+        {
+            let command = match read_registry_command(&prefix.join(registry_file))
+            {
+                Some(command) => command,
+                None => continue
+            };
+
+            let install_dir = install_dir_from_command(&command).and_then(|path| windows_to_prefix_path(&prefix, &path));
+
+            if let Some(install_dir) = install_dir.filter(|path| super::is_install_dir(path))
+            {
+                return Some(install_dir);
+            }
+        }
+    }
+
+    None
+}
+
+pub fn well_known_install_dirs() -> Vec<PathBuf>
+{
+    let mut directories: Vec<PathBuf> = Vec::new();
+
+    for prefix in wine_prefixes()
+    {
+        let drive_c = prefix.join("drive_c");
+
+        if let Ok(users) = fs::read_dir(drive_c.join("users"))
+        {
+            for user in users.filter_map(Result::ok)
+            {
+                directories.push(user.path().join("AppData").join("Local").join("osu!"));
+                directories.push(user.path().join("Local Settings").join("Application Data").join("osu!"));
+            }
+        }
+
+        directories.push(drive_c.join("Program Files").join("osu!"));
+        directories.push(drive_c.join("Program Files (x86)").join("osu!"));
+        directories.push(drive_c.join("osu!"));
+    }
+
+    //NOTE: Installers like osu-winello keep the game outside of the prefix.
+    if let Some(home) = home_dir()
+    {
+        directories.push(home.join(".local").join("share").join("osu-wine").join("osu!"));
+        directories.push(home.join(".local").join("share").join("osu-wine").join("OSU"));
+        directories.push(home.join("osu!"));
+    }
+
+    directories
+}
+
+/// Every directory that looks like a Wine prefix, in order of preference.
+fn wine_prefixes() -> Vec<PathBuf>
+{
+    let mut candidates: Vec<PathBuf> = Vec::new();
+
+    if let Some(prefix) = env::var_os("WINEPREFIX")
+    {
+        candidates.push(PathBuf::from(prefix));
+    }
+
+    if let Some(home) = home_dir()
+    {
+        let data = env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".local").join("share"));
+
+        candidates.push(home.join(".wine"));
+        candidates.extend(sub_directories(&data.join("wineprefixes")));
+
+        //NOTE: Lutris installs every game into its own prefix below ~/Games by default.
+        candidates.extend(sub_directories(&home.join("Games")));
+
+        //NOTE: Bottles keeps one prefix per bottle, the flatpak version lives in its own data directory.
+        candidates.extend(sub_directories(&data.join("bottles").join("bottles")));
+        candidates.extend(sub_directories(&home.join(".var").join("app").join("com.usebottles.bottles").join("data").join("bottles").join("bottles")));
+    }
+
+    let mut prefixes: Vec<PathBuf> = Vec::new();
+
+    for candidate in candidates
+    {
+        if is_wine_prefix(&candidate) && !prefixes.contains(&candidate)
+        {
+            prefixes.push(candidate);
+        }
+    }
+
+    prefixes
+}
+
+fn is_wine_prefix(path: &Path) -> bool
+{
+    path.join("system.reg").is_file() || path.join("drive_c").is_dir()
+}
+
+fn sub_directories(path: &Path) -> Vec<PathBuf>
+{
+    let mut directories: Vec<PathBuf> = match fs::read_dir(path)
+    {
+        Ok(entries) => entries.filter_map(Result::ok).map(|entry| entry.path()).filter(|path| path.is_dir()).collect(),
+        Err(_) => Vec::new()
+    };
+
+    directories.sort();
+    directories
+}
+
+fn home_dir() -> Option<PathBuf>
+{
+    env::var_os("HOME").filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
+/// Reads the default value of `osu\shell\open\command` out of a Wine registry file.
+///
+/// The files are plain text, keys are written as `[Software\\Classes\\osu\\shell\\open\\command] 1634567890`
+/// followed by their values, the default value being `@="..."` with backslashes and quotes escaped.
+fn read_registry_command(registry_file: &Path) -> Option<String>
+{
+    //NOTE: Registry files are usually UTF-8, but can contain stray bytes in unrelated keys.
+    let bytes = fs::read(registry_file).ok()?;
+    let contents = String::from_utf8_lossy(&bytes);
+    let mut in_command_key: bool = false;
+
+    for line in contents.lines()
+    {
+        let line = line.trim();
+
+        if line.starts_with('[')
+        {
+            let key = line.split(']').next().unwrap_or_default().trim_start_matches('[');
+            in_command_key = key.eq_ignore_ascii_case(COMMAND_KEY);
+            continue;
+        }
+
+        if !in_command_key
+        {
+            continue;
+        }
+
+        if let Some(value) = line.strip_prefix("@=")
+        {
+            //NOTE: Expandable strings are written as str(2):"...".
+            let start = value.find('"')?;
+            return Some(unescape_registry_string(&value[start..]));
+        }
+    }
+
+    None
+}
+
+fn unescape_registry_string(value: &str) -> String
+{
+    let quoted = value.strip_prefix('"').unwrap_or(value);
+    let quoted = quoted.strip_suffix('"').unwrap_or(quoted);
+    let mut unescaped: String = String::with_capacity(quoted.len());
+    let mut characters = quoted.chars();
+
+    while let Some(character) = characters.next()
+    {
+        if character == '\\'
+        {
+            if let Some(escaped) = characters.next()
+            {
+                unescaped.push(escaped);
+            }
+
+            continue;
+        }
+
+        unescaped.push(character);
+    }
+
+    unescaped
+}
+
+/// Maps an absolute Windows path, as seen by osu! running from the install directory, onto the host filesystem.
+pub fn host_path(install_dir: &Path, windows_path: &str) -> Option<PathBuf>
+{
+    if let Some(prefix) = install_dir.ancestors().find(|path| is_wine_prefix(path))
+    {
+        return windows_to_prefix_path(prefix, windows_path);
+    }
+
+    //NOTE: Without a prefix only the Z: drive can be mapped, Wine points it at the root of the host.
+    let drive = windows_path.get(..2).filter(|drive| drive.eq_ignore_ascii_case("z:"))?;
+    let mut path: PathBuf = PathBuf::from("/");
+    path.extend(windows_path[drive.len()..].split(['\\', '/']).filter(|component| !component.is_empty()));
+    Some(path)
+}
+
+/// Maps a Windows path like `C:\users\me\AppData\Local\osu!` into the prefix it was registered in.
+fn windows_to_prefix_path(prefix: &Path, windows_path: &str) -> Option<PathBuf>
+{
+    let mut components = windows_path.split(['\\', '/']).filter(|component| !component.is_empty());
+    let drive: String = components.next()?.to_ascii_lowercase();
+
+    if drive.len() != 2 || !drive.ends_with(':')
+    {
+        return None;
+    }
+
+    //NOTE: dosdevices holds a symlink per drive letter, drive_c is the default target of c:.
+    let mut path: PathBuf = prefix.join("dosdevices").join(&drive);
+
+    if !path.exists() && drive == "c:"
+    {
+        path = prefix.join("drive_c");
+    }
+
+    for component in components
+    {
+        path.push(component);
+    }
+
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{host_path, read_registry_command, unescape_registry_string, windows_to_prefix_path};
+    use crate::osu_detect::install_dir_from_command;
+    use crate::test_support::{write, TempDir};
+
+    const USER_REG: &str = concat!(
+        "WINE REGISTRY Version 2\n",
+        ";; All keys relative to \\\\User\\\\S-1-5-21-0-0-0-1000\n",
+        "\n",
+        "#arch=win64\n",
+        "\n",
+        "[Software\\\\Classes\\\\osu] 1634567890\n",
+        "#time=1d7c4a0f5e8b2c0\n",
+        "@=\"URL:osu! Protocol\"\n",
+        "\"URL Protocol\"=\"\"\n",
+        "\n",
+        "[Software\\\\Classes\\\\osu\\\\shell\\\\open\\\\command] 1634567890\n",
+        "#time=1d7c4a0f5e8b2c0\n",
+        "@=\"\\\"C:\\\\users\\\\me\\\\AppData\\\\Local\\\\osu!\\\\osu!.exe\\\" \\\"%1\\\"\"\n",
+        "\n",
+        "[Software\\\\Wine\\\\Drivers] 1634567890\n",
+        "@=\"unrelated\"\n"
+    );
+
+    #[test]
+    fn registry_strings_are_unescaped()
+    {
+        assert_eq!(unescape_registry_string(r#""C:\\osu!\\osu!.exe""#), r"C:\osu!\osu!.exe");
+        assert_eq!(unescape_registry_string(r#""\"C:\\Program Files\\osu!\\osu!.exe\" \"%1\"""#), r#""C:\Program Files\osu!\osu!.exe" "%1""#);
+        assert_eq!(unescape_registry_string(r#""plain""#), "plain");
+    }
+
+    #[test]
+    fn command_is_read_from_the_user_registry()
+    {
+        let dir = TempDir::new();
+        let registry: PathBuf = dir.path.join("user.reg");
+        write(&registry, USER_REG);
+
+        let command: String = read_registry_command(&registry).unwrap();
+
+        assert_eq!(command, r#""C:\users\me\AppData\Local\osu!\osu!.exe" "%1""#);
+        assert_eq!(install_dir_from_command(&command).unwrap(), r"C:\users\me\AppData\Local\osu!");
+    }
+
+    #[test]
+    fn command_is_read_from_the_system_registry()
+    {
+        let dir = TempDir::new();
+        let registry: PathBuf = dir.path.join("system.reg");
+
+        //NOTE: Key names are case insensitive, and expandable strings carry a str(2): type prefix.
+        write(&registry, concat!(
+            "WINE REGISTRY Version 2\n",
+            ";; All keys relative to \\\\Machine\n",
+            "\n",
+            "[Software\\\\Classes\\\\osu\\\\Shell\\\\Open\\\\Command] 1634567890\n",
+            "@=str(2):\"D:\\\\Games\\\\osu!\\\\osu!.exe %1\"\n"
+        ));
+
+        let command: String = read_registry_command(&registry).unwrap();
+
+        assert_eq!(command, r"D:\Games\osu!\osu!.exe %1");
+        assert_eq!(install_dir_from_command(&command).unwrap(), r"D:\Games\osu!");
+    }
+
+    #[test]
+    fn missing_command_key_reads_nothing()
+    {
+        let dir = TempDir::new();
+        let registry: PathBuf = dir.path.join("user.reg");
+        write(&registry, "WINE REGISTRY Version 2\n\n[Software\\\\Classes\\\\osu] 1634567890\n@=\"URL:osu! Protocol\"\n");
+
+        assert_eq!(read_registry_command(&registry), None);
+        assert_eq!(read_registry_command(&dir.path.join("system.reg")), None);
+    }
+
+    #[test]
+    fn windows_paths_map_into_the_prefix()
+    {
+        let dir = TempDir::new();
+        let prefix: PathBuf = dir.path.join("prefix");
+        fs::create_dir_all(prefix.join("drive_c")).unwrap();
+        fs::create_dir_all(prefix.join("dosdevices").join("d:")).unwrap();
+
+        //NOTE: Without a dosdevices entry, C: falls back to drive_c.
+        assert_eq!(
+            windows_to_prefix_path(&prefix, r"C:\users\me\AppData\Local\osu!"),
+            Some(prefix.join("drive_c").join("users").join("me").join("AppData").join("Local").join("osu!"))
+        );
+        assert_eq!(windows_to_prefix_path(&prefix, r"d:\Games\osu!"), Some(prefix.join("dosdevices").join("d:").join("Games").join("osu!")));
+        assert_eq!(windows_to_prefix_path(&prefix, "C:/osu!/"), Some(prefix.join("drive_c").join("osu!")));
+        assert_eq!(windows_to_prefix_path(&prefix, r"\\server\share"), None);
+        assert_eq!(windows_to_prefix_path(&prefix, "osu!"), None);
+    }
+
+    #[test]
+    fn host_paths_use_the_surrounding_prefix()
+    {
+        let dir = TempDir::new();
+        let prefix: PathBuf = dir.path.join("prefix");
+        let install_dir: PathBuf = prefix.join("drive_c").join("osu!");
+        write(&prefix.join("system.reg"), "WINE REGISTRY Version 2\n");
+        fs::create_dir_all(&install_dir).unwrap();
+
+        assert_eq!(host_path(&install_dir, r"C:\Songs"), Some(prefix.join("drive_c").join("Songs")));
+    }
+
+    #[test]
+    fn host_paths_outside_a_prefix_only_map_drive_z()
+    {
+        let dir = TempDir::new();
+
+        assert_eq!(host_path(&dir.path, r"Z:\home\me\Songs"), Some(PathBuf::from("/home/me/Songs")));
+        assert_eq!(host_path(&dir.path, r"C:\Songs"), None);
+    }
+}
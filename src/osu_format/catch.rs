@@ -0,0 +1,302 @@
+use super::data::{
+    CatchDifficultyAttributes,
+    OsuFile,
+    OsuFileCurvePoint,
+    OsuFileGamemode,
+    OsuFileHitObjectKind
+};
+use super::difficulty::{difficulty_range, strain_decay, weighted_strain_sum, StrainPeaks};
+
+const PLAYFIELD_WIDTH: f64 = 512.0;
+const CATCHER_BASE_SIZE: f64 = 106.75;
+const ALLOWED_CATCH_RANGE: f64 = 0.8;
+const BASE_DASH_SPEED: f64 = 1.0;
+
+const SECTION_LENGTH: f64 = 750.0;
+const DECAY_WEIGHT: f64 = 0.94;
+const STAR_SCALING_FACTOR: f64 = 0.153;
+
+const SKILL_MULTIPLIER: f64 = 900.0;
+const STRAIN_DECAY_BASE: f64 = 0.2;
+//NOTE: Positions are scaled as if every fruit had this radius, so circle size does not change the difficulty of a pattern.
+const NORMALISED_HITOBJECT_RADIUS: f64 = 41.0;
+const ABSOLUTE_PLAYER_POSITIONING_ERROR: f64 = 16.0;
+const DIRECTION_CHANGE_BONUS: f64 = 21.0;
+
+/// A fruit or droplet, the objects that have to be caught. Times are not scaled by the clock rate.
+#[derive(Default, Clone, Copy, Debug)]
+struct PalpableObject
+{
+    time: f64,
+    x: f64,
+    hyper_dash: bool,
+    /// How far the catcher could still walk after reaching the next object while dashing, 0 for hyper dashes.
+    distance_to_hyper_dash: f64
+}
+
+///
+/// General todo's for this file:
+/// - Hard rock moves fruits around with a seeded random offset, which isn't applied yet.
+///
+impl OsuFile
+{
+    /// The osu!catch star rating with the mods, osu!standard beatmaps are converted the way osu! converts them.
+    /// None for osu!taiko and osu!mania beatmaps, requires the general, difficulty, timing points and hit objects sections to be parsed.
+    pub fn catch_difficulty(&self, mods: u32) -> Option<CatchDifficultyAttributes>
+    {
+        let is_convert: bool = match self.general_section.mode
+        {
+            OsuFileGamemode::Catch => false,
+            OsuFileGamemode::Osu => true,
+            _ => return None
+        };
+
+        let difficulty = self.adjusted_difficulty(mods);
+        let mut objects: Vec<PalpableObject> = self.palpable_objects();
+
+        //NOTE: The catcher is a bit smaller than it looks, and even smaller for high circle sizes to account for imperfect play.
+        let catch_width: f64 = (1.0 - 0.7 * (difficulty.circle_size - 5.0) / 5.0) * CATCHER_BASE_SIZE * ALLOWED_CATCH_RANGE;
+        let half_catcher_width: f64 = catch_width / 2.0 * (1.0 - (difficulty.circle_size - 5.5).max(0.0) * 0.0625);
+
+        initialise_hyper_dashes(&mut objects, catch_width / 2.0 / ALLOWED_CATCH_RANGE);
+
+        let preempt: f64 = difficulty_range(difficulty.approach_rate, 1800.0, 1200.0, 450.0) / difficulty.clock_rate;
+
+        Some(CatchDifficultyAttributes
+        {
+            mods,
+            star_rating: movement_difficulty(&objects, half_catcher_width, difficulty.clock_rate).sqrt() * STAR_SCALING_FACTOR,
+            approach_rate: if preempt > 1200.0 { (1800.0 - preempt) / 120.0 } else { (1200.0 - preempt) / 150.0 + 5.0 },
+            max_combo: objects.len(),
+            is_convert
+        })
+    }
+
+    /// The combo of a full combo on an osu!catch beatmap, every fruit and droplet counts, banana showers don't.
+    pub fn catch_max_combo(&self) -> usize
+    {
+        self.palpable_objects().len()
+    }
+
+    /// Circles become fruits and sliders juice streams with a fruit on every end and a droplet on every tick.
+    /// Spinners become banana showers, which don't count for difficulty or combo.
+    fn palpable_objects(&self) -> Vec<PalpableObject>
+    {
+        let mut objects: Vec<PalpableObject> = Vec::new();
+        let fruit = |time: f64, x: f64| PalpableObject { time, x: x.clamp(0.0, PLAYFIELD_WIDTH), ..Default::default() };
+
+        for hit_object in self.hit_object_section.hit_objects.iter()
+        {
+            let start_time: f64 = hit_object.time as f64;
+
+            match (&hit_object.kind, self.slider_timing(hit_object))
+            {
+                (OsuFileHitObjectKind::Slider(slider), Some(timing)) =>
+                {
+                    let path = slider.path(OsuFileCurvePoint { x: hit_object.x as f32, y: hit_object.y as f32 });
+                    let spans: u32 = timing.repeat_times.len() as u32 + 1;
+
+                    objects.push(fruit(start_time, hit_object.x as f64));
+
+                    for span in 0..spans
+                    {
+                        objects.extend(timing.ticks
+                            .iter()
+                            .filter(|tick| tick.span == span)
+                            .map(|tick| fruit(tick.time, tick.position.x as f64)));
+
+                        if let Some(repeat_time) = timing.repeat_times.get(span as usize)
+                        {
+                            objects.push(fruit(*repeat_time, path.position_at(if span % 2 == 0 { 1.0 } else { 0.0 }).x as f64));
+                        }
+                    }
+
+                    objects.push(fruit(timing.end_time, timing.end_position.x as f64));
+                },
+                (OsuFileHitObjectKind::Spinner { .. }, _) => { },
+                _ => objects.push(fruit(start_time, hit_object.x as f64))
+            }
+        }
+
+        objects.sort_by(|a, b| a.time.total_cmp(&b.time));
+        objects
+    }
+}
+
+/// Marks the objects the catcher can only reach with a hyper dash, and how close the others are to needing one.
+/// Like osu! this uses the full catcher width.
+fn initialise_hyper_dashes(objects: &mut [PalpableObject], half_catcher_width: f64)
+{
+    let mut last_direction: i32 = 0;
+    let mut last_excess: f64 = half_catcher_width;
+
+    for index in 0..objects.len().saturating_sub(1)
+    {
+        let current: PalpableObject = objects[index];
+        let next: PalpableObject = objects[index + 1];
+
+        let direction: i32 = if next.x > current.x { 1 } else { -1 };
+
+        //NOTE: A quarter of a frame of grace time, which osu! has as well.
+        let time_to_next: f64 = next.time - current.time - 1000.0 / 60.0 / 4.0;
+        let distance_to_next: f64 = (next.x - current.x).abs() - if last_direction == direction { last_excess } else { half_catcher_width };
+        let distance_to_hyper_dash: f64 = time_to_next * BASE_DASH_SPEED - distance_to_next;
+
+        if distance_to_hyper_dash < 0.0
+        {
+            objects[index].hyper_dash = true;
+            objects[index].distance_to_hyper_dash = 0.0;
+            last_excess = half_catcher_width;
+        }
+        else
+        {
+            objects[index].hyper_dash = false;
+            objects[index].distance_to_hyper_dash = distance_to_hyper_dash;
+            last_excess = distance_to_hyper_dash.clamp(0.0, half_catcher_width);
+        }
+
+        last_direction = direction;
+    }
+}
+
+/// How hard it is to move the catcher from object to object, with bonuses for changing direction and for edge dashes.
+fn movement_difficulty(objects: &[PalpableObject], half_catcher_width: f64, clock_rate: f64) -> f64
+{
+    let mut peaks = StrainPeaks::new(SECTION_LENGTH);
+    let mut current_strain: f64 = 0.0;
+
+    let scaling_factor: f64 = NORMALISED_HITOBJECT_RADIUS / half_catcher_width;
+    let mut last_player_position: Option<f64> = None;
+    let mut last_distance_moved: f64 = 0.0;
+    let mut last_strain_time: f64 = 0.0;
+
+    for index in 1..objects.len()
+    {
+        let current: PalpableObject = objects[index];
+        let last: PalpableObject = objects[index - 1];
+
+        let start_time: f64 = current.time / clock_rate;
+        let previous_start_time: f64 = last.time / clock_rate;
+        let delta_time: f64 = start_time - previous_start_time;
+
+        //NOTE: Every interval is capped at the equivalent of 375 BPM streams.
+        let strain_time: f64 = delta_time.max(40.0);
+        let position: f64 = current.x * scaling_factor;
+
+        peaks.start_sections_until(start_time, |time| current_strain * strain_decay(STRAIN_DECAY_BASE, time - previous_start_time));
+
+        let previous_position: f64 = last_player_position.unwrap_or(last.x * scaling_factor);
+        let reach: f64 = NORMALISED_HITOBJECT_RADIUS - ABSOLUTE_PLAYER_POSITIONING_ERROR;
+        let mut player_position: f64 = previous_position.clamp(position - reach, position + reach);
+        let distance_moved: f64 = player_position - previous_position;
+
+        //NOTE: The speed mods change the speed of the catcher as well, not only the time between objects.
+        let weighted_strain_time: f64 = strain_time + 13.0 + 3.0 / clock_rate;
+        let mut distance_addition: f64 = distance_moved.abs().powf(1.3) / 510.0;
+
+        if distance_moved.abs() > 0.1
+        {
+            if last_distance_moved.abs() > 0.1 && distance_moved.signum() != last_distance_moved.signum()
+            {
+                let bonus_factor: f64 = distance_moved.abs().min(50.0) / 50.0;
+                let antiflow_factor: f64 = (last_distance_moved.abs().min(70.0) / 70.0).max(0.38);
+
+                distance_addition += DIRECTION_CHANGE_BONUS / (last_strain_time + 16.0).sqrt() * bonus_factor * antiflow_factor
+                    * (1.0 - (weighted_strain_time / 1000.0).powi(3)).max(0.0);
+            }
+
+            //NOTE: A base bonus for every movement, giving some weight to streams.
+            distance_addition += 12.5 * distance_moved.abs().min(NORMALISED_HITOBJECT_RADIUS * 2.0) / (NORMALISED_HITOBJECT_RADIUS * 6.0) / weighted_strain_time.sqrt();
+        }
+
+        //NOTE: Edge dashes are barely reachable without a hyper dash, after a hyper dash the catcher is always in the right place.
+        if last.distance_to_hyper_dash <= 20.0
+        {
+            let mut edge_dash_bonus: f64 = 0.0;
+
+            if !last.hyper_dash
+            {
+                edge_dash_bonus += 5.7;
+            }
+            else
+            {
+                player_position = position;
+            }
+
+            distance_addition *= 1.0 + edge_dash_bonus * ((20.0 - last.distance_to_hyper_dash) / 20.0)
+                * ((strain_time * clock_rate).min(265.0) / 265.0).powf(1.5);
+        }
+
+        last_player_position = Some(player_position);
+        last_distance_moved = distance_moved;
+        last_strain_time = strain_time;
+
+        current_strain *= strain_decay(STRAIN_DECAY_BASE, delta_time);
+        current_strain += distance_addition / weighted_strain_time * SKILL_MULTIPLIER;
+        peaks.add(current_strain);
+    }
+
+    weighted_strain_sum(peaks.peaks(), DECAY_WEIGHT)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{initialise_hyper_dashes, PalpableObject};
+    use crate::osu_format::data::{OsuFile, MOD_DOUBLE_TIME};
+    use crate::osu_format::test_support::{self, TIMING_POINTS};
+
+    fn beatmap(mode: u8, hit_objects: &str) -> OsuFile
+    {
+        test_support::beatmap(&[
+            ("General", &format!("Mode: {}", mode)),
+            ("Difficulty", "CircleSize:4\nApproachRate:9\nSliderMultiplier:1\nSliderTickRate:1"),
+            ("TimingPoints", TIMING_POINTS),
+            ("HitObjects", hit_objects)
+        ])
+    }
+
+    #[test]
+    fn fruits_and_droplets_give_combo()
+    {
+        //NOTE: The slider is two beats long, so it has a fruit on both ends and a droplet on the beat in between. The spinner gives nothing.
+        let osu_file = beatmap(2, "256,192,500,1,0\n0,192,1000,2,0,L|200:192,1,200\n256,192,3000,12,0,4000\n");
+        let times: Vec<f64> = osu_file.palpable_objects().iter().map(|object| object.time).collect();
+
+        assert_eq!(times, vec![500.0, 1000.0, 1500.0, 2000.0]);
+        assert_eq!(osu_file.catch_difficulty(0).unwrap().max_combo, 4);
+    }
+
+    #[test]
+    fn fruits_out_of_reach_need_a_hyper_dash()
+    {
+        let fruit = |time: f64, x: f64| PalpableObject { time, x, ..Default::default() };
+        let mut objects: Vec<PalpableObject> = vec![fruit(0.0, 0.0), fruit(100.0, 512.0), fruit(1100.0, 400.0)];
+
+        initialise_hyper_dashes(&mut objects, 50.0);
+
+        assert!(objects[0].hyper_dash);
+        assert_eq!(objects[0].distance_to_hyper_dash, 0.0);
+        assert!(!objects[1].hyper_dash);
+        assert!(objects[1].distance_to_hyper_dash > 0.0);
+        assert!(!objects[2].hyper_dash);
+    }
+
+    #[test]
+    fn the_approach_rate_follows_the_clock_rate()
+    {
+        let osu_file = beatmap(2, "256,192,500,1,0\n");
+
+        assert!((osu_file.catch_difficulty(0).unwrap().approach_rate - 9.0).abs() < 1e-9);
+        assert!((osu_file.catch_difficulty(MOD_DOUBLE_TIME).unwrap().approach_rate - 10.33).abs() < 0.01);
+    }
+
+    #[test]
+    fn only_standard_beatmaps_are_converted()
+    {
+        assert!(beatmap(0, "256,192,500,1,0\n").catch_difficulty(0).unwrap().is_convert);
+        assert!(!beatmap(2, "256,192,500,1,0\n").catch_difficulty(0).unwrap().is_convert);
+        assert!(beatmap(1, "256,192,500,1,0\n").catch_difficulty(0).is_none());
+        assert!(beatmap(3, "256,192,500,1,0\n").catch_difficulty(0).is_none());
+    }
+}
@@ -1,33 +1,25 @@
+use std::fmt;
 use std::str::FromStr;
 use half::{ f16 };
+use encoding_rs::{Encoding, UTF_8};
+
+use super::error::{ParseDiagnostic, ParseError, parse_field};
 
 ///
 /// General todos, fixes and pain points for this file:
 ///  - Support deprecated variables as still some maps use them, i.e. StoryInFrontFire
-///  - Support outputting an OsuFile into an .osu file.
-///  - OsuFileColour::from_str could fail if supplied something like "not,a,color".
-///  - Specialize HitObject's params field into something csv-ish.
-///  - Specialize HitObject's hit_sample field into it's own struct.
 /// 
-
-#[repr(u32)] #[derive(Clone, Debug)]
+#[repr(u32)] #[derive(Clone, Debug, Default, PartialEq)]
 pub enum OsuFileGamemode
 {
     Unknown = u32::MAX,
+    #[default]
     Osu = 0,
     Taiko = 1,
     Catch = 2,
     Mania = 3
 }
 
-impl Default for OsuFileGamemode
-{
-    fn default() -> Self 
-    { 
-        OsuFileGamemode::Osu 
-    }
-}
-
 impl OsuFileGamemode
 {
     pub fn from_u32(integer: u32) -> OsuFileGamemode
@@ -43,10 +35,45 @@ impl OsuFileGamemode
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl fmt::Display for OsuFileGamemode
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        let name = match self
+        {
+            OsuFileGamemode::Unknown => "unknown",
+            OsuFileGamemode::Osu => "osu",
+            OsuFileGamemode::Taiko => "taiko",
+            OsuFileGamemode::Catch => "catch",
+            OsuFileGamemode::Mania => "mania"
+        };
+
+        write!(formatter, "{}", name)
+    }
+}
+
+impl FromStr for OsuFileGamemode
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileGamemode, Self::Err>
+    {
+        match input.trim().to_ascii_lowercase().as_str()
+        {
+            "osu" | "standard" | "std" | "0" => Ok(OsuFileGamemode::Osu),
+            "taiko" | "1" => Ok(OsuFileGamemode::Taiko),
+            "catch" | "ctb" | "fruits" | "2" => Ok(OsuFileGamemode::Catch),
+            "mania" | "3" => Ok(OsuFileGamemode::Mania),
+            _ => Err(format!("Cannot convert {} to an OsuFileGamemode enum.", input))
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum OsuFileSampleSet
 {
     Default = 0,
+    #[default]
     Normal = 1,
     Soft = 2,
     Drum = 3
@@ -67,14 +94,6 @@ impl OsuFileSampleSet
     }
 }
 
-impl Default for OsuFileSampleSet 
-{
-    fn default() -> Self 
-    { 
-        OsuFileSampleSet::Normal 
-    }
-}
-
 impl FromStr for OsuFileSampleSet
 {
     type Err = String;
@@ -92,9 +111,26 @@ impl FromStr for OsuFileSampleSet
     }
 }
 
-#[derive(Clone, Debug)]
+impl fmt::Display for OsuFileSampleSet
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        //NOTE: Osu! falls back to the normal sample set for one it does not know.
+        let name = match self
+        {
+            OsuFileSampleSet::Default | OsuFileSampleSet::Normal => "Normal",
+            OsuFileSampleSet::Soft => "Soft",
+            OsuFileSampleSet::Drum => "Drum"
+        };
+
+        write!(formatter, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum OsuFileOverlayPosition
 {
+    #[default]
     NoChange, 
     Below, 
     Above
@@ -116,11 +152,12 @@ impl FromStr for OsuFileOverlayPosition
     }
 }
 
-impl Default for OsuFileOverlayPosition
+impl fmt::Display for OsuFileOverlayPosition
 {
-    fn default() -> Self 
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
     {
-        OsuFileOverlayPosition::NoChange 
+        //NOTE: The variant names are the names osu! writes.
+        write!(formatter, "{:?}", self)
     }
 }
 
@@ -153,7 +190,8 @@ pub struct OsuFileEditorBookmarks
 
 impl FromStr for OsuFileEditorBookmarks
 {
-    type Err = ();
+    type Err = String;
+
     fn from_str(string: &str) -> Result<Self, Self::Err> 
     {
         let mut values: Vec<i64> = Vec::new();
@@ -191,7 +229,7 @@ pub struct OsuFileMetadataTags
 
 impl FromStr for OsuFileMetadataTags
 {
-    type Err = ();
+    type Err = String;
 
     fn from_str(string: &str) -> Result<Self, Self::Err> 
     {
@@ -265,6 +303,279 @@ pub struct OsuFileEvents
     pub background: OsuFileBackground,
     pub video: OsuFileVideo,
     pub breaks: Vec<OsuFileBreakPeriod>,
+    pub storyboard: OsuFileStoryboard,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OsuFileStoryboardLayer
+{
+    #[default]
+    Background = 0,
+    Fail = 1,
+    Pass = 2,
+    Foreground = 3,
+    Overlay = 4
+}
+
+impl FromStr for OsuFileStoryboardLayer
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileStoryboardLayer, Self::Err>
+    {
+        match input.to_ascii_lowercase().as_str() 
+        {
+            "background" | "0" => Ok(OsuFileStoryboardLayer::Background),
+            "fail" | "1" => Ok(OsuFileStoryboardLayer::Fail),
+            "pass" | "2" => Ok(OsuFileStoryboardLayer::Pass),
+            "foreground" | "3" => Ok(OsuFileStoryboardLayer::Foreground),
+            "overlay" | "4" => Ok(OsuFileStoryboardLayer::Overlay),
+            _ => Err(format!("Cannot convert {} to an OsuFileStoryboardLayer enum.", input)),
+        }
+    }
+}
+
+impl fmt::Display for OsuFileStoryboardLayer
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{:?}", self)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OsuFileStoryboardOrigin
+{
+    #[default]
+    TopLeft = 0,
+    Centre = 1,
+    CentreLeft = 2,
+    TopRight = 3,
+    BottomCentre = 4,
+    TopCentre = 5,
+    Custom = 6,
+    CentreRight = 7,
+    BottomLeft = 8,
+    BottomRight = 9
+}
+
+impl FromStr for OsuFileStoryboardOrigin
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileStoryboardOrigin, Self::Err>
+    {
+        match input.to_ascii_lowercase().as_str() 
+        {
+            "topleft" | "0" => Ok(OsuFileStoryboardOrigin::TopLeft),
+            "centre" | "1" => Ok(OsuFileStoryboardOrigin::Centre),
+            "centreleft" | "2" => Ok(OsuFileStoryboardOrigin::CentreLeft),
+            "topright" | "3" => Ok(OsuFileStoryboardOrigin::TopRight),
+            "bottomcentre" | "4" => Ok(OsuFileStoryboardOrigin::BottomCentre),
+            "topcentre" | "5" => Ok(OsuFileStoryboardOrigin::TopCentre),
+            "custom" | "6" => Ok(OsuFileStoryboardOrigin::Custom),
+            "centreright" | "7" => Ok(OsuFileStoryboardOrigin::CentreRight),
+            "bottomleft" | "8" => Ok(OsuFileStoryboardOrigin::BottomLeft),
+            "bottomright" | "9" => Ok(OsuFileStoryboardOrigin::BottomRight),
+            _ => Err(format!("Cannot convert {} to an OsuFileStoryboardOrigin enum.", input)),
+        }
+    }
+}
+
+impl fmt::Display for OsuFileStoryboardOrigin
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{:?}", self)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OsuFileStoryboardLoopType
+{
+    #[default]
+    LoopForever = 0,
+    LoopOnce = 1
+}
+
+impl FromStr for OsuFileStoryboardLoopType
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileStoryboardLoopType, Self::Err>
+    {
+        match input.to_ascii_lowercase().as_str() 
+        {
+            "loopforever" | "0" => Ok(OsuFileStoryboardLoopType::LoopForever),
+            "looponce" | "1" => Ok(OsuFileStoryboardLoopType::LoopOnce),
+            _ => Err(format!("Cannot convert {} to an OsuFileStoryboardLoopType enum.", input)),
+        }
+    }
+}
+
+impl fmt::Display for OsuFileStoryboardLoopType
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{:?}", self)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OsuFileStoryboardParameter
+{
+    FlipHorizontal,
+    FlipVertical,
+    AdditiveBlend
+}
+
+impl FromStr for OsuFileStoryboardParameter
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileStoryboardParameter, Self::Err>
+    {
+        match input
+        {
+            "H" => Ok(OsuFileStoryboardParameter::FlipHorizontal),
+            "V" => Ok(OsuFileStoryboardParameter::FlipVertical),
+            "A" => Ok(OsuFileStoryboardParameter::AdditiveBlend),
+            _ => Err(format!("Cannot convert {} to an OsuFileStoryboardParameter enum.", input)),
+        }
+    }
+}
+
+impl fmt::Display for OsuFileStoryboardParameter
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        let name = match self
+        {
+            OsuFileStoryboardParameter::FlipHorizontal => "H",
+            OsuFileStoryboardParameter::FlipVertical => "V",
+            OsuFileStoryboardParameter::AdditiveBlend => "A"
+        };
+
+        write!(formatter, "{}", name)
+    }
+}
+
+/// A single transformation from start_time to end_time, values hold every value of the command
+/// in order, e.g. "start_x, start_y, end_x, end_y" for a move. Chained shorthand values are kept as is.
+#[derive(Default, Clone, Debug)]
+pub struct OsuFileStoryboardTween
+{
+    pub easing: u8,
+    pub start_time: i32,
+    pub end_time: i32,
+    pub values: Vec<f32>
+}
+
+#[derive(Clone, Debug)]
+pub enum OsuFileStoryboardCommand
+{
+    Fade(OsuFileStoryboardTween),
+    Move(OsuFileStoryboardTween),
+    MoveX(OsuFileStoryboardTween),
+    MoveY(OsuFileStoryboardTween),
+    Scale(OsuFileStoryboardTween),
+    VectorScale(OsuFileStoryboardTween),
+    Rotate(OsuFileStoryboardTween),
+    Colour(OsuFileStoryboardTween),
+    Parameter 
+    { 
+        easing: u8, 
+        start_time: i32, 
+        end_time: i32, 
+        parameter: OsuFileStoryboardParameter 
+    },
+    Loop 
+    { 
+        start_time: i32, 
+        loop_count: u32, 
+        commands: Vec<OsuFileStoryboardCommand> 
+    },
+    Trigger 
+    { 
+        trigger: String, 
+        start_time: i32, 
+        end_time: i32, 
+        group_number: i32, 
+        commands: Vec<OsuFileStoryboardCommand> 
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct OsuFileStoryboardSprite
+{
+    pub layer: OsuFileStoryboardLayer,
+    pub origin: OsuFileStoryboardOrigin,
+    pub file_name: String,
+    pub x: f32,
+    pub y: f32,
+    pub commands: Vec<OsuFileStoryboardCommand>
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct OsuFileStoryboardAnimation
+{
+    pub layer: OsuFileStoryboardLayer,
+    pub origin: OsuFileStoryboardOrigin,
+    pub file_name: String,
+    pub x: f32,
+    pub y: f32,
+    pub frame_count: u32,
+    pub frame_delay: f32,
+    pub loop_type: OsuFileStoryboardLoopType,
+    pub commands: Vec<OsuFileStoryboardCommand>
+}
+
+impl OsuFileStoryboardAnimation
+{
+    /// The animation's file name with the frame index inserted before the extension, 
+    /// "sb/star.png" with 2 frames becomes "sb/star0.png" and "sb/star1.png".
+    /// The frame count comes straight from the file, so at most MAX_ANIMATION_FRAMES names are returned.
+    pub fn frame_file_names(&self) -> Vec<String>
+    {
+        let (stem, extension) = match self.file_name.rfind('.')
+        {
+            Some(dot) => self.file_name.split_at(dot),
+            None => (self.file_name.as_str(), "")
+        };
+
+        (0..self.frame_count.min(MAX_ANIMATION_FRAMES))
+            .map(|frame| format!("{}{}{}", stem, frame, extension))
+            .collect()
+    }
+}
+
+//NOTE: Osu! has no limit of its own, but a file claiming billions of frames would otherwise allocate a name for each of them.
+//      Real animations stay in the hundreds, this leaves plenty of room.
+pub const MAX_ANIMATION_FRAMES: u32 = 10_000;
+
+#[derive(Default, Clone, Debug)]
+pub struct OsuFileStoryboardSample
+{
+    pub time: i32,
+    pub layer: OsuFileStoryboardLayer,
+    pub file_name: String,
+    pub volume: u32
+}
+
+#[derive(Clone, Debug)]
+pub enum OsuFileStoryboardElement
+{
+    Sprite(OsuFileStoryboardSprite),
+    Animation(OsuFileStoryboardAnimation),
+    Sample(OsuFileStoryboardSample)
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct OsuFileStoryboard
+{
+    /// Variables from the [Variables] section, substituted into every event line.
+    pub variables: Vec<(String, String)>,
+    pub elements: Vec<OsuFileStoryboardElement>
 }
 
 #[derive(Default, Clone, Debug)]
@@ -277,9 +588,39 @@ pub struct OsuFileTimingPoint
     pub sample_index: i32,
     pub volume: i32,
     pub uninherited: bool,
+    /// The raw effect bits, see is_kiai and omits_first_barline.
     pub effects: i32,
 }
 
+pub const EFFECT_KIAI: i32 = 1 << 0;
+pub const EFFECT_OMIT_FIRST_BARLINE: i32 = 1 << 3;
+
+impl OsuFileTimingPoint
+{
+    pub fn is_kiai(&self) -> bool
+    {
+        self.effects & EFFECT_KIAI != 0
+    }
+
+    /// Whether the first barline of an uninherited timing point is hidden, mostly used in osu!taiko and osu!mania.
+    pub fn omits_first_barline(&self) -> bool
+    {
+        self.effects & EFFECT_OMIT_FIRST_BARLINE != 0
+    }
+
+    /// The slider velocity multiplier of an inherited timing point, stored as a negative inverse percentage.
+    /// Uninherited timing points reset the multiplier to 1.
+    pub fn slider_velocity(&self) -> f64
+    {
+        if self.uninherited || self.beat_length >= 0.0
+        {
+            return 1.0;
+        }
+
+        (-100.0 / self.beat_length as f64).clamp(0.1, 10.0)
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct OsuFileTimingPoints 
 {
@@ -290,8 +631,8 @@ pub struct OsuFileTimingPoints
 pub struct OsuFileColors 
 {
     pub combo_colors: Vec<OsuFileCombo>,
-    pub slider_border: OsuFileColor,
-    pub slider_track_override: OsuFileColor,
+    pub slider_border: Option<OsuFileColor>,
+    pub slider_track_override: Option<OsuFileColor>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -309,44 +650,430 @@ pub struct OsuFileColor
     pub green: u8
 }
 
-impl OsuFileColor
+impl FromStr for OsuFileColor
 {
-    pub fn from_str(input: String) -> Result<OsuFileColor, String>
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileColor, Self::Err>
     {
         let rgb: Vec<&str> = input
             .split(",")
             .collect();
 
-        if rgb.len() != 3 
+        //NOTE: Some beatmaps carry a fourth alpha component, osu! ignores it.
+        if rgb.len() != 3 && rgb.len() != 4
         {
             return Err(String::from("Given string does not represent a color."))
         }
 
+        let as_u8 = |component: &str| -> Result<u8, String> 
+        { 
+            component.trim().parse::<u8>().map_err(|_| format!("{} is not a colour component.", component)) 
+        };
+
         let colour = OsuFileColor 
         { 
-            red: rgb[0].parse::<u8>().unwrap(), 
-            green: rgb[1].parse::<u8>().unwrap(), 
-            blue: rgb[2].parse::<u8>().unwrap()
+            red: as_u8(rgb[0])?, 
+            green: as_u8(rgb[1])?, 
+            blue: as_u8(rgb[2])?
         };
 
         Ok(colour)
     }
 }
 
+impl fmt::Display for OsuFileColor
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{},{},{}", self.red, self.green, self.blue)
+    }
+}
+
+pub const HIT_TYPE_CIRCLE: u8 = 1 << 0;
+pub const HIT_TYPE_SLIDER: u8 = 1 << 1;
+pub const HIT_TYPE_NEW_COMBO: u8 = 1 << 2;
+pub const HIT_TYPE_SPINNER: u8 = 1 << 3;
+pub const HIT_TYPE_COMBO_SKIP: u8 = 0b0111_0000;
+pub const HIT_TYPE_HOLD: u8 = 1 << 7;
+
+pub const HIT_SOUND_WHISTLE: u8 = 1 << 1;
+pub const HIT_SOUND_FINISH: u8 = 1 << 2;
+pub const HIT_SOUND_CLAP: u8 = 1 << 3;
+
+//NOTE: osu!lazer refuses sliders with a higher repeat count ("Repeat count is way too high" in ConvertHitObjectParser).
+//      We clamp to it instead, so a corrupt slider still keeps its files, see OsuFileSlider::span_count.
+pub const MAX_SLIDER_SLIDES: u32 = 9000;
+
+/// The "normalSet:additionSet:index:volume:filename" at the end of a hit object, every part is optional.
+/// Zero means the value comes from the timing point, a file name replaces the normal and addition sounds.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct OsuFileHitSample
+{
+    pub normal_set: u32,
+    pub addition_set: u32,
+    pub index: i32,
+    pub volume: u32,
+    pub file_name: String
+}
+
+impl FromStr for OsuFileHitSample
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileHitSample, Self::Err>
+    {
+        let parts: Vec<&str> = input.split(':').collect();
+
+        fn part<T: FromStr + Default>(parts: &[&str], index: usize) -> Result<T, String>
+        {
+            match parts.get(index).map(|part| part.trim()).filter(|part| !part.is_empty())
+            {
+                Some(part) => part.parse::<T>().map_err(|_| format!("{} is not a valid hit sample value.", part)),
+                None => Ok(T::default())
+            }
+        }
+
+        Ok(OsuFileHitSample
+        {
+            normal_set: part(&parts, 0)?,
+            addition_set: part(&parts, 1)?,
+            index: part(&parts, 2)?,
+            volume: part(&parts, 3)?,
+            file_name: parts.get(4).map(|part| part.trim().to_owned()).unwrap_or_default()
+        })
+    }
+}
+
+impl fmt::Display for OsuFileHitSample
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{}:{}:{}:{}:{}", self.normal_set, self.addition_set, self.index, self.volume, self.file_name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OsuFileCurveType
+{
+    #[default]
+    Bezier,
+    Catmull,
+    Linear,
+    PerfectCircle
+}
+
+impl FromStr for OsuFileCurveType
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileCurveType, Self::Err>
+    {
+        match input
+        {
+            "B" => Ok(OsuFileCurveType::Bezier),
+            "C" => Ok(OsuFileCurveType::Catmull),
+            "L" => Ok(OsuFileCurveType::Linear),
+            "P" => Ok(OsuFileCurveType::PerfectCircle),
+            _ => Err(format!("Cannot convert {} to an OsuFileCurveType enum.", input))
+        }
+    }
+}
+
+impl fmt::Display for OsuFileCurveType
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        let name = match self
+        {
+            OsuFileCurveType::Bezier => "B",
+            OsuFileCurveType::Catmull => "C",
+            OsuFileCurveType::Linear => "L",
+            OsuFileCurveType::PerfectCircle => "P"
+        };
+
+        write!(formatter, "{}", name)
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct OsuFileCurvePoint
+{
+    pub x: f32,
+    pub y: f32
+}
+
+impl FromStr for OsuFileCurvePoint
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileCurvePoint, Self::Err>
+    {
+        let (x, y) = input.split_once(':').ok_or_else(|| format!("{} is not a curve point.", input))?;
+        let as_f32 = |value: &str| -> Result<f32, String> { value.trim().parse::<f32>().map_err(|_| format!("{} is not a curve point.", input)) };
+
+        Ok(OsuFileCurvePoint { x: as_f32(x)?, y: as_f32(y)? })
+    }
+}
+
+impl fmt::Display for OsuFileCurvePoint
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{}:{}", self.x, self.y)
+    }
+}
+
+/// The "normalSet:additionSet" of a single slider edge.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct OsuFileEdgeSet
+{
+    pub normal_set: u32,
+    pub addition_set: u32
+}
+
+impl FromStr for OsuFileEdgeSet
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<OsuFileEdgeSet, Self::Err>
+    {
+        let (normal_set, addition_set) = input.split_once(':').unwrap_or((input, "0"));
+        let as_u32 = |value: &str| -> Result<u32, String> { value.trim().parse::<u32>().map_err(|_| format!("{} is not an edge set.", input)) };
+
+        Ok(OsuFileEdgeSet { normal_set: as_u32(normal_set)?, addition_set: as_u32(addition_set)? })
+    }
+}
+
+impl fmt::Display for OsuFileEdgeSet
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{}:{}", self.normal_set, self.addition_set)
+    }
+}
+
+/// "curveType|curvePoints,slides,length,edgeSounds,edgeSets", the edges are optional.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct OsuFileSlider
+{
+    pub curve_type: OsuFileCurveType,
+    /// The control points after the head of the slider, which is the position of the hit object.
+    pub curve_points: Vec<OsuFileCurvePoint>,
+    pub slides: u32,
+    /// Visual length in osu! pixels.
+    pub length: f64,
+    /// One hit sound per edge, the head, every repeat and the tail.
+    pub edge_sounds: Vec<u8>,
+    pub edge_sets: Vec<OsuFileEdgeSet>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OsuFileHitObjectKind
+{
+    #[default]
+    Circle,
+    Slider(OsuFileSlider),
+    Spinner { end_time: i32 },
+    Hold { end_time: i32 }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct OsuFileHitObject
 {
     pub x: i32,
     pub y: i32,
     pub time: i32,
+    /// The raw type, kind holds the decoded object type.
     pub hit_type: u8,
     pub hit_sound: u8,
-    pub params: String, 
-    pub hit_sample: String,
+    pub kind: OsuFileHitObjectKind,
+    /// None when the hit object does not end with a hit sample.
+    pub hit_sample: Option<OsuFileHitSample>
+}
+
+impl OsuFileHitObject
+{
+    pub fn is_new_combo(&self) -> bool
+    {
+        self.hit_type & HIT_TYPE_NEW_COMBO != 0
+    }
+
+    /// How many combo colours to skip when starting a new combo.
+    pub fn combo_colour_skip(&self) -> u8
+    {
+        (self.hit_type & HIT_TYPE_COMBO_SKIP) >> 4
+    }
+
+    /// The time spinners and holds end, the start time for circles.
+    /// Sliders need the timing points to know their end, so this is their start time as well.
+    pub fn end_time(&self) -> i32
+    {
+        match self.kind
+        {
+            OsuFileHitObjectKind::Spinner { end_time } | OsuFileHitObjectKind::Hold { end_time } => end_time,
+            _ => self.time
+        }
+    }
+}
+
+/// The approximated curve of a slider, fitted to the length the beatmap declares.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct OsuFileSliderPath
+{
+    pub points: Vec<OsuFileCurvePoint>,
+    /// The distance along the path up to every point, the last one is the length of the slider.
+    pub cumulative_length: Vec<f64>
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct OsuFileSliderTick
+{
+    /// The span the tick is in, starting at 0 for the first pass from head to tail.
+    pub span: u32,
+    pub time: f64,
+    pub position: OsuFileCurvePoint
+}
+
+/// Everything about a slider that depends on the timing points and difficulty, times are in milliseconds.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct OsuFileSliderTiming
+{
+    /// Osu! pixels per millisecond.
+    pub velocity: f64,
+    /// The duration of a single pass from head to tail.
+    pub span_duration: f64,
+    pub end_time: f64,
+    pub end_position: OsuFileCurvePoint,
+    pub ticks: Vec<OsuFileSliderTick>,
+    pub repeat_times: Vec<f64>,
+    /// Osu! judges the tail 36ms early, at this time.
+    pub legacy_last_tick_time: f64
+}
+
+/// The statistics osu! shows for a difficulty in song select, times are in milliseconds.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct BeatmapStats
+{
+    /// From the start of the audio to the end of the last hit object.
+    pub total_length: f64,
+    /// From the first to the end of the last hit object, without the breaks.
+    pub drain_time: f64,
+    pub min_bpm: f64,
+    pub max_bpm: f64,
+    /// The BPM most of the beatmap is in.
+    pub dominant_bpm: f64,
+    pub circle_count: usize,
+    pub slider_count: usize,
+    pub spinner_count: usize,
+    pub hold_count: usize,
+    /// The combo of a full combo, counted the way the gamemode of the beatmap counts it.
+    pub max_combo: usize
+}
+
+//NOTE: The bits osu! uses for the mods in replays, scores and its API.
+pub const MOD_NO_FAIL: u32 = 1 << 0;
+pub const MOD_EASY: u32 = 1 << 1;
+pub const MOD_HIDDEN: u32 = 1 << 3;
+pub const MOD_HARD_ROCK: u32 = 1 << 4;
+pub const MOD_DOUBLE_TIME: u32 = 1 << 6;
+pub const MOD_HALF_TIME: u32 = 1 << 8;
+pub const MOD_NIGHTCORE: u32 = 1 << 9;
+pub const MOD_FLASHLIGHT: u32 = 1 << 10;
+pub const MOD_SPUN_OUT: u32 = 1 << 12;
+
+/// The difficulty settings with the mods applied, the speed mods only change the clock rate.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct AdjustedDifficulty
+{
+    pub hp_drain_rate: f64,
+    pub circle_size: f64,
+    pub overall_difficulty: f64,
+    pub approach_rate: f64,
+    pub clock_rate: f64
+}
+
+/// The difficulty of an osu!standard beatmap with a set of mods.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct StandardDifficultyAttributes
+{
+    pub mods: u32,
+    pub star_rating: f64,
+    pub aim_difficulty: f64,
+    pub speed_difficulty: f64,
+    /// Zero without the flashlight mod.
+    pub flashlight_difficulty: f64,
+    /// The aim difficulty without the slider bodies relative to the one with them, 1 for beatmaps without sliders.
+    pub slider_factor: f64,
+    /// How many notes are hard enough to count for speed.
+    pub speed_note_count: f64,
+    /// The approach rate and overall difficulty after mods, double time and half time included.
+    pub approach_rate: f64,
+    pub overall_difficulty: f64,
+    pub drain_rate: f64,
+    pub max_combo: usize,
+    pub circle_count: usize,
+    pub slider_count: usize,
+    pub spinner_count: usize
+}
+
+/// The performance points a score on an osu!standard beatmap is worth, split up in what they are made of.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct StandardPerformanceAttributes
+{
+    pub pp: f64,
+    pub aim: f64,
+    pub speed: f64,
+    pub accuracy: f64,
+    pub flashlight: f64,
+    /// The misses plus the slider breaks guessed from the combo.
+    pub effective_miss_count: f64
+}
+
+/// The difficulty of an osu!taiko beatmap or an osu!standard beatmap converted to it.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct TaikoDifficultyAttributes
+{
+    pub mods: u32,
+    pub star_rating: f64,
+    pub stamina_difficulty: f64,
+    pub rhythm_difficulty: f64,
+    pub colour_difficulty: f64,
+    /// The three skills combined per section, before the star rating is rescaled from it.
+    pub peak_difficulty: f64,
+    /// The hit window of a great in milliseconds, after mods.
+    pub great_hit_window: f64,
+    pub max_combo: usize,
+    pub is_convert: bool
+}
+
+/// The difficulty of an osu!catch beatmap or an osu!standard beatmap converted to it.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct CatchDifficultyAttributes
+{
+    pub mods: u32,
+    pub star_rating: f64,
+    /// The approach rate after mods, double time and half time included.
+    pub approach_rate: f64,
+    /// Fruits and droplets, the tiny droplets and bananas don't give combo.
+    pub max_combo: usize,
+    pub is_convert: bool
+}
+
+/// The difficulty of an osu!mania beatmap.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct ManiaDifficultyAttributes
+{
+    pub mods: u32,
+    pub star_rating: f64,
+    /// The hit window of a great in milliseconds, after mods.
+    pub great_hit_window: f64,
+    pub key_count: usize,
+    pub max_combo: usize
 }
 
 #[derive(Default, Clone, Debug)]
-pub struct OsuFileHitObjects  
+pub struct OsuFileHitObjects
 {   
     pub hit_objects: Vec<OsuFileHitObject>
 }
@@ -363,17 +1090,76 @@ pub struct OsuFile
     pub events_section: OsuFileEvents,
     pub timing_points_section: OsuFileTimingPoints,
     pub colours_section: OsuFileColors,
-    pub hit_object_section: OsuFileHitObjects
+    pub hit_object_section: OsuFileHitObjects,
+    /// The lowercase names of the sections the config asked to parse, the writer only writes these.
+    pub parsed_sections: Vec<String>,
+    /// The encoding the file was read in, the writer always writes utf-8.
+    pub encoding: OsuFileEncoding,
+    /// Everything the parser skipped or did not understand, in file order.
+    pub diagnostics: Vec<ParseDiagnostic>
+}
+
+/// A single line of an OsuFileDocument, exactly as it was read.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct OsuFileDocumentLine
+{
+    /// The lowercase name of the section the line is in, empty before the first heading.
+    pub section: String,
+    /// The line without its line ending, comments and whitespace included.
+    pub text: String,
+    /// "\r\n", "\n" or empty for a last line without one.
+    pub line_ending: String,
+    /// The bytes the line was read from, line ending included. Written back as they are while the line reads the same,
+    /// empty for lines that were added or read from text.
+    pub raw: Vec<u8>
+}
+
+/// The text encoding a file was read in, osu! writes utf-8 but old beatmaps are often in the encoding of the mapper's system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OsuFileEncoding
+{
+    pub encoding: &'static Encoding,
+    pub byte_order_mark: bool
+}
+
+impl Default for OsuFileEncoding
+{
+    fn default() -> Self
+    {
+        OsuFileEncoding { encoding: UTF_8, byte_order_mark: false }
+    }
+}
+
+/// The lossless counterpart of OsuFile, an unmodified document is written back byte-for-byte.
+/// Keeps comments, unknown sections and keys, ordering and whitespace so the MD5 osu! identifies a beatmap by does not change.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct OsuFileDocument
+{
+    pub encoding: OsuFileEncoding,
+    pub lines: Vec<OsuFileDocumentLine>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OsuFileValidation
+{
+    /// Reject the file on anything osu! itself would not have written:
+    /// unknown sections or fields, malformed lines and values out of range.
+    Strict,
+    /// Skip what can't be parsed and keep going, like osu! does.
+    #[default]
+    Lenient
 }
 
 #[derive(Clone, Debug)]
 pub struct OsuFileConfig
 {
+    pub validation: OsuFileValidation,
     pub parse_general: bool,
     pub parse_editor: bool,
     pub parse_metadata: bool,
     pub parse_difficulty: bool,
     pub parse_events: bool,
+    pub parse_storyboard: bool,
     pub parse_timing_points: bool,
     pub parse_colours: bool,
     pub parse_hit_objects: bool,
@@ -385,11 +1171,13 @@ impl Default for OsuFileConfig
     {
         OsuFileConfig 
         {
+            validation: OsuFileValidation::Lenient,
             parse_general: true,
             parse_editor:  true,
             parse_metadata: true,
             parse_difficulty: true,
             parse_events: true,
+            parse_storyboard: true,
             parse_timing_points: false,
             parse_colours: true,
             parse_hit_objects: false
@@ -397,6 +1185,26 @@ impl Default for OsuFileConfig
     }
 }
 
+impl OsuFileConfig
+{
+    /// Whether the section with this lowercase name gets parsed, the events only count when the storyboard is parsed along with them.
+    pub fn parses_section(&self, section: &str) -> bool
+    {
+        match section
+        {
+            "general" => self.parse_general,
+            "editor" => self.parse_editor,
+            "metadata" => self.parse_metadata,
+            "difficulty" => self.parse_difficulty,
+            "events" | "variables" => self.parse_events && self.parse_storyboard,
+            "timingpoints" => self.parse_timing_points,
+            "colours" => self.parse_colours,
+            "hitobjects" => self.parse_hit_objects,
+            _ => false
+        }
+    }
+}
+
 pub struct CsvValue
 {
     pub value: String,
@@ -404,40 +1212,14 @@ pub struct CsvValue
 
 impl CsvValue
 {
-    //TODO: make these functions generic.
-    pub fn to_u32(&self) -> u32 
-    {
-        return self.value.parse::<u32>().unwrap();
-    }
-
-    pub fn to_u8(&self) -> u8
-    {
-        return self.value.parse::<u8>().unwrap();
-    }
-
-    pub fn to_i32(&self) -> i32 
-    {
-        return self.value.parse::<i32>().unwrap();
-    }
-
-    pub fn to_f32(&self) -> f32 
+    pub fn parse<T>(&self, field: &str) -> Result<T, ParseError>
+        where T: FromStr, T::Err: fmt::Display
     {
-        return self.value.parse::<f32>().unwrap();
+        parse_field::<T>(field, &self.value)
     }
 
     pub fn to_bool(&self) -> bool 
     {
-        if self.value == "0" || self.value == "false" 
-        {
-            return false;
-        }
-        else if self.value == "1" || self.value == "true" 
-        {
-            return true;
-        }
-        else 
-        {
-            return false;
-        }
+        self.value == "1" || self.value == "true"
     }
 }
@@ -0,0 +1,226 @@
+use half::f16;
+
+use super::data::{
+    AdjustedDifficulty,
+    OsuFile,
+    MOD_DOUBLE_TIME,
+    MOD_EASY,
+    MOD_HALF_TIME,
+    MOD_HARD_ROCK,
+    MOD_NIGHTCORE
+};
+use super::writer::format_f16;
+
+impl OsuFile
+{
+    /// The difficulty settings with the mods applied, requires the difficulty section to be parsed.
+    /// Hard rock and easy change the settings, double time and half time only the clock rate, the same as osu!lazer 2022.1101.0 which the calculators follow.
+    pub fn adjusted_difficulty(&self, mods: u32) -> AdjustedDifficulty
+    {
+        let difficulty = &self.difficulty_section;
+
+        let mut adjusted = AdjustedDifficulty
+        {
+            hp_drain_rate: f16_decimal(difficulty.hp_drain_rate),
+            circle_size: f16_decimal(difficulty.circle_size),
+            overall_difficulty: f16_decimal(difficulty.overall_difficulty),
+            approach_rate: f16_decimal(difficulty.approach_rate),
+            clock_rate: 1.0
+        };
+
+        if mods & MOD_HARD_ROCK != 0
+        {
+            adjusted.hp_drain_rate = (adjusted.hp_drain_rate * 1.4).min(10.0);
+            adjusted.circle_size = (adjusted.circle_size * 1.3).min(10.0);
+            adjusted.overall_difficulty = (adjusted.overall_difficulty * 1.4).min(10.0);
+            adjusted.approach_rate = (adjusted.approach_rate * 1.4).min(10.0);
+        }
+        else if mods & MOD_EASY != 0
+        {
+            adjusted.hp_drain_rate *= 0.5;
+            adjusted.circle_size *= 0.5;
+            adjusted.overall_difficulty *= 0.5;
+            adjusted.approach_rate *= 0.5;
+        }
+
+        //NOTE: Nightcore is double time with a different sound, osu! sets both bits for it but not every tool does.
+        if mods & (MOD_DOUBLE_TIME | MOD_NIGHTCORE) != 0
+        {
+            adjusted.clock_rate = 1.5;
+        }
+        else if mods & MOD_HALF_TIME != 0
+        {
+            adjusted.clock_rate = 0.75;
+        }
+
+        adjusted
+    }
+}
+
+/// Difficulty values are stored as f16, this gives back the decimal value the file contained.
+pub fn f16_decimal(value: f16) -> f64
+{
+    format_f16(value).parse::<f64>().unwrap_or_else(|_| value.to_f64())
+}
+
+/// Maps a difficulty setting between 0 and 10 onto a range, like the approach rate onto the preempt time.
+pub fn difficulty_range(difficulty: f64, min: f64, mid: f64, max: f64) -> f64
+{
+    if difficulty > 5.0
+    {
+        mid + (max - mid) * (difficulty - 5.0) / 5.0
+    }
+    else if difficulty < 5.0
+    {
+        mid - (mid - min) * (5.0 - difficulty) / 5.0
+    }
+    else
+    {
+        mid
+    }
+}
+
+/// The highest strain of every section of the beatmap, the strain skills of every gamemode are built on it.
+#[derive(Clone, Debug)]
+pub struct StrainPeaks
+{
+    section_length: f64,
+    peaks: Vec<f64>,
+    current_peak: f64,
+    current_section_end: Option<f64>
+}
+
+impl StrainPeaks
+{
+    pub fn new(section_length: f64) -> StrainPeaks
+    {
+        StrainPeaks { section_length, peaks: Vec::new(), current_peak: 0.0, current_section_end: None }
+    }
+
+    /// Closes every section that ends before the time, initial_strain gives the strain a new section starts with.
+    pub fn start_sections_until<F: Fn(f64) -> f64>(&mut self, time: f64, initial_strain: F)
+    {
+        let mut section_end: f64 = match self.current_section_end
+        {
+            Some(section_end) => section_end,
+            None => (time / self.section_length).ceil() * self.section_length
+        };
+
+        while time > section_end
+        {
+            self.peaks.push(self.current_peak);
+            self.current_peak = initial_strain(section_end);
+            section_end += self.section_length;
+        }
+
+        self.current_section_end = Some(section_end);
+    }
+
+    pub fn add(&mut self, strain: f64)
+    {
+        self.current_peak = self.current_peak.max(strain);
+    }
+
+    /// The peaks of every section, the one that is still open included.
+    pub fn peaks(&self) -> Vec<f64>
+    {
+        let mut peaks: Vec<f64> = self.peaks.clone();
+        peaks.push(self.current_peak);
+        peaks
+    }
+}
+
+/// Sums the strains from the hardest down, every next one weighing decay_weight times the one before.
+pub fn weighted_strain_sum(mut strains: Vec<f64>, decay_weight: f64) -> f64
+{
+    strains.sort_by(|a, b| b.total_cmp(a));
+
+    let mut weight: f64 = 1.0;
+    let mut difficulty: f64 = 0.0;
+
+    for strain in strains
+    {
+        difficulty += strain * weight;
+        weight *= decay_weight;
+    }
+
+    difficulty
+}
+
+/// The factor a strain is multiplied with after the time, decay_base is what is left of it after a second.
+pub fn strain_decay(decay_base: f64, milliseconds: f64) -> f64
+{
+    decay_base.powf(milliseconds / 1000.0)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{difficulty_range, f16_decimal, weighted_strain_sum, StrainPeaks};
+    use crate::osu_format::data::{OsuFile, MOD_DOUBLE_TIME, MOD_EASY, MOD_HALF_TIME, MOD_HARD_ROCK, MOD_NIGHTCORE};
+    use crate::osu_format::test_support;
+
+    fn beatmap() -> OsuFile
+    {
+        test_support::beatmap(&[("Difficulty", "HPDrainRate:6\nCircleSize:4\nOverallDifficulty:8\nApproachRate:9.3")])
+    }
+
+    #[test]
+    fn settings_keep_the_decimals_of_the_file()
+    {
+        let adjusted = beatmap().adjusted_difficulty(0);
+
+        assert_eq!(adjusted.approach_rate, 9.3);
+        assert_eq!(f16_decimal(half::f16::from_f32(1.4)), 1.4);
+        assert_eq!(adjusted.clock_rate, 1.0);
+    }
+
+    #[test]
+    fn hard_rock_and_easy_change_the_settings()
+    {
+        let hard_rock = beatmap().adjusted_difficulty(MOD_HARD_ROCK);
+        let easy = beatmap().adjusted_difficulty(MOD_EASY);
+
+        assert!((hard_rock.hp_drain_rate - 8.4).abs() < 1e-9);
+        assert!((hard_rock.circle_size - 5.2).abs() < 1e-9);
+        assert_eq!((hard_rock.overall_difficulty, hard_rock.approach_rate), (10.0, 10.0));
+        assert_eq!((easy.hp_drain_rate, easy.circle_size, easy.overall_difficulty, easy.approach_rate), (3.0, 2.0, 4.0, 4.65));
+    }
+
+    #[test]
+    fn rate_mods_only_change_the_clock_rate()
+    {
+        assert_eq!(beatmap().adjusted_difficulty(MOD_DOUBLE_TIME).clock_rate, 1.5);
+        assert_eq!(beatmap().adjusted_difficulty(MOD_NIGHTCORE).clock_rate, 1.5);
+        assert_eq!(beatmap().adjusted_difficulty(MOD_HALF_TIME).clock_rate, 0.75);
+        assert_eq!(beatmap().adjusted_difficulty(MOD_DOUBLE_TIME).approach_rate, 9.3);
+    }
+
+    #[test]
+    fn difficulty_range_maps_around_five()
+    {
+        assert_eq!(difficulty_range(0.0, 1800.0, 1200.0, 450.0), 1800.0);
+        assert_eq!(difficulty_range(5.0, 1800.0, 1200.0, 450.0), 1200.0);
+        assert_eq!(difficulty_range(10.0, 1800.0, 1200.0, 450.0), 450.0);
+        assert_eq!(difficulty_range(7.5, 1800.0, 1200.0, 450.0), 825.0);
+    }
+
+    #[test]
+    fn strains_are_summed_from_the_hardest_down()
+    {
+        assert_eq!(weighted_strain_sum(vec![1.0, 4.0, 2.0], 0.5), 4.0 + 2.0 * 0.5 + 1.0 * 0.25);
+    }
+
+    #[test]
+    fn strain_peaks_are_kept_per_section()
+    {
+        let mut peaks: StrainPeaks = StrainPeaks::new(400.0);
+
+        peaks.start_sections_until(100.0, |_| 0.0);
+        peaks.add(3.0);
+        peaks.start_sections_until(900.0, |time| time / 1000.0);
+        peaks.add(0.5);
+
+        assert_eq!(peaks.peaks(), vec![3.0, 0.4, 0.8]);
+    }
+}
@@ -0,0 +1,426 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::data::{OsuFileDocument, OsuFileDocumentLine, OsuFileEncoding};
+use super::error::{ParseError, ParseErrorKind};
+
+///
+/// General todo's for this file:
+/// - Edit single events, timing points and hit objects instead of only key-value pairs.
+///
+impl OsuFileDocument
+{
+    pub fn new() -> OsuFileDocument
+    {
+        OsuFileDocument::default()
+    }
+
+    /// Reads the file as is, nothing is validated. The encoding is detected and kept, so writing gives back the same bytes.
+    pub fn parse(file: PathBuf) -> Result<OsuFileDocument, ParseError>
+    {
+        let bytes: Vec<u8> = fs::read(&file)
+            .map_err(|err| ParseError::new(ParseErrorKind::Io(err.to_string())).at(Some(&file), 0, ""))?;
+
+        OsuFileDocument::from_bytes(&bytes).map_err(|err| err.at(Some(&file), 0, ""))
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<OsuFileDocument, ParseError>
+    {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|err| ParseError::new(ParseErrorKind::Io(err.to_string())))?;
+
+        OsuFileDocument::from_bytes(&bytes)
+    }
+
+    /// Every line keeps the bytes it was read from, so the lines that are not edited are written back untouched.
+    pub fn from_bytes(bytes: &[u8]) -> Result<OsuFileDocument, ParseError>
+    {
+        let (decoded_lines, encoding) = OsuFileEncoding::decode_lines(bytes);
+        let mut document: OsuFileDocument = OsuFileDocument { encoding, lines: Vec::with_capacity(decoded_lines.len()) };
+        let mut section: String = String::new();
+
+        for (decoded, raw) in decoded_lines.iter()
+        {
+            let (line, line_ending) = split_line_ending(decoded);
+            document.lines.push(read_line(&mut section, line, line_ending, raw));
+        }
+
+        Ok(document)
+    }
+
+    /// Reads a document that is already decoded, it is written back as utf-8.
+    pub fn from_text(text: &str) -> OsuFileDocument
+    {
+        let mut lines: Vec<OsuFileDocumentLine> = Vec::new();
+        let mut section: String = String::new();
+        let mut rest: &str = text;
+
+        while !rest.is_empty()
+        {
+            let (line, remainder) = rest.split_at(rest.find('\n').map_or(rest.len(), |newline| newline + 1));
+            let (line, line_ending) = split_line_ending(line);
+
+            lines.push(read_line(&mut section, line, line_ending, &[]));
+            rest = remainder;
+        }
+
+        OsuFileDocument { encoding: OsuFileEncoding::default(), lines }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        writer.write_all(self.encoding.bom_bytes())?;
+
+        for line in self.lines.iter()
+        {
+            if line.is_unedited(&self.encoding)
+            {
+                writer.write_all(&line.raw)?;
+            }
+            else
+            {
+                writer.write_all(&self.encoding.encode_text(&format!("{}{}", line.text, line.line_ending)))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self, file: PathBuf) -> io::Result<()>
+    {
+        let mut writer = BufWriter::new(File::create(file)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// The value of a key in a key-value section like [General] or [Metadata], names are case insensitive.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str>
+    {
+        let index = self.find_key(section, key)?;
+        let text: &str = &self.lines[index].text;
+        let colon = text.find(':')?;
+        Some(text[colon + 1..].trim())
+    }
+
+    /// Changes the value of a key, only the value of that line is touched.
+    /// A missing key is added to the end of its section and a missing section to the end of the document.
+    pub fn set(&mut self, section: &str, key: &str, value: &str)
+    {
+        if let Some(index) = self.find_key(section, key)
+        {
+            let line = &mut self.lines[index];
+            let colon = line.text.find(':').unwrap_or(line.text.len());
+            let spacing: usize = line.text[colon + 1..].chars().take_while(|c| *c == ' ').count();
+
+            line.text = format!("{}{}", &line.text[..colon + 1 + spacing], value);
+            return;
+        }
+
+        let section_name: String = section.to_lowercase();
+        let line_ending: String = self.line_ending();
+
+        //NOTE: [Metadata] and [Difficulty] are written without a space after the colon, so use what the section already does.
+        let separator: &str = match self.lines.iter().find(|line| line.section == section_name && line.text.contains(':') && !is_comment(&line.text))
+        {
+            Some(line) if !line.text.contains(": ") => ":",
+            _ => ": "
+        };
+
+        let new_line = OsuFileDocumentLine { section: section_name.clone(), text: format!("{}{}{}", key, separator, value), line_ending: line_ending.clone(), raw: Vec::new() };
+
+        //NOTE: Insert after the last line of the section that has content, so the blank line between sections stays.
+        let last_line = self.lines.iter().rposition(|line| line.section == section_name && !line.text.trim().is_empty());
+
+        match last_line
+        {
+            Some(index) =>
+            {
+                if self.lines[index].line_ending.is_empty()
+                {
+                    self.lines[index].line_ending = line_ending;
+                }
+
+                self.lines.insert(index + 1, new_line);
+            },
+            None =>
+            {
+                self.end_last_line(&line_ending);
+
+                if let Some(last) = self.lines.last().filter(|line| !line.text.trim().is_empty())
+                {
+                    let blank = OsuFileDocumentLine { section: last.section.clone(), text: String::new(), line_ending: line_ending.clone(), raw: Vec::new() };
+                    self.lines.push(blank);
+                }
+
+                self.lines.push(OsuFileDocumentLine { section: section_name, text: format!("[{}]", section), line_ending, raw: Vec::new() });
+                self.lines.push(new_line);
+            }
+        }
+    }
+
+    /// Removes a key from its section, returns false if it was not there.
+    pub fn remove(&mut self, section: &str, key: &str) -> bool
+    {
+        match self.find_key(section, key)
+        {
+            Some(index) =>
+            {
+                self.lines.remove(index);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// The lines of a section after its heading, comments and blank lines included.
+    pub fn section_lines(&self, section: &str) -> Vec<&OsuFileDocumentLine>
+    {
+        let section_name: String = section.to_lowercase();
+
+        self.lines
+            .iter()
+            .filter(|line| line.section == section_name && section_heading(&line.text).is_none())
+            .collect()
+    }
+
+    fn find_key(&self, section: &str, key: &str) -> Option<usize>
+    {
+        let section_name: String = section.to_lowercase();
+
+        self.lines.iter().position(|line|
+        {
+            line.section == section_name
+                && !is_comment(&line.text)
+                && line.text.split_once(':').map(|(name, _)| name.trim().eq_ignore_ascii_case(key)).unwrap_or(false)
+        })
+    }
+
+    /// The line ending the document uses, osu! itself writes "\r\n".
+    fn line_ending(&self) -> String
+    {
+        self.lines
+            .iter()
+            .map(|line| line.line_ending.clone())
+            .find(|line_ending| !line_ending.is_empty())
+            .unwrap_or_else(|| String::from("\r\n"))
+    }
+
+    fn end_last_line(&mut self, line_ending: &str)
+    {
+        if let Some(line) = self.lines.last_mut()
+        {
+            if line.line_ending.is_empty()
+            {
+                line.line_ending = line_ending.to_owned();
+            }
+        }
+    }
+}
+
+impl OsuFileDocumentLine
+{
+    /// Whether the line still reads the same as the bytes it was read from.
+    fn is_unedited(&self, encoding: &OsuFileEncoding) -> bool
+    {
+        if self.raw.is_empty()
+        {
+            return false;
+        }
+
+        //NOTE: Decoding is lossy for bytes the encoding doesn't know, so compare with the decoded bytes instead of encoding the text.
+        //      Those lines are then kept as they were, instead of written with replacement characters.
+        let decoded: String = encoding.decode_text(&self.raw);
+        decoded.len() == self.text.len() + self.line_ending.len() && decoded.starts_with(&self.text) && decoded.ends_with(&self.line_ending)
+    }
+}
+
+impl FromStr for OsuFileDocument
+{
+    type Err = Infallible;
+
+    fn from_str(text: &str) -> Result<OsuFileDocument, Self::Err>
+    {
+        Ok(OsuFileDocument::from_text(text))
+    }
+}
+
+impl fmt::Display for OsuFileDocument
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        for line in self.lines.iter()
+        {
+            write!(formatter, "{}{}", line.text, line.line_ending)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_line(section: &mut String, line: &str, line_ending: &str, raw: &[u8]) -> OsuFileDocumentLine
+{
+    if let Some(heading) = section_heading(line)
+    {
+        *section = heading;
+    }
+
+    OsuFileDocumentLine { section: section.clone(), text: line.to_owned(), line_ending: line_ending.to_owned(), raw: raw.to_vec() }
+}
+
+fn split_line_ending(line: &str) -> (&str, &str)
+{
+    if let Some(text) = line.strip_suffix("\r\n")
+    {
+        (text, "\r\n")
+    }
+    else if let Some(text) = line.strip_suffix('\n')
+    {
+        (text, "\n")
+    }
+    else
+    {
+        (line, "")
+    }
+}
+
+fn section_heading(line: &str) -> Option<String>
+{
+    let trimmed: &str = line.trim();
+
+    if trimmed.starts_with('[') && trimmed.ends_with(']') && trimmed.len() > 2
+    {
+        Some(trimmed[1..trimmed.len() - 1].to_lowercase())
+    }
+    else
+    {
+        None
+    }
+}
+
+fn is_comment(line: &str) -> bool
+{
+    line.trim_start().starts_with("//")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use encoding_rs::SHIFT_JIS;
+
+    use crate::osu_format::data::OsuFileDocument;
+
+    const BEATMAP: &str = "osu file format v14\r\n\r\n[Metadata]\r\nTitle:夜に駆ける\r\nArtist:YOASOBI\r\nVersion:Hard\r\n";
+
+    const COMMENTED: &str = "osu file format v14\n\n[General]\nAudioFilename: audio.mp3\n// keep me\nUnknownKey: 1   \n\n[Metadata]\nTitle:Song\nVersion:Hard\n\n[Mystery]\nwhatever,1,2\n";
+
+    fn utf16le(text: &str, byte_order_mark: bool) -> Vec<u8>
+    {
+        let mut bytes: Vec<u8> = if byte_order_mark { vec![0xFF, 0xFE] } else { Vec::new() };
+        bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        bytes
+    }
+
+    fn written(document: &OsuFileDocument) -> Vec<u8>
+    {
+        let mut bytes: Vec<u8> = Vec::new();
+        document.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn utf16_files_are_written_back_byte_for_byte()
+    {
+        for byte_order_mark in [true, false]
+        {
+            let bytes: Vec<u8> = utf16le(BEATMAP, byte_order_mark);
+            let document: OsuFileDocument = OsuFileDocument::from_bytes(&bytes).unwrap();
+
+            assert_eq!(document.get("Metadata", "Title"), Some("夜に駆ける"));
+            assert_eq!(written(&document), bytes);
+        }
+    }
+
+    #[test]
+    fn utf8_files_with_a_byte_order_mark_are_written_back_byte_for_byte()
+    {
+        let bytes: Vec<u8> = [&[0xEF, 0xBB, 0xBF], BEATMAP.as_bytes()].concat();
+        let document: OsuFileDocument = OsuFileDocument::from_bytes(&bytes).unwrap();
+
+        assert_eq!(document.lines[0].text, "osu file format v14");
+        assert_eq!(written(&document), bytes);
+    }
+
+    #[test]
+    fn shift_jis_files_are_written_back_byte_for_byte()
+    {
+        let (bytes, _, _) = SHIFT_JIS.encode(BEATMAP);
+        let document: OsuFileDocument = OsuFileDocument::from_bytes(&bytes).unwrap();
+
+        assert_eq!(document.encoding.name(), "Shift_JIS");
+        assert_eq!(document.get("Metadata", "Title"), Some("夜に駆ける"));
+        assert_eq!(written(&document), bytes.into_owned());
+    }
+
+    #[test]
+    fn only_edited_lines_are_encoded_again()
+    {
+        //NOTE: A lone surrogate decodes to a replacement character, encoding that again would change the bytes of the line.
+        let mut bytes: Vec<u8> = utf16le("osu file format v14\r\n//", true);
+        bytes.extend_from_slice(&[0x00, 0xD8]);
+        bytes.extend(utf16le("\r\n[Metadata]\r\nVersion:Hard\r\n", false));
+
+        let mut document: OsuFileDocument = OsuFileDocument::from_bytes(&bytes).unwrap();
+        document.set("Metadata", "Version", "Insane");
+
+        let expected: Vec<u8> = [&bytes[..bytes.len() - utf16le("Hard\r\n", false).len()], &utf16le("Insane\r\n", false)].concat();
+        assert_eq!(written(&document), expected);
+    }
+
+    #[test]
+    fn unedited_documents_are_written_back_unchanged()
+    {
+        let document: OsuFileDocument = COMMENTED.parse().unwrap();
+
+        assert_eq!(document.to_string(), COMMENTED);
+        assert_eq!(written(&document), COMMENTED.as_bytes());
+        assert_eq!(document.section_lines("mystery")[0].text, "whatever,1,2");
+    }
+
+    #[test]
+    fn setting_a_value_only_touches_its_line()
+    {
+        let mut document: OsuFileDocument = COMMENTED.parse().unwrap();
+        document.set("general", "unknownkey", "2");
+
+        assert_eq!(document.get("General", "UnknownKey"), Some("2"));
+        assert_eq!(document.to_string(), COMMENTED.replace("UnknownKey: 1   ", "UnknownKey: 2"));
+    }
+
+    #[test]
+    fn missing_keys_and_sections_are_added()
+    {
+        let mut document: OsuFileDocument = COMMENTED.parse().unwrap();
+        document.set("Metadata", "Artist", "Artist");
+        document.set("Difficulty", "CircleSize", "4");
+
+        let expected: String = COMMENTED.replace("Version:Hard\n", "Version:Hard\nArtist:Artist\n") + "\n[Difficulty]\nCircleSize: 4\n";
+        assert_eq!(document.to_string(), expected);
+    }
+
+    #[test]
+    fn removing_a_key_leaves_the_rest()
+    {
+        let mut document: OsuFileDocument = COMMENTED.parse().unwrap();
+
+        assert!(document.remove("General", "UnknownKey"));
+        assert!(!document.remove("General", "UnknownKey"));
+        assert_eq!(document.to_string(), COMMENTED.replace("UnknownKey: 1   \n", ""));
+    }
+}
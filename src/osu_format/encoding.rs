@@ -0,0 +1,150 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+
+use super::data::OsuFileEncoding;
+
+const UTF_8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF_16LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF_16BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+impl OsuFileEncoding
+{
+    /// Detects the encoding of a whole file, in order: a byte order mark, utf-16 without one, utf-8 and
+    /// at last a guess at the legacy encoding, like Shift-JIS for old Japanese beatmaps or windows-1252.
+    pub fn detect(bytes: &[u8]) -> OsuFileEncoding
+    {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes)
+        {
+            return OsuFileEncoding { encoding, byte_order_mark: true };
+        }
+
+        //NOTE: Every file starts with the ascii "osu file format" header, in utf-16 every other byte of it is zero.
+        let encoding: &'static Encoding = match bytes
+        {
+            [first, 0, ..] if *first != 0 => UTF_16LE,
+            [0, second, ..] if *second != 0 => UTF_16BE,
+            _ if std::str::from_utf8(bytes).is_ok() => UTF_8,
+            _ =>
+            {
+                let mut detector = EncodingDetector::new();
+                detector.feed(bytes, true);
+                detector.guess(None, false)
+            }
+        };
+
+        OsuFileEncoding { encoding, byte_order_mark: false }
+    }
+
+    /// Detects the encoding and decodes the file with it, the byte order mark is left out of the text.
+    pub fn decode(bytes: &[u8]) -> (String, OsuFileEncoding)
+    {
+        let encoding: OsuFileEncoding = OsuFileEncoding::detect(bytes);
+        let bom_length: usize = if encoding.byte_order_mark { bom(encoding.encoding).len() } else { 0 };
+        let (text, _) = encoding.encoding.decode_without_bom_handling(&bytes[bom_length..]);
+
+        (text.into_owned(), encoding)
+    }
+
+    /// Like decode, but line by line. Every line comes with the bytes it was decoded from, its line ending included,
+    /// so a line that is not edited can be written back exactly as it was read.
+    pub fn decode_lines(bytes: &[u8]) -> (Vec<(String, &[u8])>, OsuFileEncoding)
+    {
+        let encoding: OsuFileEncoding = OsuFileEncoding::detect(bytes);
+        let mut lines: Vec<(String, &[u8])> = Vec::new();
+        let mut rest: &[u8] = &bytes[encoding.bom_bytes().len()..];
+
+        while !rest.is_empty()
+        {
+            let (line, remainder) = rest.split_at(encoding.line_length(rest));
+            lines.push((encoding.decode_text(line), line));
+            rest = remainder;
+        }
+
+        (lines, encoding)
+    }
+
+    /// Decodes bytes that don't start with a byte order mark.
+    pub fn decode_text(&self, bytes: &[u8]) -> String
+    {
+        let (text, _) = self.encoding.decode_without_bom_handling(bytes);
+        text.into_owned()
+    }
+
+    /// Encodes the text back into this encoding, characters the encoding can't represent become numeric character references.
+    pub fn encode(&self, text: &str) -> Vec<u8>
+    {
+        let mut bytes: Vec<u8> = self.bom_bytes().to_vec();
+        bytes.extend(self.encode_text(text));
+        bytes
+    }
+
+    /// Like encode, without the byte order mark.
+    pub fn encode_text(&self, text: &str) -> Vec<u8>
+    {
+        let mut bytes: Vec<u8> = Vec::with_capacity(text.len());
+
+        //NOTE: encoding_rs only decodes utf-16, encoding into it falls back to utf-8.
+        if self.encoding == UTF_16LE
+        {
+            bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        }
+        else if self.encoding == UTF_16BE
+        {
+            bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+        }
+        else
+        {
+            let (encoded, _, _) = self.encoding.encode(text);
+            bytes.extend_from_slice(&encoded);
+        }
+
+        bytes
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        self.encoding.name()
+    }
+
+    /// The byte order mark the file started with, empty when it had none.
+    pub fn bom_bytes(&self) -> &'static [u8]
+    {
+        if self.byte_order_mark { bom(self.encoding) } else { &[] }
+    }
+
+    /// The length in bytes of the first line, its line ending included.
+    fn line_length(&self, bytes: &[u8]) -> usize
+    {
+        //NOTE: In utf-16 a newline takes two bytes at an even offset, the other encodings we detect keep ascii bytes as they are.
+        let newline: Option<usize> = if self.encoding == UTF_16LE
+        {
+            bytes.chunks(2).position(|unit| unit == [0x0A, 0x00]).map(|unit| unit * 2 + 1)
+        }
+        else if self.encoding == UTF_16BE
+        {
+            bytes.chunks(2).position(|unit| unit == [0x00, 0x0A]).map(|unit| unit * 2 + 1)
+        }
+        else
+        {
+            bytes.iter().position(|byte| *byte == b'\n')
+        };
+
+        newline.map_or(bytes.len(), |newline| newline + 1)
+    }
+}
+
+fn bom(encoding: &'static Encoding) -> &'static [u8]
+{
+    if encoding == UTF_16LE
+    {
+        &UTF_16LE_BOM
+    }
+    else if encoding == UTF_16BE
+    {
+        &UTF_16BE_BOM
+    }
+    else
+    {
+        &UTF_8_BOM
+    }
+}
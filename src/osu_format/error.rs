@@ -0,0 +1,189 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind
+{
+    /// The file could not be opened or read.
+    Io(String),
+    /// The first line is not an "osu file format vXX" header.
+    MissingVersion,
+    /// A line in a key-value section does not contain a colon.
+    InvalidKeyValue,
+    /// A comma separated line has fewer values than required.
+    MissingValues { expected: usize, found: usize },
+    /// A value could not be converted into the type of its field.
+    InvalidValue { value: String, cause: String },
+    /// A value osu! would not write for its field.
+    OutOfRange { value: f64, min: f64, max: f64 },
+    /// A section heading osu! does not know about.
+    UnknownSection,
+    /// A key osu! does not know about in a key-value section.
+    UnknownField { value: String },
+    /// An element or command in the storyboard is malformed.
+    InvalidStoryboard(String)
+}
+
+impl fmt::Display for ParseErrorKind
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            ParseErrorKind::Io(cause) => write!(formatter, "failed reading file: {}", cause),
+            ParseErrorKind::MissingVersion => write!(formatter, "file does not contain a version number"),
+            ParseErrorKind::InvalidKeyValue => write!(formatter, "invalid key-value pair"),
+            ParseErrorKind::MissingValues { expected, found } => write!(formatter, "expected at least {} values, got {}", expected, found),
+            ParseErrorKind::InvalidValue { value, cause } => write!(formatter, "invalid value {:?}: {}", value, cause),
+            ParseErrorKind::OutOfRange { value, min, max } => write!(formatter, "value {} is outside of {} to {}", value, min, max),
+            ParseErrorKind::UnknownSection => write!(formatter, "unknown section"),
+            ParseErrorKind::UnknownField { value } => write!(formatter, "unknown field with value {:?}", value),
+            ParseErrorKind::InvalidStoryboard(cause) => write!(formatter, "invalid storyboard: {}", cause)
+        }
+    }
+}
+
+/// An error in an .osu or .osb file, the location is filled in as far as it is known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError
+{
+    pub path: Option<PathBuf>,
+    /// 1-based line number, 0 when the error is not about a single line.
+    pub line: usize,
+    pub section: String,
+    pub field: Option<String>,
+    //NOTE: Boxed to keep the Result of every section parser small, errors are the exception.
+    pub kind: Box<ParseErrorKind>
+}
+
+impl ParseError
+{
+    pub fn new(kind: ParseErrorKind) -> ParseError
+    {
+        ParseError { path: None, line: 0, section: String::new(), field: None, kind: Box::new(kind) }
+    }
+
+    pub fn invalid_value(field: &str, value: &str, cause: impl fmt::Display) -> ParseError
+    {
+        ParseError::new(ParseErrorKind::InvalidValue { value: value.to_owned(), cause: cause.to_string() }).with_field(field)
+    }
+
+    pub fn with_field(mut self, field: &str) -> ParseError
+    {
+        self.field = Some(field.to_owned());
+        self
+    }
+
+    /// Attaches the location the parser was at, keeping a field set by the section parser.
+    pub fn at(mut self, path: Option<&PathBuf>, line: usize, section: &str) -> ParseError
+    {
+        self.locate(path, line, section);
+        self
+    }
+
+    pub fn locate(&mut self, path: Option<&PathBuf>, line: usize, section: &str)
+    {
+        self.path = path.cloned();
+        self.line = line;
+        self.section = section.to_owned();
+    }
+}
+
+impl fmt::Display for ParseError
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        if let Some(path) = &self.path
+        {
+            write!(formatter, "{}:", path.display())?;
+        }
+
+        if self.line > 0
+        {
+            write!(formatter, "{}: ", self.line)?;
+        }
+        else if self.path.is_some()
+        {
+            write!(formatter, " ")?;
+        }
+
+        if !self.section.is_empty()
+        {
+            write!(formatter, "[{}] ", self.section)?;
+        }
+
+        if let Some(field) = &self.field
+        {
+            write!(formatter, "{}: ", field)?;
+        }
+
+        write!(formatter, "{}", self.kind)
+    }
+}
+
+impl Error for ParseError { }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ParseSeverity
+{
+    /// Nothing is lost, like a field osu! does not use anymore or a value out of range.
+    Notice,
+    /// The line was skipped, the rest of the file is still usable.
+    Warning,
+    /// The file could not be parsed at all.
+    Error
+}
+
+impl fmt::Display for ParseSeverity
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        let name = match self
+        {
+            ParseSeverity::Notice => "notice",
+            ParseSeverity::Warning => "warning",
+            ParseSeverity::Error => "error"
+        };
+
+        write!(formatter, "{}", name)
+    }
+}
+
+/// Something the parser ran into, the line number lives in the error and the line itself in text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseDiagnostic
+{
+    pub severity: ParseSeverity,
+    pub error: ParseError,
+    pub text: String
+}
+
+impl ParseDiagnostic
+{
+    pub fn new(severity: ParseSeverity, error: ParseError) -> ParseDiagnostic
+    {
+        ParseDiagnostic { severity, error, text: String::new() }
+    }
+
+    pub fn line(&self) -> usize
+    {
+        self.error.line
+    }
+}
+
+impl fmt::Display for ParseDiagnostic
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(formatter, "{}: {}", self.severity, self.error)
+    }
+}
+
+/// Converts a single value of a field, any conversion failure becomes an InvalidValue error.
+pub fn parse_field<T>(field: &str, value: &str) -> Result<T, ParseError>
+    where T: FromStr, T::Err: fmt::Display
+{
+    value.trim().parse::<T>().map_err(|err| ParseError::invalid_value(field, value, err))
+}
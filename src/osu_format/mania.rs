@@ -0,0 +1,243 @@
+use super::data::{
+    ManiaDifficultyAttributes,
+    OsuFile,
+    OsuFileGamemode,
+    OsuFileHitObjectKind,
+    MOD_DOUBLE_TIME,
+    MOD_EASY,
+    MOD_HALF_TIME,
+    MOD_HARD_ROCK,
+    MOD_NIGHTCORE
+};
+use super::difficulty::{f16_decimal, strain_decay, weighted_strain_sum, StrainPeaks};
+
+const PLAYFIELD_WIDTH: f64 = 512.0;
+const SECTION_LENGTH: f64 = 400.0;
+const DECAY_WEIGHT: f64 = 0.9;
+const STAR_SCALING_FACTOR: f64 = 0.018;
+
+const INDIVIDUAL_DECAY_BASE: f64 = 0.125;
+const OVERALL_DECAY_BASE: f64 = 0.3;
+const RELEASE_THRESHOLD: f64 = 30.0;
+
+#[derive(Clone, Copy, Debug)]
+struct ManiaObject
+{
+    column: usize,
+    start_time: f64,
+    end_time: f64
+}
+
+///
+/// General todo's for this file:
+/// - Converting osu!standard beatmaps to osu!mania, that needs the pattern generation osu! does for converts.
+/// - The key mods change the key count.
+///
+impl OsuFile
+{
+    /// The osu!mania star rating with the mods, None for beatmaps of the other gamemodes.
+    /// Requires the general, difficulty and hit objects sections to be parsed.
+    pub fn mania_difficulty(&self, mods: u32) -> Option<ManiaDifficultyAttributes>
+    {
+        if self.general_section.mode != OsuFileGamemode::Mania
+        {
+            return None;
+        }
+
+        //NOTE: The key count is the circle size before mods, hard rock and easy don't change it in osu!mania.
+        let key_count: usize = f16_decimal(self.difficulty_section.circle_size).round().max(1.0) as usize;
+        let clock_rate: f64 = self.adjusted_difficulty(mods).clock_rate;
+
+        let mut objects: Vec<ManiaObject> = self.hit_object_section.hit_objects
+            .iter()
+            .map(|hit_object| ManiaObject
+            {
+                column: ((hit_object.x as f64 * key_count as f64 / PLAYFIELD_WIDTH).floor().max(0.0) as usize).min(key_count - 1),
+                start_time: hit_object.time as f64 / clock_rate,
+                end_time: hit_object.end_time() as f64 / clock_rate
+            })
+            .collect();
+
+        objects.sort_by(|a, b| a.start_time.total_cmp(&b.start_time).then(a.column.cmp(&b.column)));
+
+        Some(ManiaDifficultyAttributes
+        {
+            mods,
+            star_rating: strain_difficulty(&objects, key_count) * STAR_SCALING_FACTOR,
+            great_hit_window: ((self.mania_great_hit_window(mods) * clock_rate).trunc() / clock_rate).ceil(),
+            key_count,
+            max_combo: self.mania_max_combo()
+        })
+    }
+
+    /// The combo of a full combo on an osu!mania beatmap, hold notes give combo for their head and every 100ms they are held.
+    pub fn mania_max_combo(&self) -> usize
+    {
+        self.hit_object_section.hit_objects
+            .iter()
+            .map(|hit_object| match hit_object.kind
+            {
+                OsuFileHitObjectKind::Hold { end_time } => 1 + ((end_time - hit_object.time).max(0) / 100) as usize,
+                _ => 1
+            })
+            .sum()
+    }
+
+    fn mania_great_hit_window(&self, mods: u32) -> f64
+    {
+        let overall_difficulty: f64 = f16_decimal(self.difficulty_section.overall_difficulty);
+        let mut hit_window: f64 = 34.0 + 3.0 * (10.0 - overall_difficulty).clamp(0.0, 10.0);
+
+        if mods & MOD_HARD_ROCK != 0
+        {
+            hit_window /= 1.4;
+        }
+        else if mods & MOD_EASY != 0
+        {
+            hit_window *= 1.4;
+        }
+
+        //NOTE: In osu!mania the speed mods don't change the hit window, so it is scaled along with the clock rate.
+        if mods & (MOD_DOUBLE_TIME | MOD_NIGHTCORE) != 0
+        {
+            hit_window *= 1.5;
+        }
+        else if mods & MOD_HALF_TIME != 0
+        {
+            hit_window *= 0.75;
+        }
+
+        hit_window
+    }
+}
+
+/// The strain of every column on its own, plus the strain of the hands together. Holding notes makes everything else harder.
+fn strain_difficulty(objects: &[ManiaObject], key_count: usize) -> f64
+{
+    let mut peaks = StrainPeaks::new(SECTION_LENGTH);
+
+    let mut start_times: Vec<f64> = vec![0.0; key_count];
+    let mut end_times: Vec<f64> = vec![0.0; key_count];
+    let mut individual_strains: Vec<f64> = vec![0.0; key_count];
+    let mut highest_individual_strain: f64 = 0.0;
+    let mut overall_strain: f64 = 1.0;
+
+    for index in 1..objects.len()
+    {
+        let current: ManiaObject = objects[index];
+        let previous_start_time: f64 = objects[index - 1].start_time;
+        let delta_time: f64 = current.start_time - previous_start_time;
+
+        peaks.start_sections_until(current.start_time, |time|
+        {
+            highest_individual_strain * strain_decay(INDIVIDUAL_DECAY_BASE, time - previous_start_time)
+                + overall_strain * strain_decay(OVERALL_DECAY_BASE, time - previous_start_time)
+        });
+
+        let mut is_overlapping: bool = false;
+        let mut closest_end_time: f64 = (current.end_time - current.start_time).abs();
+        let mut hold_factor: f64 = 1.0;
+        let mut hold_addition: f64 = 0.0;
+
+        for end_time in end_times.iter()
+        {
+            //NOTE: The note is overlapped when a hold in another column is still going when it starts and ends before it.
+            is_overlapping |= *end_time - current.start_time > 1.0 && current.end_time - *end_time > 1.0;
+
+            if *end_time - current.end_time > 1.0
+            {
+                hold_factor = 1.25;
+            }
+
+            closest_end_time = closest_end_time.min((current.end_time - *end_time).abs());
+        }
+
+        //NOTE: Releasing several holds at once is as easy as releasing one, so the addition only counts when the releases are apart.
+        if is_overlapping
+        {
+            hold_addition = 1.0 / (1.0 + (0.5 * (RELEASE_THRESHOLD - closest_end_time)).exp());
+        }
+
+        let column: usize = current.column;
+        individual_strains[column] *= strain_decay(INDIVIDUAL_DECAY_BASE, current.start_time - start_times[column]);
+        individual_strains[column] += 2.0 * hold_factor;
+
+        //NOTE: The notes of a chord count as hard as the hardest column in it.
+        highest_individual_strain = if delta_time <= 1.0 { highest_individual_strain.max(individual_strains[column]) } else { individual_strains[column] };
+
+        overall_strain *= strain_decay(OVERALL_DECAY_BASE, delta_time);
+        overall_strain += (1.0 + hold_addition) * hold_factor;
+
+        start_times[column] = current.start_time;
+        end_times[column] = current.end_time;
+
+        peaks.add(highest_individual_strain + overall_strain);
+    }
+
+    weighted_strain_sum(peaks.peaks(), DECAY_WEIGHT)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::{OsuFile, MOD_DOUBLE_TIME, MOD_EASY, MOD_HALF_TIME, MOD_HARD_ROCK};
+    use crate::osu_format::test_support;
+
+    fn beatmap(mode: u8, circle_size: f32, hit_objects: &str) -> OsuFile
+    {
+        test_support::beatmap(&[
+            ("General", &format!("Mode: {}", mode)),
+            ("Difficulty", &format!("CircleSize:{}\nOverallDifficulty:8", circle_size)),
+            ("HitObjects", hit_objects)
+        ])
+    }
+
+    const NOTES: &str = "64,192,1000,1,0,0:0:0:0:\n192,192,1250,1,0,0:0:0:0:\n320,192,1500,128,0,2050:0:0:0:0:\n448,192,1750,1,0,0:0:0:0:\n";
+
+    #[test]
+    fn the_key_count_is_the_circle_size()
+    {
+        assert_eq!(beatmap(3, 4.0, NOTES).mania_difficulty(0).unwrap().key_count, 4);
+        assert_eq!(beatmap(3, 7.0, NOTES).mania_difficulty(MOD_HARD_ROCK).unwrap().key_count, 7);
+    }
+
+    #[test]
+    fn holds_give_combo_while_they_are_held()
+    {
+        //NOTE: The hold lasts 550ms, which gives its head and five more.
+        assert_eq!(beatmap(3, 4.0, NOTES).mania_difficulty(0).unwrap().max_combo, 3 + 6);
+    }
+
+    #[test]
+    fn the_hit_window_follows_the_mods()
+    {
+        let osu_file = beatmap(3, 4.0, NOTES);
+
+        assert_eq!(osu_file.mania_difficulty(0).unwrap().great_hit_window, 40.0);
+        assert_eq!(osu_file.mania_difficulty(MOD_HARD_ROCK).unwrap().great_hit_window, 28.0);
+        assert_eq!(osu_file.mania_difficulty(MOD_EASY).unwrap().great_hit_window, 56.0);
+        //NOTE: The speed mods widen the window in song time, so it stays about as wide in real time.
+        assert_eq!(osu_file.mania_difficulty(MOD_DOUBLE_TIME).unwrap().great_hit_window, 60.0);
+        assert_eq!(osu_file.mania_difficulty(MOD_HALF_TIME).unwrap().great_hit_window, 30.0);
+    }
+
+    #[test]
+    fn faster_rates_are_harder()
+    {
+        let notes: String = (0..64).map(|index| format!("{},192,{},1,0,0:0:0:0:\n", 64 + (index % 4) * 128, 1000 + index * 125)).collect();
+        let osu_file = beatmap(3, 4.0, &notes);
+        let star_rating: f64 = osu_file.mania_difficulty(0).unwrap().star_rating;
+
+        assert!(star_rating > 0.0);
+        assert!(osu_file.mania_difficulty(MOD_DOUBLE_TIME).unwrap().star_rating > star_rating);
+    }
+
+    #[test]
+    fn other_modes_are_not_converted()
+    {
+        for mode in 0..3
+        {
+            assert!(beatmap(mode, 4.0, NOTES).mania_difficulty(0).is_none());
+        }
+    }
+}
@@ -1,15 +1,32 @@
+pub mod catch;
 pub mod data;
-
-use std::io::{BufRead, BufReader};
-use std::fs::File;
+pub mod difficulty;
+pub mod document;
+pub mod encoding;
+pub mod error;
+pub mod mania;
+pub mod samples;
+pub mod slider;
+pub mod standard;
+pub mod stats;
+pub mod storyboard;
+pub mod taiko;
+#[cfg(test)]
+mod test_support;
+pub mod timing;
+pub mod writer;
+
+use std::io::{self, BufRead, Read};
+use std::fs;
 use std::path::PathBuf;
-use std::str::FromStr;
 use half::{ f16 };
 
 use data::{
     OsuFile,
     OsuFileConfig,
+    OsuFileEncoding,
     OsuFileBackground,
+    OsuFileBreakPeriod,
     OsuFileCombo,
     OsuFileColor,
     OsuFileVideo,
@@ -17,22 +34,26 @@ use data::{
     OsuFileGamemode,
     OsuFileTimingPoint,
     OsuFileHitObject,
+    OsuFileHitObjectKind,
+    OsuFileHitSample,
+    OsuFileSlider,
+    OsuFileCurveType,
+    OsuFileCurvePoint,
+    OsuFileEdgeSet,
     OsuFileOverlayPosition,
     OsuFileEditorBookmarks,
     OsuFileMetadataTags,
-    CsvValue
+    CsvValue,
+    OsuFileValidation,
+    HIT_TYPE_CIRCLE,
+    HIT_TYPE_SLIDER,
+    HIT_TYPE_SPINNER,
+    HIT_TYPE_HOLD
 };
+use error::{ParseDiagnostic, ParseError, ParseErrorKind, ParseSeverity, parse_field};
+
+const SECTIONS: [&str; 9] = ["general", "editor", "metadata", "difficulty", "events", "variables", "timingpoints", "colours", "hitobjects"];
 
-///
-/// General todo's for this file:
-/// - Ensure safe conversion OsuFile string -> i8/u8/u32/i32/f32/enum.
-///   Move this to a util file, to deduplictate the code and some extra for error handling.
-/// - Parse storyboard elements for Events section.
-/// - Parse the "Effects" field of Timingpoint, require some bitwise magic.
-/// 
-/// Long term
-/// - Support non utf-8 files.
-/// 
 impl OsuFile
 {
     pub fn new() -> OsuFile
@@ -40,7 +61,7 @@ impl OsuFile
         OsuFile::default()
     }
 
-    fn match_kvp(&self, line: String) -> Result<(String, String), String>
+    fn match_kvp(&self, line: String) -> Result<(String, String), ParseError>
     {        
         let kvp: Vec<&str> = line.split(':').collect();
         let kvp_len = kvp.len();
@@ -60,18 +81,17 @@ impl OsuFile
         }
         else
         {
-            let err = format!("invalid key-value pair on value: {} ", line);
-            Err(err)
+            Err(ParseError::new(ParseErrorKind::InvalidKeyValue))
         }
     }
 
-    fn match_csv(&self, line: String) -> Result<Vec<CsvValue>, String>
+    fn match_csv(&self, line: String) -> Result<Vec<CsvValue>, ParseError>
     {
         let csv: Vec<&str> = line.split(",").collect();
 
-        if csv.len() <= 0 
+        if csv.is_empty()
         {
-            return Err("no values inside csv.".to_owned());
+            return Err(ParseError::new(ParseErrorKind::MissingValues { expected: 1, found: 0 }));
         }
 
         let mut values: Vec<CsvValue> = Vec::new();
@@ -84,345 +104,463 @@ impl OsuFile
         Ok(values)
     }
 
-    fn parse_version(&mut self, line: String) -> Result<(), String>
+    fn notice(&mut self, error: ParseError)
+    {
+        self.diagnostics.push(ParseDiagnostic::new(ParseSeverity::Notice, error));
+    }
+
+    fn unknown_field(&mut self, key: &str, value: String)
+    {
+        self.notice(ParseError::new(ParseErrorKind::UnknownField { value }).with_field(key));
+    }
+
+    /// Osu! loads values outside of what its editor allows just fine, so these are only a notice.
+    fn check_range(&mut self, field: &str, value: f64, min: f64, max: f64)
+    {
+        if value < min || value > max
+        {
+            self.notice(ParseError::new(ParseErrorKind::OutOfRange { value, min, max }).with_field(field));
+        }
+    }
+
+    fn parse_version(&mut self, line: String) -> Result<(), ParseError>
     {
         if !line.contains("osu file format") 
         {
-            return Err("File does not contain a version number.".to_owned());
+            return Err(ParseError::new(ParseErrorKind::MissingVersion));
         }
 
         self.version = line.trim().to_owned();
         Ok(())
     }
 
-    fn parse_general(&mut self, line: String) -> Result<(), String>
+    fn parse_general(&mut self, line: String) -> Result<(), ParseError>
     {
-        let kvp = self.match_kvp(line);
-
-        if let Ok((key, value)) = kvp
+        let (key, value) = self.match_kvp(line)?;
+        let mut section = self.general_section.clone();
+
+        let as_u32 = || -> Result<u32, ParseError> { parse_field::<u32>(&key, &value) };
+        let as_i32 = || -> Result<i32, ParseError> { parse_field::<i32>(&key, &value) };
+        let as_f32 = || -> Result<f32, ParseError> { parse_field::<f32>(&key, &value) };
+        let as_bool = || -> bool { value == "1" };
+        let as_sample_set = || -> Result<OsuFileSampleSet, ParseError> { parse_field::<OsuFileSampleSet>(&key, &value) };
+        let as_game_mode = || -> Result<OsuFileGamemode, ParseError> { Ok(OsuFileGamemode::from_u32(as_u32()?)) };
+        let as_overlay = || -> Result<OsuFileOverlayPosition, ParseError> { parse_field::<OsuFileOverlayPosition>(&key, &value) };
+
+        match key.as_ref() 
         {
-            let mut section = self.general_section.clone();
-
-            //TODO: generalize these functions.
-            let as_u32 = || -> u32 { value.parse::<u32>().unwrap() };
-            let as_i32 = || -> i32 { value.parse::<i32>().unwrap() };
-            let as_f32 = || -> f32 { value.parse::<f32>().unwrap() };
-            let as_bool = || -> bool { if value == "1"  { true } else { false } };
-            let as_sample_set = || -> OsuFileSampleSet { OsuFileSampleSet::from_str(&value).unwrap() };
-            let as_game_mode = || -> OsuFileGamemode { OsuFileGamemode::from_u32(as_u32()) };
-            let as_overlay = || -> OsuFileOverlayPosition { OsuFileOverlayPosition::from_str(&value).unwrap()};
+            "audiofilename" => { section.audio_file_name = value },
+            "audioleadin" => { section.audio_lead_in = as_i32()? },
+            "previewtime" => { section.preview_time = as_i32()?; },
+            "countdown" => 
+            { 
+                section.countdown = as_u32()?; 
+                self.check_range(&key, section.countdown as f64, 0.0, 3.0);
+            },
+            "sampleset" => 
+            { 
+                section.sample_set = as_sample_set()?; 
 
-            match key.as_ref() 
-            {
-                "audiofilename" => { section.audio_file_name = value },
-                "audioleadin" => { section.audio_lead_in = as_i32() },
-                "previewtime" => { section.preview_time = as_i32(); },
-                "countdown" => { section.countdown = as_u32(); },
-                "sampleset" => { section.sample_set = as_sample_set(); },
-                "stackleniency" => { section.stack_leniency = as_f32(); },
-                "mode" => { section.mode = as_game_mode(); },
-                "letterboxinbreaks" => { section.letterbox_in_breaks = as_bool(); },
-                "useskinsprites" => { section.use_skin_sprites = as_bool(); },
-                "overlayposition" => { section.overlay_position = as_overlay(); },
-                "skinpreference" => { section.skin_preference = value },
-                "epilepsywarning" => { section.epilepsy_warning = as_bool() },
-                "countdownoffset" => { section.countdown_offset = as_u32()},
-                "specialstyle" => { section.special_style = as_bool()},
-                "widescreenstoryboard" => { section.widescreen_storyboard = as_bool() },
-                "samplesmatchplaybackrate" => { section.samples_match_playback_rate = as_bool() },
-                _ => { println!("Unknown field {} inside general section with value: {}", key, value); }
-            }
+                if section.sample_set == OsuFileSampleSet::Default
+                {
+                    self.notice(ParseError::invalid_value(&key, &value, "unknown sample set"));
+                }
+            },
+            "stackleniency" => 
+            { 
+                section.stack_leniency = as_f32()?; 
+                self.check_range(&key, section.stack_leniency as f64, 0.0, 1.0);
+            },
+            "mode" => 
+            { 
+                section.mode = as_game_mode()?; 
 
-            self.general_section = section;
-        }
-        else if let Err(error) = kvp 
-        {
-            return Err(error);
+                if section.mode == OsuFileGamemode::Unknown
+                {
+                    self.notice(ParseError::invalid_value(&key, &value, "unknown gamemode"));
+                }
+            },
+            "letterboxinbreaks" => { section.letterbox_in_breaks = as_bool(); },
+            "useskinsprites" => { section.use_skin_sprites = as_bool(); },
+            "overlayposition" => { section.overlay_position = as_overlay()?; },
+            "skinpreference" => { section.skin_preference = value },
+            "epilepsywarning" => { section.epilepsy_warning = as_bool() },
+            "countdownoffset" => { section.countdown_offset = as_u32()?},
+            "specialstyle" => { section.special_style = as_bool()},
+            "widescreenstoryboard" => { section.widescreen_storyboard = as_bool() },
+            "samplesmatchplaybackrate" => { section.samples_match_playback_rate = as_bool() },
+            _ => { self.unknown_field(&key, value); }
         }
 
+        self.general_section = section;
+
         Ok(())
     }
 
-    fn parse_editor(&mut self, line: String) -> Result<(), String>
+    fn parse_editor(&mut self, line: String) -> Result<(), ParseError>
     {
-        let kvp = self.match_kvp(line);
-
-        if let Ok((key, value)) = kvp
-        {
-            let mut section = self.editor_section.clone();
-            let as_u32 = || -> u32 { value.parse::<u32>().unwrap() };
-            let as_f32 = || -> f32 { value.parse::<f32>().unwrap() };
-
-            match key.as_ref()
-            {
-                "bookmarks" => { section.bookmarks = OsuFileEditorBookmarks::from_str(&value).unwrap() },
-                "distancespacing" => { section.distance_spacing = as_f32(); },
-                "beatdivisor" => { section.beat_divisor = as_f32(); },
-                "gridsize" => { section.grid_size = as_u32(); },
-                "timelinezoom" => { section.timeline_zoom = as_f32(); }
-                _ => { println!("Unknown field {} inside editor section with value: {}", key, value); }
-            }
+        let (key, value) = self.match_kvp(line)?;
+        let mut section = self.editor_section.clone();
+        let as_u32 = || -> Result<u32, ParseError> { parse_field::<u32>(&key, &value) };
+        let as_f32 = || -> Result<f32, ParseError> { parse_field::<f32>(&key, &value) };
 
-            self.editor_section = section;
-        }        
-        else if let Err(error) = kvp 
+        match key.as_ref()
         {
-            return Err(error);
+            "bookmarks" => { section.bookmarks = parse_field::<OsuFileEditorBookmarks>(&key, &value)? },
+            "distancespacing" => { section.distance_spacing = as_f32()?; },
+            "beatdivisor" => { section.beat_divisor = as_f32()?; },
+            "gridsize" => { section.grid_size = as_u32()?; },
+            "timelinezoom" => { section.timeline_zoom = as_f32()?; }
+            _ => { self.unknown_field(&key, value); }
         }
 
+        self.editor_section = section;
+
         Ok(())
     }
     
-    fn parse_metadata(&mut self, line: String) -> Result<(), String>
+    fn parse_metadata(&mut self, line: String) -> Result<(), ParseError>
     {
-        let kvp = self.match_kvp(line);
-
-        if let Ok((key, value)) = kvp
-        {
-            let mut section = self.metadata_section.clone();
-            let as_i64 = |v: String| -> i64 { v.parse::<i64>().unwrap() };
-
-            match key.as_ref() 
-            {
-                "title" => { section.title = value },
-                "titleunicode" => { section.title_unicode =  value },
-                "artist" => { section.artist = value },
-                "artistunicode" => { section.artist_unicode = value },
-                "creator" => { section.creator = value },
-                "version" => { section.version = value },
-                "source" => { section.source =  value },
-                "tags" => { section.tags = OsuFileMetadataTags::from_str(&value).unwrap(); },
-                "beatmapid" => { section.beatmap_id= as_i64(value); },
-                "beatmapsetid" => { section.beatmap_set_id = as_i64(value); },
-                _ => { println!("Unknown field {} inside metadata section with value: {}", "", value); }
-            }
+        let (key, value) = self.match_kvp(line)?;
+        let mut section = self.metadata_section.clone();
+        let as_i64 = || -> Result<i64, ParseError> { parse_field::<i64>(&key, &value) };
 
-            self.metadata_section = section;
-        }
-        else if let Err(error) = kvp 
+        match key.as_ref() 
         {
-            return Err(error);
+            "title" => { section.title = value },
+            "titleunicode" => { section.title_unicode =  value },
+            "artist" => { section.artist = value },
+            "artistunicode" => { section.artist_unicode = value },
+            "creator" => { section.creator = value },
+            "version" => { section.version = value },
+            "source" => { section.source =  value },
+            "tags" => { section.tags = parse_field::<OsuFileMetadataTags>(&key, &value)?; },
+            "beatmapid" => { section.beatmap_id = as_i64()?; },
+            "beatmapsetid" => { section.beatmap_set_id = as_i64()?; },
+            _ => { self.unknown_field(&key, value); }
         }
 
+        self.metadata_section = section;
+
         Ok(())
     }
 
-    fn parse_difficulty(&mut self, line: String) -> Result<(), String>
+    fn parse_difficulty(&mut self, line: String) -> Result<(), ParseError>
     {
-        let kvp = self.match_kvp(line);
+        let (key, value) = self.match_kvp(line)?;
+        let mut section = self.difficulty_section.clone();
+        let as_f16 = || -> Result<f16, ParseError> { Ok(f16::from_f32(parse_field::<f32>(&key, &value)?)) };
 
-        if let Ok((key, value)) = kvp
+        //NOTE: The circle size of mania is its key count, which goes up to 18 with the key mods.
+        let circle_size_max: f64 = if self.general_section.mode == OsuFileGamemode::Mania { 18.0 } else { 10.0 };
+        
+        let (parsed, min, max) = match key.as_ref()
         {
-            let mut section = self.difficulty_section.clone();
-            let as_f16 = || -> f16 { f16::from_f32(value.parse::<f32>().unwrap()) };
-            
-            match key.as_ref()
-            {
-                "hpdrainrate" => { section.hp_drain_rate = as_f16(); },
-                "circlesize" => { section.circle_size = as_f16(); },
-                "overalldifficulty" => { section.overall_difficulty = as_f16(); },
-                "approachrate" => { section.approach_rate = as_f16(); },
-                "slidermultiplier" => { section.slider_multiplier = as_f16(); },
-                "slidertickrate" => { section.slider_tick_rate= as_f16(); },
-                _ => { println!("Unknown field {} inside difficulty section with value: {}", key, value); }
+            "hpdrainrate" => { section.hp_drain_rate = as_f16()?; (section.hp_drain_rate, 0.0, 10.0) },
+            "circlesize" => { section.circle_size = as_f16()?; (section.circle_size, 0.0, circle_size_max) },
+            "overalldifficulty" => { section.overall_difficulty = as_f16()?; (section.overall_difficulty, 0.0, 10.0) },
+            "approachrate" => { section.approach_rate = as_f16()?; (section.approach_rate, 0.0, 10.0) },
+            "slidermultiplier" => { section.slider_multiplier = as_f16()?; (section.slider_multiplier, 0.4, 3.6) },
+            "slidertickrate" => { section.slider_tick_rate = as_f16()?; (section.slider_tick_rate, 0.5, 8.0) },
+            _ => 
+            { 
+                self.unknown_field(&key, value); 
+                return Ok(());
             }
+        };
 
-            self.difficulty_section = section;
-        }
-        else if let Err(error) = kvp 
-        {
-            return Err(error);
-        }
+        self.check_range(&key, parsed.to_f64(), min, max);
+
+        self.difficulty_section = section;
 
         Ok(())
     }
 
-    fn parse_events(&mut self, line: String) -> Result<(), String>
+    fn parse_events(&mut self, line: String, parse_storyboard: bool) -> Result<(), ParseError>
     {
-        let line_split: Vec<&str> = line.split(",").collect();
+        let line = self.events_section.storyboard.substitute_variables(&line);
+        let line_split: Vec<&str> = line.split(",").map(|value| value.trim()).collect();
 
-        if line_split.len() == 0
+        if line_split.is_empty()
         {
-            return Err(format!("no csv string provided, got: {}", line));
+            return Err(ParseError::new(ParseErrorKind::MissingValues { expected: 1, found: 0 }));
         }
 
-        let mut section = self.events_section.clone();
         let event_type = line_split[0];
 
         if line_split.len() >= 3 && (event_type == "0" || event_type == "Background") 
         {
             let file = line_split[2].to_owned().replace("\"", "");
-            let x_offset = if line_split.len() == 4 { line_split[3].parse::<i32>().unwrap() } else { 0 };
-            let y_offset = if line_split.len() == 5 { line_split[4].parse::<i32>().unwrap() } else { 0 };
+            let x_offset = if line_split.len() >= 4 { parse_field::<i32>("xOffset", line_split[3])? } else { 0 };
+            let y_offset = if line_split.len() >= 5 { parse_field::<i32>("yOffset", line_split[4])? } else { 0 };
             
-            section.background = OsuFileBackground 
+            self.events_section.background = OsuFileBackground 
             {
                 exists: true, 
                 file_name: file,
-                x_offset: x_offset,
-                y_offset: y_offset
+                x_offset,
+                y_offset
             };  
         }
-
-        if line_split.len() >= 3 && (event_type == "1" || event_type == "Video")
+        else if line_split.len() >= 3 && (event_type == "1" || event_type == "Video")
         {
-            let start_time = line_split[1].parse::<i32>().unwrap();
+            let start_time = parse_field::<i32>("startTime", line_split[1])?;
             let file = line_split[2].to_owned().replace("\"", "");
-            let x_offset = if line_split.len() == 4 { line_split[3].parse::<i32>().unwrap() } else { 0 };
-            let y_offset = if line_split.len() == 5 { line_split[4].parse::<i32>().unwrap() } else { 0 };
+            let x_offset = if line_split.len() >= 4 { parse_field::<i32>("xOffset", line_split[3])? } else { 0 };
+            let y_offset = if line_split.len() >= 5 { parse_field::<i32>("yOffset", line_split[4])? } else { 0 };
             
-            section.video = OsuFileVideo 
+            self.events_section.video = OsuFileVideo 
             {
                 exists: true, 
-                start_time: start_time,
+                start_time,
                 file_name: file,
-                x_offset: x_offset,
-                y_offset: y_offset
+                x_offset,
+                y_offset
             };  
         }
+        else if line_split.len() >= 3 && (event_type == "2" || event_type == "Break")
+        {
+            self.events_section.breaks.push(OsuFileBreakPeriod 
+            { 
+                start: parse_field::<u32>("startTime", line_split[1])?, 
+                end: parse_field::<u32>("endTime", line_split[2])? 
+            });
+        }
+        else if parse_storyboard
+        {
+            //NOTE: Sprites and animations can have thousands of commands, so the section is not cloned here.
+            self.events_section.storyboard
+                .parse_line(&line)
+                .map_err(|err| ParseError::new(ParseErrorKind::InvalidStoryboard(err)))?;
+        }
 
-        self.events_section = section; 
         Ok(())
     }
 
-    fn parse_timing_points(&mut self, line: String) -> Result<(), String>
+    fn parse_timing_points(&mut self, line: String) -> Result<(), ParseError>
     {
-        let mut section = self.timing_points_section.clone();
-        let csv_match_result = self.match_csv(line);
+        let csv_match = self.match_csv(line)?;
+        let mut timing_point: OsuFileTimingPoint = OsuFileTimingPoint { ..Default::default() }; 
 
-        if let Ok(csv_match) = csv_match_result
+        for (index, csv) in csv_match.into_iter().enumerate()
         {
-            let mut index: u32 = 0;
-            let mut timing_point: OsuFileTimingPoint = OsuFileTimingPoint { ..Default::default() }; 
-
-            for csv in csv_match
+            match index
             {
-                match index
-                {
-                    0 => { timing_point.time = csv.to_f32() },
-                    1 => { timing_point.beat_length = csv.to_f32() },
-                    2 => { timing_point.meter = csv.to_i32() },
-                    3 => { timing_point.sample_set = OsuFileSampleSet::from_u32(csv.to_u32()) },
-                    4 => { timing_point.sample_index = csv.to_i32(); },
-                    5 => { timing_point.volume = csv.to_i32(); },
-                    6 => { timing_point.uninherited = csv.to_bool() },
-                    7 => { timing_point.effects = csv.to_i32() }
-                    _ => { }
-                }
-
-                index += 1;
+                0 => { timing_point.time = csv.parse::<f32>("time")? },
+                1 => { timing_point.beat_length = csv.parse::<f32>("beatLength")? },
+                2 => { timing_point.meter = csv.parse::<i32>("meter")? },
+                3 => 
+                { 
+                    let sample_set = csv.parse::<u32>("sampleSet")?;
+                    self.check_range("sampleSet", sample_set as f64, 0.0, 3.0);
+                    timing_point.sample_set = OsuFileSampleSet::from_u32(sample_set);
+                },
+                4 => { timing_point.sample_index = csv.parse::<i32>("sampleIndex")?; },
+                5 => 
+                { 
+                    timing_point.volume = csv.parse::<i32>("volume")?; 
+                    self.check_range("volume", timing_point.volume as f64, 0.0, 100.0);
+                },
+                6 => { timing_point.uninherited = csv.to_bool() },
+                7 => { timing_point.effects = csv.parse::<i32>("effects")? }
+                _ => { }
             }
+        }
+
+        self.timing_points_section.timing_points.push(timing_point);
+
+        Ok(())
+    }
+
+    fn parse_colours(&mut self, line: String) -> Result<(), ParseError>
+    {
+        let (key, value) = self.match_kvp(line)?;
+        let mut section = self.colours_section.clone();
+
+        if key.starts_with("combo")
+        {
+            let num: String = key.replace("combo", "");
+            let index = parse_field::<i8>(&key, &num)?;
+            let color = parse_field::<OsuFileColor>(&key, &value)?;
 
-            section.timing_points.push(timing_point);
+            section.combo_colors.push(OsuFileCombo { index, color });
+        }
+        else if key.starts_with("sliderborder")
+        {
+            section.slider_border = Some(parse_field::<OsuFileColor>(&key, &value)?);
+        }
+        else if key.starts_with("slidertrackoverride")
+        {
+            section.slider_track_override = Some(parse_field::<OsuFileColor>(&key, &value)?);
+        }
+        else
+        {
+            self.unknown_field(&key, value);
         }
 
-        self.timing_points_section = section;
+        self.colours_section = section;
+
         Ok(())
     }
 
-    fn parse_colours(&mut self, line: String) -> Result<(), String>
+    fn parse_hit_objects(&mut self, line: String) -> Result<(), ParseError>
     {
-        let kvp = self.match_kvp(line);
+        let csv_match = self.match_csv(line)?;
 
-        if let Ok((key, value)) = kvp
+        if csv_match.len() < 5
         {
-            let mut section = self.colours_section.clone();
+            return Err(ParseError::new(ParseErrorKind::MissingValues { expected: 5, found: csv_match.len() }));
+        }
 
-            if key.starts_with("combo")
-            {
-                let num: String = key.replace("combo", "");
-                let index = num.parse::<i8>().unwrap();
+        //NOTE: Old beatmaps can have fractional positions and times, osu! truncates those.
+        let mut hit_object: OsuFileHitObject = OsuFileHitObject 
+        { 
+            x: csv_match[0].parse::<f32>("x")? as i32,
+            y: csv_match[1].parse::<f32>("y")? as i32,
+            time: csv_match[2].parse::<f64>("time")? as i32,
+            hit_type: csv_match[3].parse::<u8>("type")?,
+            hit_sound: csv_match[4].parse::<u8>("hitSound")?,
+            ..Default::default() 
+        }; 
 
-                if let Ok(color) = OsuFileColor::from_str(value)
-                {
-                    section.combo_colors.push(OsuFileCombo { index: index, color: color });
-                }
-            }
-            else if key.starts_with("sliderborder")
-            {
-                section.slider_border = OsuFileColor::from_str(value).unwrap();
-            }
-            else if key.starts_with("slidertrackoverride")
+        let object_types: u8 = hit_object.hit_type & (HIT_TYPE_CIRCLE | HIT_TYPE_SLIDER | HIT_TYPE_SPINNER | HIT_TYPE_HOLD);
+
+        if object_types.count_ones() != 1
+        {
+            self.notice(ParseError::invalid_value("type", &csv_match[3].value, "expected exactly one of circle, slider, spinner or hold"));
+        }
+
+        //NOTE: Everything after the hit sound depends on the type of the object, 
+        //      the hit sample is the optional trailing value.
+        let rest: Vec<String> = csv_match.into_iter().skip(5).map(|csv| csv.value).collect();
+        let missing = |expected: usize| -> ParseError { ParseError::new(ParseErrorKind::MissingValues { expected, found: 5 + rest.len() }) };
+        let hit_sample: Option<String>;
+
+        if hit_object.hit_type & HIT_TYPE_SLIDER != 0
+        {
+            if rest.len() < 3
             {
-                section.slider_track_override = OsuFileColor::from_str(value).unwrap();
+                return Err(missing(8));
             }
-            else
+
+            hit_object.kind = OsuFileHitObjectKind::Slider(parse_slider(&rest)?);
+            hit_sample = rest.get(5).cloned();
+        }
+        else if hit_object.hit_type & HIT_TYPE_SPINNER != 0
+        {
+            let end_time: &String = rest.first().ok_or_else(|| missing(6))?;
+            hit_object.kind = OsuFileHitObjectKind::Spinner { end_time: parse_field::<f64>("endTime", end_time)? as i32 };
+            hit_sample = rest.get(1).cloned();
+        }
+        else if hit_object.hit_type & HIT_TYPE_HOLD != 0
+        {
+            //NOTE: Mania holds separate the end time and the hit sample with a colon.
+            let hold: &String = rest.first().ok_or_else(|| missing(6))?;
+            let (end_time, sample) = match hold.split_once(':')
             {
-                println!("Unhandled key-value pair in Colours, key: {}, value: {}", key, value);
-            }
+                Some((end_time, sample)) => (end_time, Some(sample.to_owned())),
+                None => (hold.as_str(), None)
+            };
 
-            self.colours_section = section;
+            hit_object.kind = OsuFileHitObjectKind::Hold { end_time: parse_field::<f64>("endTime", end_time)? as i32 };
+            hit_sample = sample;
         }
-        else if let Err(error) = kvp 
+        else
         {
-            return Err(error);
+            hit_sample = rest.first().cloned();
         }
 
+        hit_object.hit_sample = match hit_sample
+        {
+            Some(hit_sample) => Some(parse_field::<OsuFileHitSample>("hitSample", &hit_sample)?),
+            None => None
+        };
+
+        self.hit_object_section.hit_objects.push(hit_object);
+
         Ok(())
     }
 
-    fn parse_hit_objects(&mut self, line: String) -> Result<(), String>
+    /// Parses the file, malformed lines are skipped and end up in the diagnostics.
+    /// Fails when the file can not be read or is not an .osu file at all, is_valid is false in that case.
+    /// With strict validation any diagnostic fails the file, including notices.
+    pub fn parse(&mut self, file: PathBuf, config: OsuFileConfig) -> Result<(), ParseError>
+    {
+        match fs::read(&file)
+        {
+            Ok(bytes) => self.parse_decoded(&bytes, Some(&file), config),
+            Err(err) => Err(self.read_failed(err, Some(&file)))
+        }
+    }
+
+    /// Like parse, but reads from anything, e.g. an entry of an .osz archive.
+    /// Diagnostics don't carry a path in that case.
+    pub fn parse_reader<R: Read>(&mut self, mut reader: R, config: OsuFileConfig) -> Result<(), ParseError>
     {
-        let mut section = self.hit_object_section.clone();
-        let csv_match_result = self.match_csv(line);
+        let mut bytes: Vec<u8> = Vec::new();
 
-        if let Ok(csv_match) = csv_match_result
+        match reader.read_to_end(&mut bytes)
         {
-            let mut index: u32 = 0;
-            let mut hit_object: OsuFileHitObject = OsuFileHitObject { ..Default::default() }; 
+            Ok(_) => self.parse_decoded(&bytes, None, config),
+            Err(err) => Err(self.read_failed(err, None))
+        }
+    }
 
-            for csv in csv_match
-            {
-                match index
-                {
-                    0 => { hit_object.x = csv.to_i32() },
-                    1 => { hit_object.y = csv.to_i32() },
-                    2 => { hit_object.time = csv.to_i32() },
-                    3 => { hit_object.hit_sound= csv.to_u8() },
-                    4 => { hit_object.params = csv.value },
-                    5 => { hit_object.hit_sample = csv.value },
-                    _ => { }
-                }
+    pub fn parse_bytes(&mut self, bytes: &[u8], config: OsuFileConfig) -> Result<(), ParseError>
+    {
+        self.parse_decoded(bytes, None, config)
+    }
 
-                index += 1;
-            }
+    pub fn parse_str(&mut self, text: &str, config: OsuFileConfig) -> Result<(), ParseError>
+    {
+        let text: &str = text.strip_prefix('\u{feff}').unwrap_or(text);
+        self.parse_lines(text.as_bytes(), None, config)
+    }
 
-            section.hit_objects.push(hit_object);
-        }
+    fn read_failed(&mut self, err: io::Error, file: Option<&PathBuf>) -> ParseError
+    {
+        let err = ParseError::new(ParseErrorKind::Io(err.to_string())).at(file, 0, "");
+        self.diagnostics.push(ParseDiagnostic::new(ParseSeverity::Error, err.clone()));
+        self.is_valid = false;
+        err
+    }
 
-        self.hit_object_section = section;
-        Ok(())
+    fn parse_decoded(&mut self, bytes: &[u8], file: Option<&PathBuf>, config: OsuFileConfig) -> Result<(), ParseError>
+    {
+        let (text, encoding) = OsuFileEncoding::decode(bytes);
+        self.encoding = encoding;
+        self.parse_lines(text.as_bytes(), file, config)
     }
 
-    pub fn parse(&mut self, file: PathBuf, config: OsuFileConfig)
+    fn parse_lines<R: BufRead>(&mut self, file_reader: R, file: Option<&PathBuf>, config: OsuFileConfig) -> Result<(), ParseError>
     {
-        let osu_file = File::open(file)
-            .expect("Failed reading .osu file.");
-        
-        let file_reader: BufReader<File> = BufReader::new(osu_file);
         let mut context: String = String::new();
 
         self.is_valid = true;
 
-        let no_op = || -> Result<(), String> { Ok(()) };
+        let strict: bool = config.validation == OsuFileValidation::Strict;
+        let no_op = || -> Result<(), ParseError> { Ok(()) };
+        let storyboard_error = |err: String| -> ParseError { ParseError::new(ParseErrorKind::InvalidStoryboard(err)) };
 
-        for line_wrap in file_reader.lines()
+        for (line_index, line_wrap) in file_reader.lines().enumerate()
         {
             if !self.is_valid 
             { 
                 break; 
             }
 
-            let mut line: String = String::new();
+            let first_diagnostic: usize = self.diagnostics.len();
 
-            if let Err(_) = line_wrap 
+            let line: String = match line_wrap
             {
-                continue;
-            }
-            else if let Ok(unwrapped_line) = line_wrap 
-            {
-                line = unwrapped_line;
-            }
+                Ok(line) => line,
+                Err(err) => 
+                {
+                    let err = ParseError::new(ParseErrorKind::Io(err.to_string())).at(file, line_index + 1, &context);
+                    self.diagnostics.push(ParseDiagnostic::new(ParseSeverity::Warning, err));
+                    continue;
+                }
+            };
             
             let line_copy = line.clone();
             
@@ -437,55 +575,282 @@ impl OsuFile
                 let heading: String = line
                     .chars()
                     .skip(1)
-                    .take_while(|c| 
-                        match c 
-                        {
-                            ']' => false,
-                            _ => true
-                        }
-                    )
+                    .take_while(|c| *c != ']')
                     .collect();
 
                 if heading.is_empty() { continue; };
                 context = heading.to_lowercase();
+
+                //NOTE: Osu! skips sections it does not know, so only the heading is reported.
+                if !SECTIONS.contains(&context.as_str())
+                {
+                    let err = ParseError::new(ParseErrorKind::UnknownSection).at(file, line_index + 1, &context);
+                    self.diagnostics.push(ParseDiagnostic { severity: ParseSeverity::Notice, error: err, text: line_copy });
+                }
+                else if config.parses_section(&context) && !self.parsed_sections.contains(&context)
+                {
+                    self.parsed_sections.push(context.clone());
+                }
             }
             else 
             {
                 //TODO: This is really clunky and hard to read, can we handle this in the function we're calling?
-                let result: Result<(), String> = match context.as_ref()
+                let result: Result<(), ParseError> = match context.as_ref()
                 {
                     "" => self.parse_version(line),
                     "general" => if config.parse_general { self.parse_general(line) } else { no_op() },
                     "editor" => if config.parse_editor { self.parse_editor(line) } else { no_op() },
                     "metadata" => if config.parse_metadata { self.parse_metadata(line) } else { no_op() },
                     "difficulty" => if config.parse_difficulty { self.parse_difficulty(line) } else { no_op() },
-                    "events" => if config.parse_events { self.parse_events(line) } else { no_op() },
+                    "events" => if config.parse_events { self.parse_events(line, config.parse_storyboard) } else { no_op() },
+                    "variables" => if config.parse_events && config.parse_storyboard { self.events_section.storyboard.parse_variable(&line).map_err(storyboard_error) } else { no_op() },
                     "timingpoints" => if config.parse_timing_points { self.parse_timing_points(line) } else { no_op() },
                     "colours" => if config.parse_colours { self.parse_colours(line) } else { no_op() },
                     "hitobjects" => if config.parse_hit_objects { self.parse_hit_objects(line) } else { no_op() },
-                    _ => Err(format!("Context {} was not handled.", context), )
+                    _ => no_op()
                 };
 
+                //NOTE: The section parsers don't know where they are, so the notices they raised get located here.
+                for diagnostic in self.diagnostics[first_diagnostic..].iter_mut()
+                {
+                    diagnostic.error.locate(file, line_index + 1, &context);
+                    diagnostic.text = line_copy.clone();
+                }
 
-                match result 
+                if let Err(err) = result
                 {
-                    Err(err) => 
+                    let err = err.at(file, line_index + 1, &context);
+
+                    //NOTE: If we parsed the file and found that the version is incorrect....
+                    //      Then this is a faulty file to begin with.
+                    let severity = if context.is_empty() { ParseSeverity::Error } else { ParseSeverity::Warning };
+                    self.diagnostics.push(ParseDiagnostic { severity, error: err.clone(), text: line_copy });
+
+                    if severity == ParseSeverity::Error
                     {
-                        //NOTE: If we parsed the file and found that the version is incorrect....
-                        //      Then this is a faulty file to begin with.
-                        if context == "" 
-                        {
-                            println!("Tried parsing an invalid file, error: {}", err); 
-                            self.is_valid = false;
-                        }
-                        else
-                        {
-                            println!("Failed to parse line for with error: {}\n\tContext {}\n\tValue {}", err, context, line_copy); 
-                        }
+                        self.is_valid = false;
+                        return Err(err);
                     }
-                    _ => { },
-                };
+                }
             }
+
+            if strict
+            {
+                if let Some(diagnostic) = self.diagnostics[first_diagnostic..].first_mut()
+                {
+                    diagnostic.severity = ParseSeverity::Error;
+                    self.is_valid = false;
+                    return Err(diagnostic.error.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses "curveType|curvePoints,slides,length,edgeSounds,edgeSets", the values after the hit sound of a slider.
+fn parse_slider(values: &[String]) -> Result<OsuFileSlider, ParseError>
+{
+    let mut curve = values[0].split('|');
+    let curve_type = parse_field::<OsuFileCurveType>("curveType", curve.next().unwrap_or_default())?;
+    let mut curve_points: Vec<OsuFileCurvePoint> = Vec::new();
+
+    for point in curve
+    {
+        curve_points.push(parse_field::<OsuFileCurvePoint>("curvePoints", point)?);
+    }
+
+    let mut slider = OsuFileSlider
+    {
+        curve_type,
+        curve_points,
+        slides: parse_field::<u32>("slides", &values[1])?,
+        length: parse_field::<f64>("length", &values[2])?,
+        ..Default::default()
+    };
+
+    if let Some(edge_sounds) = values.get(3)
+    {
+        for edge_sound in edge_sounds.split('|')
+        {
+            slider.edge_sounds.push(parse_field::<u8>("edgeSounds", edge_sound)?);
         }
     }
-}
\ No newline at end of file
+
+    if let Some(edge_sets) = values.get(4)
+    {
+        for edge_set in edge_sets.split('|')
+        {
+            slider.edge_sets.push(parse_field::<OsuFileEdgeSet>("edgeSets", edge_set)?);
+        }
+    }
+
+    Ok(slider)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::data::{OsuFile, OsuFileConfig, OsuFileValidation, OsuFileHitObjectKind, OsuFileCurveType, OsuFileCurvePoint, OsuFileEdgeSet, OsuFileHitSample};
+    use super::error::{ParseError, ParseErrorKind, ParseSeverity};
+
+    const UNUSUAL: &str = "osu file format v14\n\n[General]\nMode: 0\nFancyField: 1\n\n[Difficulty]\nCircleSize:4\nOverallDifficulty:12\nApproachRate:9\n";
+
+    fn parse(text: &str, config: OsuFileConfig) -> (OsuFile, Result<(), ParseError>)
+    {
+        let mut osu_file: OsuFile = OsuFile::new();
+        let result = osu_file.parse_str(text, config);
+        (osu_file, result)
+    }
+
+    #[test]
+    fn invalid_values_are_located()
+    {
+        let (osu_file, result) = parse("osu file format v14\n\n[Difficulty]\nHPDrainRate:5\nCircleSize:big\n", OsuFileConfig::default());
+        let diagnostic = &osu_file.diagnostics[0];
+
+        assert!(result.is_ok());
+        assert_eq!(osu_file.diagnostics.len(), 1);
+        assert_eq!(diagnostic.severity, ParseSeverity::Warning);
+        assert_eq!(diagnostic.line(), 5);
+        assert_eq!(diagnostic.error.section, "difficulty");
+        assert_eq!(diagnostic.error.field.as_deref(), Some("circlesize"));
+        assert_eq!(diagnostic.text, "CircleSize:big");
+        assert!(matches!(&*diagnostic.error.kind, ParseErrorKind::InvalidValue { value, .. } if value == "big"));
+        assert_eq!(diagnostic.error.to_string(), "5: [difficulty] circlesize: invalid value \"big\": invalid float literal");
+    }
+
+    #[test]
+    fn missing_values_of_hit_objects_are_counted()
+    {
+        let config = OsuFileConfig { parse_hit_objects: true, ..Default::default() };
+        let (osu_file, _) = parse("osu file format v14\n\n[HitObjects]\n256,192,1000\n", config);
+
+        assert_eq!(osu_file.diagnostics[0].error.section, "hitobjects");
+        assert_eq!(*osu_file.diagnostics[0].error.kind, ParseErrorKind::MissingValues { expected: 5, found: 3 });
+    }
+
+    #[test]
+    fn files_without_a_version_are_invalid()
+    {
+        let (osu_file, result) = parse("not a beatmap\n[General]\nMode: 0\n", OsuFileConfig::default());
+
+        assert!(!osu_file.is_valid);
+        assert_eq!(*result.unwrap_err().kind, ParseErrorKind::MissingVersion);
+        assert_eq!(osu_file.diagnostics[0].severity, ParseSeverity::Error);
+        assert_eq!(osu_file.diagnostics[0].line(), 1);
+    }
+
+    #[test]
+    fn lenient_validation_keeps_going()
+    {
+        let (osu_file, result) = parse(UNUSUAL, OsuFileConfig::default());
+        let severities: Vec<ParseSeverity> = osu_file.diagnostics.iter().map(|diagnostic| diagnostic.severity).collect();
+
+        assert!(result.is_ok());
+        assert!(osu_file.is_valid);
+        assert_eq!(severities, vec![ParseSeverity::Notice, ParseSeverity::Notice]);
+        assert!(matches!(*osu_file.diagnostics[1].error.kind, ParseErrorKind::OutOfRange { .. }));
+        assert_eq!(osu_file.difficulty_section.overall_difficulty.to_f32(), 12.0);
+        assert_eq!(osu_file.difficulty_section.approach_rate.to_f32(), 9.0);
+    }
+
+    #[test]
+    fn strict_validation_stops_at_the_first_diagnostic()
+    {
+        let config = OsuFileConfig { validation: OsuFileValidation::Strict, ..Default::default() };
+        let (osu_file, result) = parse(UNUSUAL, config);
+        let err: ParseError = result.unwrap_err();
+
+        assert!(!osu_file.is_valid);
+        assert_eq!(osu_file.diagnostics.len(), 1);
+        assert_eq!(osu_file.diagnostics[0].severity, ParseSeverity::Error);
+        assert_eq!(err.line, 5);
+        assert_eq!(err.field.as_deref(), Some("fancyfield"));
+        assert_eq!(osu_file.difficulty_section.approach_rate.to_f32(), 0.0);
+    }
+
+    #[test]
+    fn strict_validation_accepts_clean_files()
+    {
+        let config = OsuFileConfig { validation: OsuFileValidation::Strict, ..Default::default() };
+        let (osu_file, result) = parse("osu file format v14\n\n[Difficulty]\nCircleSize:4\n", config);
+
+        assert!(result.is_ok());
+        assert!(osu_file.is_valid);
+        assert!(osu_file.diagnostics.is_empty());
+    }
+
+    fn parse_hit_objects(lines: &str) -> OsuFile
+    {
+        let config = OsuFileConfig { parse_hit_objects: true, ..Default::default() };
+        let (osu_file, result) = parse(&format!("osu file format v14\n\n[HitObjects]\n{}", lines), config);
+
+        assert!(result.is_ok());
+        assert!(osu_file.diagnostics.is_empty(), "{:?}", osu_file.diagnostics);
+        osu_file
+    }
+
+    #[test]
+    fn circles_and_their_combo_bits_are_decoded()
+    {
+        let osu_file = parse_hit_objects("256,192,1000,37,2,1:2:3:70:hit.wav\n10.5,20.9,1500.7,1,0\n");
+        let circle = &osu_file.hit_object_section.hit_objects[0];
+
+        assert_eq!(circle.kind, OsuFileHitObjectKind::Circle);
+        assert!(circle.is_new_combo());
+        assert_eq!(circle.combo_colour_skip(), 2);
+        assert_eq!(circle.hit_sample, Some(OsuFileHitSample { normal_set: 1, addition_set: 2, index: 3, volume: 70, file_name: String::from("hit.wav") }));
+
+        let truncated = &osu_file.hit_object_section.hit_objects[1];
+        assert_eq!((truncated.x, truncated.y, truncated.time), (10, 20, 1500));
+        assert!(!truncated.is_new_combo());
+        assert_eq!(truncated.hit_sample, None);
+    }
+
+    #[test]
+    fn sliders_are_decoded()
+    {
+        let osu_file = parse_hit_objects("100,100,1000,2,0,P|150:50|200:100,2,157.5,2|0|8,1:2|0:0|2:0,0:0:0:0:\n");
+        let hit_object = &osu_file.hit_object_section.hit_objects[0];
+
+        let slider = match &hit_object.kind
+        {
+            OsuFileHitObjectKind::Slider(slider) => slider,
+            kind => panic!("expected a slider, got {:?}", kind)
+        };
+
+        assert_eq!(slider.curve_type, OsuFileCurveType::PerfectCircle);
+        assert_eq!(slider.curve_points, vec![OsuFileCurvePoint { x: 150.0, y: 50.0 }, OsuFileCurvePoint { x: 200.0, y: 100.0 }]);
+        assert_eq!(slider.slides, 2);
+        assert_eq!(slider.length, 157.5);
+        assert_eq!(slider.edge_sounds, vec![2, 0, 8]);
+        assert_eq!(slider.edge_sets[0], OsuFileEdgeSet { normal_set: 1, addition_set: 2 });
+        assert!(hit_object.hit_sample.is_some());
+    }
+
+    #[test]
+    fn spinners_and_holds_are_decoded()
+    {
+        let osu_file = parse_hit_objects("256,192,1000,12,0,3000,0:0:0:0:\n64,192,4000,128,0,4500:1:0:0:40:\n64,192,5000,128,0,5250\n");
+        let hit_objects = &osu_file.hit_object_section.hit_objects;
+
+        assert_eq!(hit_objects[0].kind, OsuFileHitObjectKind::Spinner { end_time: 3000 });
+        assert_eq!(hit_objects[0].end_time(), 3000);
+        assert_eq!(hit_objects[1].kind, OsuFileHitObjectKind::Hold { end_time: 4500 });
+        assert_eq!(hit_objects[1].hit_sample.as_ref().map(|hit_sample| hit_sample.volume), Some(40));
+        assert_eq!(hit_objects[2].kind, OsuFileHitObjectKind::Hold { end_time: 5250 });
+        assert_eq!(hit_objects[2].hit_sample, None);
+    }
+
+    #[test]
+    fn objects_without_exactly_one_type_are_noticed()
+    {
+        let config = OsuFileConfig { parse_hit_objects: true, ..Default::default() };
+        let (osu_file, _) = parse("osu file format v14\n\n[HitObjects]\n256,192,1000,3,0,B|300:300,1,100\n", config);
+
+        assert_eq!(osu_file.diagnostics[0].severity, ParseSeverity::Notice);
+        assert_eq!(osu_file.diagnostics[0].error.field.as_deref(), Some("type"));
+    }
+}
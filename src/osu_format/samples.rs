@@ -0,0 +1,216 @@
+use super::data::{
+    OsuFile,
+    OsuFileHitObject,
+    OsuFileHitObjectKind,
+    OsuFileHitSample,
+    OsuFileSampleSet,
+    OsuFileSlider,
+    OsuFileTimingPoint,
+    HIT_SOUND_WHISTLE,
+    HIT_SOUND_FINISH,
+    HIT_SOUND_CLAP
+};
+
+//NOTE: Osu! picks up a timing point slightly before it starts when playing hitsounds.
+const SAMPLE_LENIENCY: f32 = 5.0;
+
+///
+/// General todo's for this file:
+/// - Skin elements (hitcircle.png etc.) can be overridden by the beatmap as well.
+///
+impl OsuFile
+{
+    /// Lowercase names of every custom sample osu! could look up for this beatmap.
+    /// Names without an extension can be a .wav, .ogg or .mp3 file, names from a hit sample are exact.
+    /// Requires the general, difficulty, timing points and hit objects sections to be parsed.
+    pub fn hitsound_samples(&self) -> Vec<String>
+    {
+        let mut samples: Vec<String> = Vec::new();
+
+        for hit_object in self.hit_object_section.hit_objects.iter()
+        {
+            self.hit_object_samples(hit_object, &mut samples);
+        }
+
+        samples.sort();
+        samples.dedup();
+        samples
+    }
+
+    fn hit_object_samples(&self, hit_object: &OsuFileHitObject, samples: &mut Vec<String>)
+    {
+        let hit_sample: OsuFileHitSample = hit_object.hit_sample.clone().unwrap_or_default();
+        let start_time = hit_object.time as f32;
+
+        if !hit_sample.file_name.is_empty()
+        {
+            //NOTE: A custom file replaces the normal and addition sounds entirely.
+            samples.push(hit_sample.file_name.to_lowercase());
+        }
+        else
+        {
+            match &hit_object.kind
+            {
+                OsuFileHitObjectKind::Circle => self.push_hit_samples(start_time, hit_object.hit_sound, &hit_sample, samples),
+                OsuFileHitObjectKind::Spinner { end_time } => self.push_hit_samples(*end_time as f32, hit_object.hit_sound, &hit_sample, samples),
+                OsuFileHitObjectKind::Hold { .. } => self.push_hit_samples(start_time, hit_object.hit_sound, &hit_sample, samples),
+                OsuFileHitObjectKind::Slider(_) => { }
+            }
+        }
+
+        match &hit_object.kind
+        {
+            OsuFileHitObjectKind::Spinner { .. } =>
+            {
+                samples.push("spinnerspin".to_owned());
+                samples.push("spinnerbonus".to_owned());
+            },
+            OsuFileHitObjectKind::Slider(slider) => self.slider_samples(hit_object, slider, &hit_sample, samples),
+            _ => { }
+        }
+    }
+
+    fn slider_samples(&self, hit_object: &OsuFileHitObject, slider: &OsuFileSlider, hit_sample: &OsuFileHitSample, samples: &mut Vec<String>)
+    {
+        let start_time = hit_object.time as f32;
+        let slides: usize = slider.span_count() as usize;
+
+        let timing = self.slider_timing(hit_object).unwrap_or_default();
+        let span_duration = timing.span_duration as f32;
+        let end_time = timing.end_time as f32;
+
+        for edge in 0..=slides
+        {
+            let edge_time = start_time + span_duration * edge as f32;
+            let edge_sound = slider.edge_sounds.get(edge).copied().unwrap_or(hit_object.hit_sound);
+            let mut edge_sample = hit_sample.clone();
+
+            if let Some(edge_set) = slider.edge_sets.get(edge)
+            {
+                if edge_set.normal_set != 0 { edge_sample.normal_set = edge_set.normal_set; }
+                if edge_set.addition_set != 0 { edge_sample.addition_set = edge_set.addition_set; }
+            }
+
+            self.push_hit_samples(edge_time, edge_sound, &edge_sample, samples);
+        }
+
+        //NOTE: The slide, whistle and tick sounds play throughout the body,
+        //      so every timing point active during the slider can be used.
+        for timing_point in self.timing_points_between(start_time, end_time)
+        {
+            let index = if hit_sample.index != 0 { hit_sample.index } else { timing_point.sample_index };
+            let normal = self.resolve_sample_set(hit_sample.normal_set, Some(timing_point));
+            let addition = if hit_sample.addition_set != 0 { sample_set_name(&OsuFileSampleSet::from_u32(hit_sample.addition_set)) } else { normal };
+
+            if index == 0
+            {
+                continue;
+            }
+
+            samples.push(sample_name(normal, "sliderslide", index));
+            samples.push(sample_name(normal, "slidertick", index));
+
+            if hit_object.hit_sound & HIT_SOUND_WHISTLE != 0
+            {
+                samples.push(sample_name(addition, "sliderwhistle", index));
+            }
+        }
+    }
+
+    fn push_hit_samples(&self, time: f32, hit_sound: u8, hit_sample: &OsuFileHitSample, samples: &mut Vec<String>)
+    {
+        let timing_point = self.sample_timing_point_at(time);
+        let index = if hit_sample.index != 0 { hit_sample.index } else { timing_point.map(|point| point.sample_index).unwrap_or(0) };
+
+        //NOTE: Index 0 means osu! uses the skin's samples instead of the beatmap's.
+        if index == 0
+        {
+            return;
+        }
+
+        let normal = self.resolve_sample_set(hit_sample.normal_set, timing_point);
+        let addition = if hit_sample.addition_set != 0 { sample_set_name(&OsuFileSampleSet::from_u32(hit_sample.addition_set)) } else { normal };
+
+        samples.push(sample_name(normal, "hitnormal", index));
+
+        if hit_sound & HIT_SOUND_WHISTLE != 0 { samples.push(sample_name(addition, "hitwhistle", index)); }
+        if hit_sound & HIT_SOUND_FINISH != 0 { samples.push(sample_name(addition, "hitfinish", index)); }
+        if hit_sound & HIT_SOUND_CLAP != 0 { samples.push(sample_name(addition, "hitclap", index)); }
+    }
+
+    fn resolve_sample_set(&self, object_set: u32, timing_point: Option<&OsuFileTimingPoint>) -> &'static str
+    {
+        if object_set != 0
+        {
+            return sample_set_name(&OsuFileSampleSet::from_u32(object_set));
+        }
+
+        match timing_point
+        {
+            Some(point) if point.sample_set != OsuFileSampleSet::Default => sample_set_name(&point.sample_set),
+            _ => sample_set_name(&self.general_section.sample_set)
+        }
+    }
+
+    fn sample_timing_point_at(&self, time: f32) -> Option<&OsuFileTimingPoint>
+    {
+        self.timing_points_section.timing_point_at((time + SAMPLE_LENIENCY) as f64)
+    }
+
+    fn timing_points_between(&self, start: f32, end: f32) -> Vec<&OsuFileTimingPoint>
+    {
+        let mut points: Vec<&OsuFileTimingPoint> = self.sample_timing_point_at(start).into_iter().collect();
+
+        points.extend(self.timing_points_section.timing_points
+            .iter()
+            .filter(|point| point.time > start + SAMPLE_LENIENCY && point.time <= end + SAMPLE_LENIENCY));
+
+        points
+    }
+
+}
+
+fn sample_set_name(sample_set: &OsuFileSampleSet) -> &'static str
+{
+    match sample_set
+    {
+        OsuFileSampleSet::Soft => "soft",
+        OsuFileSampleSet::Drum => "drum",
+        _ => "normal"
+    }
+}
+
+fn sample_name(sample_set: &str, sound: &str, index: i32) -> String
+{
+    //NOTE: Index 1 uses the plain file name, higher indices get the index appended.
+    if index == 1
+    {
+        return format!("{}-{}", sample_set, sound);
+    }
+
+    format!("{}-{}{}", sample_set, sound, index)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::OsuFile;
+    use crate::osu_format::test_support::beatmap;
+
+    fn parse(hit_objects: &str) -> OsuFile
+    {
+        //NOTE: The timing point uses the soft sample set with custom index 2.
+        beatmap(&[("Difficulty", "SliderMultiplier:1\nSliderTickRate:1"), ("TimingPoints", "0,500,4,2,2,60,1,0"), ("HitObjects", hit_objects)])
+    }
+
+    #[test]
+    fn slider_edges_use_their_own_sounds()
+    {
+        let osu_file: OsuFile = parse("0,0,1000,2,0,L|200:0,1,200,2|8,0:0|3:0\n");
+        let samples: Vec<String> = osu_file.hitsound_samples();
+
+        assert!(samples.contains(&"soft-hitwhistle2".to_owned()));
+        assert!(samples.contains(&"drum-hitclap2".to_owned()));
+        assert!(samples.contains(&"soft-sliderslide2".to_owned()));
+    }
+}
@@ -0,0 +1,682 @@
+use std::f64::consts::PI;
+use std::ops::{Add, Sub};
+
+use super::data::{
+    OsuFile,
+    OsuFileCurvePoint,
+    OsuFileCurveType,
+    OsuFileHitObject,
+    OsuFileHitObjectKind,
+    OsuFileSlider,
+    OsuFileSliderPath,
+    OsuFileSliderTick,
+    OsuFileSliderTiming,
+    MAX_SLIDER_SLIDES
+};
+use super::difficulty::f16_decimal;
+
+//NOTE: The tolerances and detail osu! itself approximates the curves with.
+const BEZIER_TOLERANCE: f64 = 0.25;
+const CIRCULAR_ARC_TOLERANCE: f64 = 0.1;
+const CATMULL_DETAIL: usize = 50;
+
+const BASE_SCORING_DISTANCE: f64 = 100.0;
+const LEGACY_LAST_TICK_OFFSET: f64 = 36.0;
+const MAX_LENGTH: f64 = 100_000.0;
+
+/// A position or direction on the playfield, slider paths and the difficulty calculation are computed with these.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct Vector
+{
+    pub x: f64,
+    pub y: f64
+}
+
+impl Vector
+{
+    pub fn new(x: f64, y: f64) -> Vector
+    {
+        Vector { x, y }
+    }
+
+    pub fn scale(self, factor: f64) -> Vector
+    {
+        Vector::new(self.x * factor, self.y * factor)
+    }
+
+    pub fn dot(self, other: Vector) -> f64
+    {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the cross product, positive when other turns counter-clockwise from self.
+    pub fn cross(self, other: Vector) -> f64
+    {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(self) -> f64
+    {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalized(self) -> Vector
+    {
+        let length = self.length();
+        if length == 0.0 { self } else { self.scale(1.0 / length) }
+    }
+}
+
+impl Add for Vector
+{
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector
+    {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vector
+{
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector
+    {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl From<OsuFileCurvePoint> for Vector
+{
+    fn from(point: OsuFileCurvePoint) -> Vector
+    {
+        Vector::new(point.x as f64, point.y as f64)
+    }
+}
+
+impl From<Vector> for OsuFileCurvePoint
+{
+    fn from(vector: Vector) -> OsuFileCurvePoint
+    {
+        OsuFileCurvePoint { x: vector.x as f32, y: vector.y as f32 }
+    }
+}
+
+///
+/// General todo's for this file:
+/// - Stack leniency moves the positions of stacked objects, which the paths don't account for yet.
+///
+impl OsuFileSlider
+{
+    /// Approximates the curve starting at the head of the slider, then shortens or extends it to the declared length.
+    pub fn path(&self, head: OsuFileCurvePoint) -> OsuFileSliderPath
+    {
+        let mut control_points: Vec<Vector> = vec![Vector::from(head)];
+        control_points.extend(self.curve_points.iter().map(|point| Vector::from(*point)));
+
+        let mut points: Vec<Vector> = Vec::new();
+
+        //NOTE: A control point repeated twice is a red anchor, which starts a new segment.
+        let mut segment_start: usize = 0;
+
+        for index in 1..=control_points.len()
+        {
+            if index < control_points.len() && control_points[index] != control_points[index - 1]
+            {
+                continue;
+            }
+
+            let segment: &[Vector] = &control_points[segment_start..index];
+            segment_start = index;
+
+            for point in approximate_segment(self.curve_type, segment)
+            {
+                if points.last() != Some(&point)
+                {
+                    points.push(point);
+                }
+            }
+        }
+
+        if points.is_empty()
+        {
+            points.push(Vector::from(head));
+        }
+
+        let ends_in_anchor: bool = control_points.len() >= 2 && control_points[control_points.len() - 1] == control_points[control_points.len() - 2];
+        fit_length(points, self.length, ends_in_anchor)
+    }
+
+    /// The passes from head to tail and back, slides comes straight from the file so it is clamped to what osu! accepts.
+    /// Every span is sampled, so a corrupt count would otherwise take forever.
+    pub fn span_count(&self) -> u32
+    {
+        self.slides.clamp(1, MAX_SLIDER_SLIDES)
+    }
+}
+
+impl OsuFileSliderPath
+{
+    pub fn length(&self) -> f64
+    {
+        self.cumulative_length.last().copied().unwrap_or(0.0)
+    }
+
+    /// The position at progress 0 (the head) to 1 (the tail) along the path.
+    pub fn position_at(&self, progress: f64) -> OsuFileCurvePoint
+    {
+        let distance: f64 = progress.clamp(0.0, 1.0) * self.length();
+        let index: usize = self.cumulative_length.partition_point(|length| *length < distance);
+
+        if index == 0
+        {
+            return self.points.first().copied().unwrap_or_default();
+        }
+
+        if index >= self.points.len()
+        {
+            return self.points.last().copied().unwrap_or_default();
+        }
+
+        let start = Vector::from(self.points[index - 1]);
+        let end = Vector::from(self.points[index]);
+        let segment_length: f64 = self.cumulative_length[index] - self.cumulative_length[index - 1];
+
+        if segment_length <= 0.0
+        {
+            return start.into();
+        }
+
+        let fraction: f64 = (distance - self.cumulative_length[index - 1]) / segment_length;
+        start.add(end.sub(start).scale(fraction)).into()
+    }
+}
+
+impl OsuFile
+{
+    /// The timing of a slider, None for other hit objects.
+    /// Requires the general, difficulty and timing points sections to be parsed.
+    pub fn slider_timing(&self, hit_object: &OsuFileHitObject) -> Option<OsuFileSliderTiming>
+    {
+        let slider = match &hit_object.kind
+        {
+            OsuFileHitObjectKind::Slider(slider) => slider,
+            _ => return None
+        };
+
+        let start_time: f64 = hit_object.time as f64;
+        let path: OsuFileSliderPath = slider.path(OsuFileCurvePoint { x: hit_object.x as f32, y: hit_object.y as f32 });
+        let length: f64 = path.length().min(MAX_LENGTH);
+        let spans: u32 = slider.span_count();
+
+        let beat_length: f64 = self.timing_points_section.beat_length_at(start_time);
+        let slider_velocity: f64 = self.timing_points_section.slider_velocity_at(start_time);
+        let scoring_distance: f64 = BASE_SCORING_DISTANCE * f16_decimal(self.difficulty_section.slider_multiplier) * slider_velocity;
+        let velocity: f64 = if beat_length > 0.0 { scoring_distance / beat_length } else { 0.0 };
+        let span_duration: f64 = if velocity > 0.0 { length / velocity } else { 0.0 };
+
+        //NOTE: Before version 8 ticks did not get closer together with a higher slider velocity.
+        let tick_distance_multiplier: f64 = if self.format_version().unwrap_or(14) < 8 { 1.0 / slider_velocity } else { 1.0 };
+        let tick_rate: f64 = f16_decimal(self.difficulty_section.slider_tick_rate);
+        let tick_distance: f64 = if tick_rate > 0.0 { (scoring_distance / tick_rate * tick_distance_multiplier).clamp(0.0, length) } else { 0.0 };
+
+        //NOTE: Ticks closer than 10ms to the end of a span are left out.
+        let min_distance_from_end: f64 = velocity * 10.0;
+
+        let mut ticks: Vec<OsuFileSliderTick> = Vec::new();
+        let mut repeat_times: Vec<f64> = Vec::new();
+
+        for span in 0..spans
+        {
+            let span_start_time: f64 = start_time + span as f64 * span_duration;
+            let reversed: bool = span % 2 == 1;
+            let mut span_ticks: Vec<OsuFileSliderTick> = Vec::new();
+            let mut distance: f64 = tick_distance;
+
+            while tick_distance > 0.0 && distance <= length
+            {
+                if distance >= length - min_distance_from_end
+                {
+                    break;
+                }
+
+                let path_progress: f64 = distance / length;
+                let time_progress: f64 = if reversed { 1.0 - path_progress } else { path_progress };
+
+                span_ticks.push(OsuFileSliderTick { span, time: span_start_time + time_progress * span_duration, position: path.position_at(path_progress) });
+                distance += tick_distance;
+            }
+
+            if reversed
+            {
+                span_ticks.reverse();
+            }
+
+            ticks.extend(span_ticks);
+
+            if span < spans - 1
+            {
+                repeat_times.push(span_start_time + span_duration);
+            }
+        }
+
+        let total_duration: f64 = span_duration * spans as f64;
+        let final_span_start_time: f64 = start_time + (spans - 1) as f64 * span_duration;
+
+        Some(OsuFileSliderTiming
+        {
+            velocity,
+            span_duration,
+            end_time: start_time + total_duration,
+            end_position: path.position_at(if spans.is_multiple_of(2) { 0.0 } else { 1.0 }),
+            ticks,
+            repeat_times,
+            legacy_last_tick_time: (start_time + total_duration / 2.0).max(final_span_start_time + span_duration - LEGACY_LAST_TICK_OFFSET)
+        })
+    }
+
+    /// The time a hit object ends, for sliders this takes the timing points into account.
+    pub fn hit_object_end_time(&self, hit_object: &OsuFileHitObject) -> f64
+    {
+        match self.slider_timing(hit_object)
+        {
+            Some(timing) => timing.end_time,
+            None => hit_object.end_time() as f64
+        }
+    }
+}
+
+impl OsuFileSliderTiming
+{
+    /// The combo the slider gives: its head, ticks, repeats and tail.
+    pub fn combo(&self) -> usize
+    {
+        2 + self.ticks.len() + self.repeat_times.len()
+    }
+}
+
+fn approximate_segment(curve_type: OsuFileCurveType, segment: &[Vector]) -> Vec<Vector>
+{
+    match curve_type
+    {
+        OsuFileCurveType::Linear => segment.to_vec(),
+        OsuFileCurveType::Catmull => approximate_catmull(segment),
+        OsuFileCurveType::PerfectCircle if segment.len() == 3 =>
+        {
+            //NOTE: Osu! falls back to a bezier curve when the three points don't describe a usable circle.
+            approximate_circular_arc(segment).unwrap_or_else(|| approximate_bezier(segment))
+        },
+        _ => approximate_bezier(segment)
+    }
+}
+
+fn approximate_bezier(control_points: &[Vector]) -> Vec<Vector>
+{
+    let mut output: Vec<Vector> = Vec::new();
+
+    if control_points.len() < 3
+    {
+        return control_points.to_vec();
+    }
+
+    let mut to_flatten: Vec<Vec<Vector>> = vec![control_points.to_vec()];
+
+    while let Some(parent) = to_flatten.pop()
+    {
+        if bezier_is_flat_enough(&parent)
+        {
+            bezier_approximate(&parent, &mut output);
+            continue;
+        }
+
+        let (left, right) = bezier_subdivide(&parent);
+
+        //NOTE: The left half has to be flattened first, so it goes on the stack last.
+        to_flatten.push(right);
+        to_flatten.push(left);
+    }
+
+    output.push(control_points[control_points.len() - 1]);
+    output
+}
+
+fn bezier_is_flat_enough(control_points: &[Vector]) -> bool
+{
+    control_points.windows(3).all(|points|
+    {
+        let deviation = points[0].sub(points[1].scale(2.0)).add(points[2]);
+        deviation.dot(deviation) <= BEZIER_TOLERANCE * BEZIER_TOLERANCE * 4.0
+    })
+}
+
+/// Splits the curve in half with de Casteljau's algorithm.
+fn bezier_subdivide(control_points: &[Vector]) -> (Vec<Vector>, Vec<Vector>)
+{
+    let count: usize = control_points.len();
+    let mut midpoints: Vec<Vector> = control_points.to_vec();
+    let mut left: Vec<Vector> = vec![Vector::new(0.0, 0.0); count];
+    let mut right: Vec<Vector> = vec![Vector::new(0.0, 0.0); count];
+
+    for index in 0..count
+    {
+        left[index] = midpoints[0];
+        right[count - index - 1] = midpoints[count - index - 1];
+
+        for point in 0..count - index - 1
+        {
+            midpoints[point] = midpoints[point].add(midpoints[point + 1]).scale(0.5);
+        }
+    }
+
+    (left, right)
+}
+
+/// Adds the points of a curve that is flat enough, leaving out its last point.
+fn bezier_approximate(control_points: &[Vector], output: &mut Vec<Vector>)
+{
+    let count: usize = control_points.len();
+    let (mut left, right) = bezier_subdivide(control_points);
+
+    left.extend_from_slice(&right[1..]);
+    output.push(control_points[0]);
+
+    for index in 1..count - 1
+    {
+        let middle = 2 * index;
+        let point = left[middle - 1].add(left[middle].scale(2.0)).add(left[middle + 1]).scale(0.25);
+        output.push(point);
+    }
+}
+
+fn approximate_catmull(control_points: &[Vector]) -> Vec<Vector>
+{
+    let count: usize = control_points.len();
+    let mut output: Vec<Vector> = Vec::new();
+
+    for index in 0..count.saturating_sub(1)
+    {
+        let v1 = if index > 0 { control_points[index - 1] } else { control_points[index] };
+        let v2 = control_points[index];
+        let v3 = if index + 1 < count { control_points[index + 1] } else { v2.add(v2).sub(v1) };
+        let v4 = if index + 2 < count { control_points[index + 2] } else { v3.add(v3).sub(v2) };
+
+        for step in 0..CATMULL_DETAIL
+        {
+            output.push(catmull_point(v1, v2, v3, v4, step as f64 / CATMULL_DETAIL as f64));
+        }
+    }
+
+    if let Some(last) = control_points.last()
+    {
+        output.push(*last);
+    }
+
+    output
+}
+
+fn catmull_point(v1: Vector, v2: Vector, v3: Vector, v4: Vector, t: f64) -> Vector
+{
+    let t2: f64 = t * t;
+    let t3: f64 = t * t2;
+
+    let coordinate = |p1: f64, p2: f64, p3: f64, p4: f64| -> f64
+    {
+        0.5 * (2.0 * p2 + (-p1 + p3) * t + (2.0 * p1 - 5.0 * p2 + 4.0 * p3 - p4) * t2 + (-p1 + 3.0 * p2 - 3.0 * p3 + p4) * t3)
+    };
+
+    Vector::new(coordinate(v1.x, v2.x, v3.x, v4.x), coordinate(v1.y, v2.y, v3.y, v4.y))
+}
+
+/// The arc through three points, None when they are (nearly) on a line or the circle is too small to draw.
+fn approximate_circular_arc(control_points: &[Vector]) -> Option<Vec<Vector>>
+{
+    let (a, b, c) = (control_points[0], control_points[1], control_points[2]);
+
+    if ((b.y - a.y) * (c.x - a.x) - (b.x - a.x) * (c.y - a.y)).abs() < 1e-3
+    {
+        return None;
+    }
+
+    let d: f64 = 2.0 * (a.x * (b.y - c.y) + b.x * (c.y - a.y) + c.x * (a.y - b.y));
+    let (a_squared, b_squared, c_squared) = (a.dot(a), b.dot(b), c.dot(c));
+    let centre = Vector::new(
+        (a_squared * (b.y - c.y) + b_squared * (c.y - a.y) + c_squared * (a.y - b.y)) / d,
+        (a_squared * (c.x - b.x) + b_squared * (a.x - c.x) + c_squared * (b.x - a.x)) / d);
+
+    let radius: f64 = a.sub(centre).length();
+    let theta_start: f64 = (a.y - centre.y).atan2(a.x - centre.x);
+    let mut theta_end: f64 = (c.y - centre.y).atan2(c.x - centre.x);
+
+    while theta_end < theta_start
+    {
+        theta_end += 2.0 * PI;
+    }
+
+    let mut direction: f64 = 1.0;
+    let mut theta_range: f64 = theta_end - theta_start;
+
+    //NOTE: Go the other way around when b is not on the arc from a to c counter-clockwise.
+    let ortho_a_to_c = Vector::new(c.y - a.y, -(c.x - a.x));
+
+    if ortho_a_to_c.dot(b.sub(a)) < 0.0
+    {
+        direction = -1.0;
+        theta_range = 2.0 * PI - theta_range;
+    }
+
+    let point_count: usize = if 2.0 * radius <= CIRCULAR_ARC_TOLERANCE
+    {
+        2
+    }
+    else
+    {
+        let step: f64 = 2.0 * (1.0 - CIRCULAR_ARC_TOLERANCE / radius).acos();
+        ((theta_range / step).ceil() as usize).max(2)
+    };
+
+    let points: Vec<Vector> = (0..point_count)
+        .map(|index|
+        {
+            let theta: f64 = theta_start + direction * index as f64 / (point_count - 1) as f64 * theta_range;
+            centre.add(Vector::new(theta.cos(), theta.sin()).scale(radius))
+        })
+        .collect();
+
+    Some(points)
+}
+
+/// Cuts the path off at the declared length, or extends its last segment when the path is too short.
+fn fit_length(mut points: Vec<Vector>, expected_length: f64, ends_in_anchor: bool) -> OsuFileSliderPath
+{
+    let mut cumulative_length: Vec<f64> = vec![0.0];
+
+    for pair in points.windows(2)
+    {
+        let previous: f64 = *cumulative_length.last().unwrap_or(&0.0);
+        cumulative_length.push(previous + pair[1].sub(pair[0]).length());
+    }
+
+    let calculated_length: f64 = *cumulative_length.last().unwrap_or(&0.0);
+
+    //NOTE: Osu! does not extend a slider whose last two control points are the same.
+    let keep_calculated: bool = expected_length <= 0.0 || expected_length == calculated_length || (ends_in_anchor && expected_length > calculated_length);
+
+    if keep_calculated || points.len() < 2
+    {
+        return OsuFileSliderPath { points: points.into_iter().map(OsuFileCurvePoint::from).collect(), cumulative_length };
+    }
+
+    cumulative_length.pop();
+
+    while cumulative_length.len() > 1 && *cumulative_length.last().unwrap_or(&0.0) >= expected_length
+    {
+        cumulative_length.pop();
+        points.pop();
+    }
+
+    let end_index: usize = points.len() - 1;
+    let direction: Vector = points[end_index].sub(points[end_index - 1]).normalized();
+    let remaining: f64 = expected_length - *cumulative_length.last().unwrap_or(&0.0);
+
+    points[end_index] = points[end_index - 1].add(direction.scale(remaining));
+    cumulative_length.push(expected_length);
+
+    OsuFileSliderPath { points: points.into_iter().map(OsuFileCurvePoint::from).collect(), cumulative_length }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::{OsuFile, OsuFileCurvePoint, OsuFileCurveType, OsuFileSlider, OsuFileSliderPath, MAX_SLIDER_SLIDES};
+    use crate::osu_format::test_support::{beatmap, TIMING_POINTS};
+
+    fn parse(hit_objects: &str) -> OsuFile
+    {
+        beatmap(&[("Difficulty", "SliderMultiplier:1\nSliderTickRate:1"), ("TimingPoints", TIMING_POINTS), ("HitObjects", hit_objects)])
+    }
+
+    /// The path of a slider starting at 0:0, a length of 0 keeps the length of the curve itself.
+    fn slider_path(curve_type: OsuFileCurveType, curve_points: &[(f32, f32)], length: f64) -> OsuFileSliderPath
+    {
+        let slider = OsuFileSlider {
+            curve_type,
+            curve_points: curve_points.iter().map(|(x, y)| OsuFileCurvePoint { x: *x, y: *y }).collect(),
+            length,
+            ..Default::default()
+        };
+
+        slider.path(OsuFileCurvePoint { x: 0.0, y: 0.0 })
+    }
+
+    fn assert_near(point: OsuFileCurvePoint, x: f32, y: f32, tolerance: f32)
+    {
+        assert!((point.x - x).abs() <= tolerance && (point.y - y).abs() <= tolerance, "{:?} is not near {}:{}", point, x, y);
+    }
+
+    #[test]
+    fn slider_timing_follows_the_beat_length()
+    {
+        let osu_file: OsuFile = parse("0,0,1000,2,0,L|200:0,2,200\n");
+        let timing = osu_file.slider_timing(&osu_file.hit_object_section.hit_objects[0]).unwrap();
+
+        assert_eq!(timing.span_duration, 1000.0);
+        assert_eq!(timing.end_time, 3000.0);
+        assert_eq!(timing.repeat_times, vec![2000.0]);
+        assert_eq!(timing.ticks.iter().map(|tick| tick.time).collect::<Vec<f64>>(), vec![1500.0, 2500.0]);
+        assert_eq!(timing.end_position.x, 0.0);
+    }
+
+    #[test]
+    fn absurd_slide_counts_are_clamped()
+    {
+        let osu_file: OsuFile = parse("0,0,1000,2,0,L|200:0,4000000000,200\n");
+        let timing = osu_file.slider_timing(&osu_file.hit_object_section.hit_objects[0]).unwrap();
+
+        //NOTE: The slider survives parsing, only its timing is bounded.
+        assert!(osu_file.diagnostics.is_empty());
+        assert_eq!(timing.repeat_times.len(), MAX_SLIDER_SLIDES as usize - 1);
+    }
+
+    #[test]
+    fn bezier_curves_are_flattened()
+    {
+        let path: OsuFileSliderPath = slider_path(OsuFileCurveType::Bezier, &[(100.0, 100.0), (200.0, 0.0)], 0.0);
+
+        //NOTE: The arc length of this quadratic curve is 229.56.
+        assert!((path.length() - 229.56).abs() < 0.5, "{}", path.length());
+        assert_near(path.position_at(0.0), 0.0, 0.0, 0.0);
+        assert_near(path.position_at(0.5), 100.0, 50.0, 0.5);
+        assert_near(path.position_at(1.0), 200.0, 0.0, 0.0);
+        assert!(path.points.iter().all(|point| point.y >= 0.0 && point.y <= 50.0));
+    }
+
+    #[test]
+    fn perfect_circles_follow_the_arc()
+    {
+        let path: OsuFileSliderPath = slider_path(OsuFileCurveType::PerfectCircle, &[(100.0, 100.0), (200.0, 0.0)], 0.0);
+
+        //NOTE: The three points lie on the circle around 100:0 with a radius of 100, the arc is half of it.
+        assert!((path.length() - 100.0 * std::f64::consts::PI).abs() < 0.5, "{}", path.length());
+        assert_near(path.position_at(0.5), 100.0, 100.0, 0.5);
+        assert_near(path.position_at(1.0), 200.0, 0.0, 0.01);
+
+        for point in path.points.iter()
+        {
+            let radius: f32 = ((point.x - 100.0).powi(2) + point.y.powi(2)).sqrt();
+            assert!((radius - 100.0).abs() < 0.01, "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn degenerate_perfect_circles_fall_back_to_bezier()
+    {
+        let collinear: OsuFileSliderPath = slider_path(OsuFileCurveType::PerfectCircle, &[(100.0, 0.0), (200.0, 0.0)], 0.0);
+        let bezier: OsuFileSliderPath = slider_path(OsuFileCurveType::Bezier, &[(100.0, 0.0), (200.0, 0.0)], 0.0);
+
+        assert_eq!(collinear.points, bezier.points);
+        assert!((collinear.length() - 200.0).abs() < 1e-6);
+        assert!(collinear.points.iter().all(|point| point.y == 0.0));
+
+        //NOTE: A perfect circle needs exactly three points, more are drawn as a bezier curve as well.
+        let four_points: OsuFileSliderPath = slider_path(OsuFileCurveType::PerfectCircle, &[(100.0, 100.0), (200.0, 0.0), (300.0, 100.0)], 0.0);
+        let four_bezier: OsuFileSliderPath = slider_path(OsuFileCurveType::Bezier, &[(100.0, 100.0), (200.0, 0.0), (300.0, 100.0)], 0.0);
+        assert_eq!(four_points.points, four_bezier.points);
+    }
+
+    #[test]
+    fn catmull_curves_pass_through_their_points()
+    {
+        let path: OsuFileSliderPath = slider_path(OsuFileCurveType::Catmull, &[(100.0, 100.0), (200.0, 0.0)], 0.0);
+
+        assert!(path.points.contains(&OsuFileCurvePoint { x: 100.0, y: 100.0 }));
+        assert_eq!(path.points.first(), Some(&OsuFileCurvePoint { x: 0.0, y: 0.0 }));
+        assert_eq!(path.points.last(), Some(&OsuFileCurvePoint { x: 200.0, y: 0.0 }));
+        assert!(path.length() > 2.0 * 100.0 * std::f64::consts::SQRT_2);
+    }
+
+    #[test]
+    fn red_anchors_start_a_new_segment()
+    {
+        let path: OsuFileSliderPath = slider_path(OsuFileCurveType::Bezier, &[(100.0, 0.0), (100.0, 0.0), (100.0, 100.0)], 0.0);
+
+        //NOTE: Without the anchor this would be a curve, with it the path turns sharply at 100:0.
+        assert_eq!(path.points, vec![
+            OsuFileCurvePoint { x: 0.0, y: 0.0 },
+            OsuFileCurvePoint { x: 100.0, y: 0.0 },
+            OsuFileCurvePoint { x: 100.0, y: 100.0 }
+        ]);
+        assert_eq!(path.length(), 200.0);
+    }
+
+    #[test]
+    fn short_paths_are_extended()
+    {
+        let path: OsuFileSliderPath = slider_path(OsuFileCurveType::Linear, &[(100.0, 0.0)], 150.0);
+
+        assert_eq!(path.length(), 150.0);
+        assert_eq!(path.points.last(), Some(&OsuFileCurvePoint { x: 150.0, y: 0.0 }));
+    }
+
+    #[test]
+    fn long_paths_are_truncated()
+    {
+        let path: OsuFileSliderPath = slider_path(OsuFileCurveType::Linear, &[(100.0, 0.0), (100.0, 100.0)], 150.0);
+
+        assert_eq!(path.length(), 150.0);
+        assert_eq!(path.points.last(), Some(&OsuFileCurvePoint { x: 100.0, y: 50.0 }));
+
+        //NOTE: Points past the declared length are dropped, not just moved.
+        let past_a_point: OsuFileSliderPath = slider_path(OsuFileCurveType::Linear, &[(100.0, 0.0), (200.0, 0.0), (300.0, 0.0)], 50.0);
+        assert_eq!(past_a_point.points, vec![OsuFileCurvePoint { x: 0.0, y: 0.0 }, OsuFileCurvePoint { x: 50.0, y: 0.0 }]);
+    }
+
+    #[test]
+    fn paths_ending_in_an_anchor_are_not_extended()
+    {
+        let path: OsuFileSliderPath = slider_path(OsuFileCurveType::Linear, &[(100.0, 0.0), (100.0, 0.0)], 150.0);
+
+        assert_eq!(path.length(), 100.0);
+        assert_eq!(path.points.last(), Some(&OsuFileCurvePoint { x: 100.0, y: 0.0 }));
+    }
+}
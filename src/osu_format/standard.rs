@@ -0,0 +1,1400 @@
+use std::f64::consts::PI;
+use std::ops::{Add, Sub};
+
+use super::data::{
+    AdjustedDifficulty,
+    OsuFile,
+    OsuFileCurvePoint,
+    OsuFileHitObjectKind,
+    OsuFileSliderPath,
+    StandardDifficultyAttributes,
+    StandardPerformanceAttributes,
+    MOD_FLASHLIGHT,
+    MOD_HIDDEN,
+    MOD_NO_FAIL,
+    MOD_SPUN_OUT
+};
+use super::difficulty::{difficulty_range, strain_decay, weighted_strain_sum, StrainPeaks};
+use super::slider::Vector;
+
+const OBJECT_RADIUS: f64 = 64.0;
+//NOTE: osu! draws circles a tiny bit larger than the circle size says, to make up for a rounding error in the old playfield.
+const BROKEN_GAMEFIELD_ROUNDING_ALLOWANCE: f64 = 1.00041;
+const STACK_DISTANCE: f64 = 3.0;
+const STACK_OFFSET: f64 = -6.4;
+const TAIL_LENIENCY: f64 = 36.0;
+
+//NOTE: Distances are scaled as if every circle had this radius, so circle size does not change the aim difficulty of a pattern.
+const NORMALISED_RADIUS: f64 = 50.0;
+const NORMALISED_DIAMETER: f64 = NORMALISED_RADIUS * 2.0;
+const MIN_DELTA_TIME: f64 = 25.0;
+const MAXIMUM_SLIDER_RADIUS: f64 = NORMALISED_RADIUS * 2.4;
+const ASSUMED_SLIDER_RADIUS: f64 = NORMALISED_RADIUS * 1.8;
+
+const SECTION_LENGTH: f64 = 400.0;
+const DECAY_WEIGHT: f64 = 0.9;
+const REDUCED_STRAIN_BASELINE: f64 = 0.75;
+const DIFFICULTY_MULTIPLIER: f64 = 0.0675;
+const PERFORMANCE_BASE_MULTIPLIER: f64 = 1.14;
+
+const AIM_SKILL_MULTIPLIER: f64 = 23.55;
+const AIM_DECAY_BASE: f64 = 0.15;
+const AIM_REDUCED_SECTION_COUNT: usize = 10;
+const AIM_DIFFICULTY_MULTIPLIER: f64 = 1.06;
+const WIDE_ANGLE_MULTIPLIER: f64 = 1.5;
+const ACUTE_ANGLE_MULTIPLIER: f64 = 1.95;
+const SLIDER_MULTIPLIER: f64 = 1.35;
+const VELOCITY_CHANGE_MULTIPLIER: f64 = 0.75;
+
+const SPEED_SKILL_MULTIPLIER: f64 = 1375.0;
+const SPEED_DECAY_BASE: f64 = 0.3;
+const SPEED_REDUCED_SECTION_COUNT: usize = 5;
+const SPEED_DIFFICULTY_MULTIPLIER: f64 = 1.04;
+const SINGLE_SPACING_THRESHOLD: f64 = 125.0;
+const MIN_SPEED_BONUS: f64 = 75.0;
+const SPEED_BALANCING_FACTOR: f64 = 40.0;
+
+const RHYTHM_HISTORY_TIME_MAX: f64 = 5000.0;
+const RHYTHM_HISTORY_OBJECTS_MAX: usize = 32;
+const RHYTHM_MULTIPLIER: f64 = 0.75;
+
+const FLASHLIGHT_SKILL_MULTIPLIER: f64 = 0.052;
+const FLASHLIGHT_DECAY_BASE: f64 = 0.15;
+const FLASHLIGHT_DIFFICULTY_MULTIPLIER: f64 = 1.06;
+const FLASHLIGHT_MAX_OPACITY_BONUS: f64 = 0.4;
+const FLASHLIGHT_HIDDEN_BONUS: f64 = 0.2;
+const FLASHLIGHT_MIN_VELOCITY: f64 = 0.5;
+const FLASHLIGHT_SLIDER_MULTIPLIER: f64 = 1.3;
+const FLASHLIGHT_MIN_ANGLE_MULTIPLIER: f64 = 0.2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum NestedKind
+{
+    Head,
+    Tick,
+    Repeat,
+    Tail
+}
+
+#[derive(Clone, Copy, Debug)]
+struct NestedObject
+{
+    kind: NestedKind,
+    time: f64,
+    position: Vector
+}
+
+#[derive(Clone, Debug)]
+struct SliderObject
+{
+    path: OsuFileSliderPath,
+    span_duration: f64,
+    repeat_count: u32,
+    nested: Vec<NestedObject>,
+    lazy_end_position: Vector,
+    lazy_travel_distance: f64,
+    lazy_travel_time: f64
+}
+
+#[derive(Clone, Debug)]
+enum ObjectKind
+{
+    Circle,
+    Slider(Box<SliderObject>),
+    Spinner
+}
+
+/// A hit object the way the difficulty calculation needs it, times are not scaled by the clock rate.
+#[derive(Clone, Debug)]
+struct StandardObject
+{
+    start_time: f64,
+    end_time: f64,
+    position: Vector,
+    end_position: Vector,
+    stack_height: i32,
+    stack_offset: Vector,
+    kind: ObjectKind
+}
+
+impl StandardObject
+{
+    fn stacked_position(&self) -> Vector
+    {
+        self.position.add(self.stack_offset)
+    }
+
+    fn stacked_end_position(&self) -> Vector
+    {
+        self.end_position.add(self.stack_offset)
+    }
+
+    /// Where the cursor is when the player leaves the object, sliders don't have to be followed to their end.
+    fn end_cursor_position(&self) -> Vector
+    {
+        match &self.kind
+        {
+            ObjectKind::Slider(slider) => slider.lazy_end_position,
+            _ => self.stacked_position()
+        }
+    }
+
+    fn is_slider(&self) -> bool
+    {
+        matches!(self.kind, ObjectKind::Slider(_))
+    }
+
+    fn is_spinner(&self) -> bool
+    {
+        matches!(self.kind, ObjectKind::Spinner)
+    }
+}
+
+/// The movement to an object from the one before it, times are scaled by the clock rate.
+#[derive(Default, Clone, Debug)]
+struct DifficultyObject
+{
+    /// The index of the object in StandardCalculator::objects.
+    object: usize,
+    start_time: f64,
+    delta_time: f64,
+    strain_time: f64,
+    lazy_jump_distance: f64,
+    minimum_jump_distance: f64,
+    minimum_jump_time: f64,
+    travel_distance: f64,
+    travel_time: f64,
+    angle: Option<f64>,
+    hit_window_great: f64
+}
+
+struct StandardCalculator
+{
+    mods: u32,
+    difficulty: AdjustedDifficulty,
+    radius: f64,
+    preempt: f64,
+    fade_in: f64,
+    objects: Vec<StandardObject>,
+    difficulty_objects: Vec<DifficultyObject>
+}
+
+///
+/// General todo's for this file:
+/// - Beatmaps before version 6 stack their objects with an older algorithm.
+/// - The relax and autopilot mods.
+///
+impl OsuFile
+{
+    /// The osu!standard star rating with the mods, ported from osu!lazer 2022.1101.0, the release with the pp update of November 2022.
+    /// Requires the general, difficulty, timing points and hit objects sections to be parsed.
+    pub fn standard_difficulty(&self, mods: u32) -> StandardDifficultyAttributes
+    {
+        let difficulty: AdjustedDifficulty = self.adjusted_difficulty(mods);
+        let scale: f64 = (1.0 - 0.7 * (difficulty.circle_size - 5.0) / 5.0) / 2.0 * BROKEN_GAMEFIELD_ROUNDING_ALLOWANCE;
+        let preempt: f64 = difficulty_range(difficulty.approach_rate, 1800.0, 1200.0, 450.0);
+
+        let mut calculator = StandardCalculator
+        {
+            mods,
+            difficulty,
+            radius: OBJECT_RADIUS * scale,
+            preempt,
+            fade_in: 400.0 * (preempt / 450.0).min(1.0),
+            objects: Vec::new(),
+            difficulty_objects: Vec::new()
+        };
+
+        let mut max_combo: usize = 0;
+
+        for hit_object in self.hit_object_section.hit_objects.iter()
+        {
+            let position = Vector::new(hit_object.x as f64, hit_object.y as f64);
+            let start_time: f64 = hit_object.time as f64;
+
+            let object: StandardObject = match (&hit_object.kind, self.slider_timing(hit_object))
+            {
+                (OsuFileHitObjectKind::Slider(slider), Some(timing)) =>
+                {
+                    max_combo += timing.combo();
+
+                    let end_position = Vector::from(timing.end_position);
+                    let mut nested: Vec<NestedObject> = vec![NestedObject { kind: NestedKind::Head, time: start_time, position }];
+                    let spans: u32 = timing.repeat_times.len() as u32 + 1;
+                    let path: OsuFileSliderPath = slider.path(OsuFileCurvePoint { x: hit_object.x as f32, y: hit_object.y as f32 });
+
+                    for span in 0..spans
+                    {
+                        nested.extend(timing.ticks
+                            .iter()
+                            .filter(|tick| tick.span == span)
+                            .map(|tick| NestedObject { kind: NestedKind::Tick, time: tick.time, position: Vector::from(tick.position) }));
+
+                        if let Some(repeat_time) = timing.repeat_times.get(span as usize)
+                        {
+                            let repeat_position = Vector::from(path.position_at(if span % 2 == 0 { 1.0 } else { 0.0 }));
+                            nested.push(NestedObject { kind: NestedKind::Repeat, time: *repeat_time, position: repeat_position });
+                        }
+                    }
+
+                    nested.push(NestedObject { kind: NestedKind::Tail, time: timing.end_time, position: end_position });
+
+                    StandardObject
+                    {
+                        start_time,
+                        end_time: timing.end_time,
+                        position,
+                        end_position,
+                        stack_height: 0,
+                        stack_offset: Vector::default(),
+                        kind: ObjectKind::Slider(Box::new(SliderObject
+                        {
+                            path,
+                            span_duration: timing.span_duration,
+                            repeat_count: spans - 1,
+                            nested,
+                            lazy_end_position: position,
+                            lazy_travel_distance: 0.0,
+                            lazy_travel_time: 0.0
+                        }))
+                    }
+                },
+                (OsuFileHitObjectKind::Spinner { end_time }, _) =>
+                {
+                    max_combo += 1;
+                    StandardObject { start_time, end_time: *end_time as f64, position, end_position: position, stack_height: 0, stack_offset: Vector::default(), kind: ObjectKind::Spinner }
+                },
+                _ =>
+                {
+                    max_combo += 1;
+                    StandardObject { start_time, end_time: start_time, position, end_position: position, stack_height: 0, stack_offset: Vector::default(), kind: ObjectKind::Circle }
+                }
+            };
+
+            calculator.objects.push(object);
+        }
+
+        if calculator.objects.is_empty()
+        {
+            return StandardDifficultyAttributes { mods, ..Default::default() };
+        }
+
+        calculator.apply_stacking(self.general_section.stack_leniency as f64, scale);
+        calculator.compute_slider_cursor_positions();
+        calculator.create_difficulty_objects();
+
+        let mut attributes: StandardDifficultyAttributes = calculator.attributes();
+        attributes.max_combo = max_combo;
+        attributes
+    }
+}
+
+impl StandardCalculator
+{
+    /// Moves objects that are on top of each other up and to the left, so every one of them stays visible.
+    fn apply_stacking(&mut self, stack_leniency: f64, scale: f64)
+    {
+        let objects = &mut self.objects;
+        let stack_threshold: f64 = self.preempt * stack_leniency;
+        for i in (1..objects.len()).rev()
+        {
+            let mut n: usize = i;
+
+            if objects[i].stack_height != 0 || objects[i].is_spinner()
+            {
+                continue;
+            }
+
+            let mut object_i: usize = i;
+
+            if let ObjectKind::Circle = objects[i].kind
+            {
+                while n > 0
+                {
+                    n -= 1;
+
+                    if objects[n].is_spinner()
+                    {
+                        continue;
+                    }
+
+                    if objects[object_i].start_time - objects[n].end_time > stack_threshold
+                    {
+                        break;
+                    }
+
+                    //NOTE: A slider ending on the circle moves the whole stack after it away from the slider instead.
+                    if objects[n].is_slider() && distance(objects[n].end_position, objects[object_i].position) < STACK_DISTANCE
+                    {
+                        let offset: i32 = objects[object_i].stack_height - objects[n].stack_height + 1;
+
+                        for j in n + 1..=i
+                        {
+                            if distance(objects[n].end_position, objects[j].position) < STACK_DISTANCE
+                            {
+                                objects[j].stack_height -= offset;
+                            }
+                        }
+
+                        break;
+                    }
+
+                    if distance(objects[n].position, objects[object_i].position) < STACK_DISTANCE
+                    {
+                        objects[n].stack_height = objects[object_i].stack_height + 1;
+                        object_i = n;
+                    }
+                }
+            }
+            else if objects[i].is_slider()
+            {
+                while n > 0
+                {
+                    n -= 1;
+
+                    if objects[n].is_spinner()
+                    {
+                        continue;
+                    }
+
+                    if objects[object_i].start_time - objects[n].start_time > stack_threshold
+                    {
+                        break;
+                    }
+
+                    if distance(objects[n].end_position, objects[object_i].position) < STACK_DISTANCE
+                    {
+                        objects[n].stack_height = objects[object_i].stack_height + 1;
+                        object_i = n;
+                    }
+                }
+            }
+        }
+
+        for object in objects.iter_mut()
+        {
+            let offset: f64 = object.stack_height as f64 * scale * STACK_OFFSET;
+            object.stack_offset = Vector::new(offset, offset);
+        }
+    }
+
+    /// Follows every slider the laziest way a player can, only moving when the cursor would leave the follow circle.
+    fn compute_slider_cursor_positions(&mut self)
+    {
+        let scaling_factor: f64 = NORMALISED_RADIUS / self.radius;
+
+        for object in self.objects.iter_mut()
+        {
+            let head: Vector = object.stacked_position();
+            let offset: Vector = object.stack_offset;
+            let duration: f64 = object.end_time - object.start_time;
+            let start_time: f64 = object.start_time;
+
+            let slider = match &mut object.kind
+            {
+                ObjectKind::Slider(slider) => slider,
+                _ => continue
+            };
+
+            let mut nested: Vec<NestedObject> = slider.nested.iter().map(|nested| NestedObject { position: nested.position.add(offset), ..*nested }).collect();
+            let mut tracking_end_time: f64 = (start_time + duration - TAIL_LENIENCY).max(start_time + duration / 2.0);
+
+            //NOTE: A last tick after the tracking end goes after the tail, which osu! does as well to keep its values the same.
+            if let Some(last_tick) = nested.iter().rposition(|nested| nested.kind == NestedKind::Tick)
+            {
+                if nested[last_tick].time > tracking_end_time
+                {
+                    tracking_end_time = nested[last_tick].time;
+                    let tick: NestedObject = nested.remove(last_tick);
+                    nested.push(tick);
+                }
+            }
+
+            slider.lazy_travel_time = tracking_end_time - start_time;
+
+            let mut end_time_min: f64 = if slider.span_duration > 0.0 { slider.lazy_travel_time / slider.span_duration } else { 0.0 };
+            end_time_min = if end_time_min % 2.0 >= 1.0 { 1.0 - end_time_min % 1.0 } else { end_time_min % 1.0 };
+
+            slider.lazy_end_position = Vector::from(slider.path.position_at(end_time_min)).add(offset);
+
+            let mut cursor_position: Vector = head;
+
+            for (index, nested_object) in nested.iter().enumerate().skip(1)
+            {
+                let is_last: bool = index == nested.len() - 1;
+                let mut movement: Vector = nested_object.position.sub(cursor_position);
+                let mut required_movement: f64 = ASSUMED_SLIDER_RADIUS;
+
+                if is_last
+                {
+                    let lazy_movement: Vector = slider.lazy_end_position.sub(cursor_position);
+
+                    if lazy_movement.length() < movement.length()
+                    {
+                        movement = lazy_movement;
+                    }
+                }
+                else if nested_object.kind == NestedKind::Repeat
+                {
+                    required_movement = NORMALISED_RADIUS;
+                }
+
+                let mut movement_length: f64 = scaling_factor * movement.length();
+
+                if movement_length > required_movement
+                {
+                    let factor: f64 = (movement_length - required_movement) / movement_length;
+
+                    cursor_position = cursor_position.add(movement.scale(factor));
+                    movement_length *= factor;
+                    slider.lazy_travel_distance += movement_length;
+                }
+
+                if is_last
+                {
+                    slider.lazy_end_position = cursor_position;
+                }
+            }
+        }
+    }
+
+    fn create_difficulty_objects(&mut self)
+    {
+        let clock_rate: f64 = self.difficulty.clock_rate;
+        let hit_window_great: f64 = difficulty_range(self.difficulty.overall_difficulty, 80.0, 50.0, 20.0);
+
+        //NOTE: Smaller circles get a bonus on top of the normalisation, they are harder to aim at than the distance says.
+        let mut scaling_factor: f64 = NORMALISED_RADIUS / self.radius;
+
+        if self.radius < 30.0
+        {
+            scaling_factor *= 1.0 + (30.0 - self.radius).min(5.0) / 50.0;
+        }
+
+        for index in 1..self.objects.len()
+        {
+            let current = &self.objects[index];
+            let last = &self.objects[index - 1];
+            let last_last = if index >= 2 { Some(&self.objects[index - 2]) } else { None };
+
+            let delta_time: f64 = (current.start_time - last.start_time) / clock_rate;
+            let strain_time: f64 = delta_time.max(MIN_DELTA_TIME);
+
+            let mut difficulty_object = DifficultyObject
+            {
+                object: index,
+                start_time: current.start_time / clock_rate,
+                delta_time,
+                strain_time,
+                minimum_jump_time: strain_time,
+                hit_window_great: 2.0 * hit_window_great / clock_rate,
+                ..Default::default()
+            };
+
+            if let ObjectKind::Slider(slider) = &current.kind
+            {
+                difficulty_object.travel_distance = slider.lazy_travel_distance * (1.0 + slider.repeat_count as f64 / 2.5).powf(1.0 / 2.5);
+                difficulty_object.travel_time = (slider.lazy_travel_time / clock_rate).max(MIN_DELTA_TIME);
+            }
+
+            if !current.is_spinner() && !last.is_spinner()
+            {
+                let last_cursor_position: Vector = last.end_cursor_position();
+
+                difficulty_object.lazy_jump_distance = current.stacked_position().scale(scaling_factor).sub(last_cursor_position.scale(scaling_factor)).length();
+                difficulty_object.minimum_jump_distance = difficulty_object.lazy_jump_distance;
+
+                if let ObjectKind::Slider(last_slider) = &last.kind
+                {
+                    let last_travel_time: f64 = (last_slider.lazy_travel_time / clock_rate).max(MIN_DELTA_TIME);
+                    difficulty_object.minimum_jump_time = (strain_time - last_travel_time).max(MIN_DELTA_TIME);
+
+                    //NOTE: The cursor may already be on its way during the slider, so the jump starts from wherever is closer.
+                    let tail_jump_distance: f64 = last.stacked_end_position().sub(current.stacked_position()).length() * scaling_factor;
+                    difficulty_object.minimum_jump_distance = (difficulty_object.lazy_jump_distance - (MAXIMUM_SLIDER_RADIUS - ASSUMED_SLIDER_RADIUS))
+                        .min(tail_jump_distance - MAXIMUM_SLIDER_RADIUS)
+                        .max(0.0);
+                }
+
+                if let Some(last_last) = last_last.filter(|last_last| !last_last.is_spinner())
+                {
+                    let v1: Vector = last_last.end_cursor_position().sub(last.stacked_position());
+                    let v2: Vector = current.stacked_position().sub(last_cursor_position);
+
+                    difficulty_object.angle = Some(v1.cross(v2).atan2(v1.dot(v2)).abs());
+                }
+            }
+
+            self.difficulty_objects.push(difficulty_object);
+        }
+    }
+
+    fn attributes(&self) -> StandardDifficultyAttributes
+    {
+        let clock_rate: f64 = self.difficulty.clock_rate;
+
+        let aim_difficulty: f64 = self.aim_difficulty(true).sqrt() * DIFFICULTY_MULTIPLIER;
+        let aim_difficulty_without_sliders: f64 = self.aim_difficulty(false).sqrt() * DIFFICULTY_MULTIPLIER;
+        let (speed_difficulty, speed_note_count) = self.speed_difficulty();
+        let speed_difficulty: f64 = speed_difficulty.sqrt() * DIFFICULTY_MULTIPLIER;
+        let flashlight_difficulty: f64 = if self.mods & MOD_FLASHLIGHT != 0 { self.flashlight_difficulty().sqrt() * DIFFICULTY_MULTIPLIER } else { 0.0 };
+
+        let base_performance: f64 = (base_skill_performance(aim_difficulty).powf(1.1)
+            + base_skill_performance(speed_difficulty).powf(1.1)
+            + (flashlight_difficulty.powi(2) * 25.0).powf(1.1))
+            .powf(1.0 / 1.1);
+
+        let star_rating: f64 = if base_performance > 0.00001
+        {
+            PERFORMANCE_BASE_MULTIPLIER.cbrt() * 0.027 * ((100_000.0 / 2f64.powf(1.0 / 1.1) * base_performance).cbrt() + 4.0)
+        }
+        else
+        {
+            0.0
+        };
+
+        let preempt: f64 = self.preempt / clock_rate;
+        let hit_window_great: f64 = difficulty_range(self.difficulty.overall_difficulty, 80.0, 50.0, 20.0) / clock_rate;
+
+        StandardDifficultyAttributes
+        {
+            mods: self.mods,
+            star_rating,
+            aim_difficulty,
+            speed_difficulty,
+            flashlight_difficulty,
+            slider_factor: if aim_difficulty > 0.0 { aim_difficulty_without_sliders / aim_difficulty } else { 1.0 },
+            speed_note_count,
+            approach_rate: if preempt > 1200.0 { (1800.0 - preempt) / 120.0 } else { (1200.0 - preempt) / 150.0 + 5.0 },
+            overall_difficulty: (80.0 - hit_window_great) / 6.0,
+            drain_rate: self.difficulty.hp_drain_rate,
+            max_combo: 0,
+            circle_count: self.objects.iter().filter(|object| matches!(object.kind, ObjectKind::Circle)).count(),
+            slider_count: self.objects.iter().filter(|object| object.is_slider()).count(),
+            spinner_count: self.objects.iter().filter(|object| object.is_spinner()).count()
+        }
+    }
+
+    fn base_object(&self, index: usize) -> &StandardObject
+    {
+        &self.objects[self.difficulty_objects[index].object]
+    }
+
+    fn aim_difficulty(&self, with_sliders: bool) -> f64
+    {
+        let mut peaks = StrainPeaks::new(SECTION_LENGTH);
+        let mut current_strain: f64 = 0.0;
+
+        for (index, current) in self.difficulty_objects.iter().enumerate()
+        {
+            let previous_start_time: f64 = if index > 0 { self.difficulty_objects[index - 1].start_time } else { 0.0 };
+
+            peaks.start_sections_until(current.start_time, |time| current_strain * strain_decay(AIM_DECAY_BASE, time - previous_start_time));
+
+            current_strain *= strain_decay(AIM_DECAY_BASE, current.delta_time);
+            current_strain += self.evaluate_aim(index, with_sliders) * AIM_SKILL_MULTIPLIER;
+            peaks.add(current_strain);
+        }
+
+        reduced_strain_difficulty(peaks.peaks(), AIM_REDUCED_SECTION_COUNT) * AIM_DIFFICULTY_MULTIPLIER
+    }
+
+    /// The speed difficulty and how many notes count towards it.
+    fn speed_difficulty(&self) -> (f64, f64)
+    {
+        let mut peaks = StrainPeaks::new(SECTION_LENGTH);
+        let mut current_strain: f64 = 0.0;
+        let mut current_rhythm: f64 = 0.0;
+        let mut object_strains: Vec<f64> = Vec::new();
+
+        for (index, current) in self.difficulty_objects.iter().enumerate()
+        {
+            let previous_start_time: f64 = if index > 0 { self.difficulty_objects[index - 1].start_time } else { 0.0 };
+
+            peaks.start_sections_until(current.start_time, |time| current_strain * current_rhythm * strain_decay(SPEED_DECAY_BASE, time - previous_start_time));
+
+            current_strain *= strain_decay(SPEED_DECAY_BASE, current.strain_time);
+            current_strain += self.evaluate_speed(index) * SPEED_SKILL_MULTIPLIER;
+            current_rhythm = self.evaluate_rhythm(index);
+
+            let total_strain: f64 = current_strain * current_rhythm;
+            object_strains.push(total_strain);
+            peaks.add(total_strain);
+        }
+
+        let max_strain: f64 = object_strains.iter().copied().fold(0.0, f64::max);
+
+        //NOTE: Every note counts as much as its strain compared to the hardest one, smoothed out around half of it.
+        let speed_note_count: f64 = if max_strain > 0.0
+        {
+            object_strains.iter().map(|strain| 1.0 / (1.0 + (-(strain / max_strain * 12.0 - 6.0)).exp())).sum()
+        }
+        else
+        {
+            0.0
+        };
+
+        (reduced_strain_difficulty(peaks.peaks(), SPEED_REDUCED_SECTION_COUNT) * SPEED_DIFFICULTY_MULTIPLIER, speed_note_count)
+    }
+
+    fn flashlight_difficulty(&self) -> f64
+    {
+        let mut peaks = StrainPeaks::new(SECTION_LENGTH);
+        let mut current_strain: f64 = 0.0;
+        let hidden: bool = self.mods & MOD_HIDDEN != 0;
+
+        for (index, current) in self.difficulty_objects.iter().enumerate()
+        {
+            let previous_start_time: f64 = if index > 0 { self.difficulty_objects[index - 1].start_time } else { 0.0 };
+
+            peaks.start_sections_until(current.start_time, |time| current_strain * strain_decay(FLASHLIGHT_DECAY_BASE, time - previous_start_time));
+
+            current_strain *= strain_decay(FLASHLIGHT_DECAY_BASE, current.delta_time);
+            current_strain += self.evaluate_flashlight(index, hidden) * FLASHLIGHT_SKILL_MULTIPLIER;
+            peaks.add(current_strain);
+        }
+
+        peaks.peaks().iter().sum::<f64>() * FLASHLIGHT_DIFFICULTY_MULTIPLIER
+    }
+
+    /// How hard it is to move the cursor to the object, from its velocity, angle and the change in velocity.
+    fn evaluate_aim(&self, index: usize, with_sliders: bool) -> f64
+    {
+        if index <= 1 || self.base_object(index).is_spinner() || self.base_object(index - 1).is_spinner()
+        {
+            return 0.0;
+        }
+
+        let current = &self.difficulty_objects[index];
+        let last = &self.difficulty_objects[index - 1];
+        let last_last = &self.difficulty_objects[index - 2];
+        let last_is_slider: bool = self.base_object(index - 1).is_slider();
+
+        let mut current_velocity: f64 = current.lazy_jump_distance / current.strain_time;
+
+        if last_is_slider && with_sliders
+        {
+            let travel_velocity: f64 = last.travel_distance / last.travel_time;
+            let movement_velocity: f64 = current.minimum_jump_distance / current.minimum_jump_time;
+            current_velocity = current_velocity.max(movement_velocity + travel_velocity);
+        }
+
+        let mut previous_velocity: f64 = last.lazy_jump_distance / last.strain_time;
+
+        if self.base_object(index - 2).is_slider() && with_sliders
+        {
+            let travel_velocity: f64 = last_last.travel_distance / last_last.travel_time;
+            let movement_velocity: f64 = last.minimum_jump_distance / last.minimum_jump_time;
+            previous_velocity = previous_velocity.max(movement_velocity + travel_velocity);
+        }
+
+        let mut wide_angle_bonus: f64 = 0.0;
+        let mut acute_angle_bonus: f64 = 0.0;
+        let mut velocity_change_bonus: f64 = 0.0;
+        let mut aim_strain: f64 = current_velocity;
+
+        //NOTE: Angles only count when the rhythm stays about the same.
+        if current.strain_time.max(last.strain_time) < 1.25 * current.strain_time.min(last.strain_time)
+        {
+            if let (Some(current_angle), Some(last_angle), Some(last_last_angle)) = (current.angle, last.angle, last_last.angle)
+            {
+                let angle_bonus: f64 = current_velocity.min(previous_velocity);
+
+                wide_angle_bonus = wide_angle(current_angle);
+                acute_angle_bonus = acute_angle(current_angle);
+
+                if current.strain_time > 100.0
+                {
+                    acute_angle_bonus = 0.0;
+                }
+                else
+                {
+                    acute_angle_bonus *= acute_angle(last_angle)
+                        * angle_bonus.min(NORMALISED_DIAMETER * 1.25 / current.strain_time)
+                        * (PI / 2.0 * ((100.0 - current.strain_time) / 25.0).min(1.0)).sin().powi(2)
+                        * (PI / 2.0 * (current.lazy_jump_distance.clamp(NORMALISED_RADIUS, NORMALISED_DIAMETER) - NORMALISED_RADIUS) / NORMALISED_RADIUS).sin().powi(2);
+                }
+
+                //NOTE: Repeating the same angle is easier than changing it, so the bonus shrinks when the last angle was alike.
+                wide_angle_bonus *= angle_bonus * (1.0 - wide_angle_bonus.min(wide_angle(last_angle).powi(3)));
+                acute_angle_bonus *= 0.5 + 0.5 * (1.0 - acute_angle_bonus.min(acute_angle(last_last_angle).powi(3)));
+            }
+        }
+
+        if previous_velocity.max(current_velocity) != 0.0
+        {
+            //NOTE: Uses the velocities without the slider movement, slider bodies should not add to the change in velocity.
+            let previous_velocity: f64 = (last.lazy_jump_distance + last_last.travel_distance) / last.strain_time;
+            let current_velocity: f64 = (current.lazy_jump_distance + last.travel_distance) / current.strain_time;
+
+            let distance_ratio: f64 = (PI / 2.0 * (previous_velocity - current_velocity).abs() / previous_velocity.max(current_velocity)).sin().powi(2);
+            let overlap_velocity_buff: f64 = (NORMALISED_DIAMETER * 1.25 / current.strain_time.min(last.strain_time)).min((previous_velocity - current_velocity).abs());
+
+            velocity_change_bonus = overlap_velocity_buff * distance_ratio
+                * (current.strain_time.min(last.strain_time) / current.strain_time.max(last.strain_time)).powi(2);
+        }
+
+        aim_strain += (acute_angle_bonus * ACUTE_ANGLE_MULTIPLIER).max(wide_angle_bonus * WIDE_ANGLE_MULTIPLIER + velocity_change_bonus * VELOCITY_CHANGE_MULTIPLIER);
+
+        if last_is_slider && with_sliders
+        {
+            aim_strain += last.travel_distance / last.travel_time * SLIDER_MULTIPLIER;
+        }
+
+        aim_strain
+    }
+
+    /// How hard it is to tap the object in time, from the time since the last object and the distance to it.
+    fn evaluate_speed(&self, index: usize) -> f64
+    {
+        if self.base_object(index).is_spinner()
+        {
+            return 0.0;
+        }
+
+        let current = &self.difficulty_objects[index];
+        let mut strain_time: f64 = current.strain_time;
+
+        //NOTE: Notes that are hit with both hands alternating the rhythm, like 1/4 doubles, are easier than their speed says.
+        let mut doubletapness: f64 = 1.0;
+
+        if let Some(next) = self.difficulty_objects.get(index + 1)
+        {
+            let current_delta_time: f64 = current.delta_time.max(1.0);
+            let next_delta_time: f64 = next.delta_time.max(1.0);
+            let delta_difference: f64 = (next_delta_time - current_delta_time).abs();
+            let speed_ratio: f64 = current_delta_time / current_delta_time.max(delta_difference);
+            let window_ratio: f64 = (current_delta_time / current.hit_window_great).min(1.0).powi(2);
+
+            doubletapness = speed_ratio.powf(1.0 - window_ratio);
+        }
+
+        strain_time /= (strain_time / current.hit_window_great / 0.93).clamp(0.92, 1.0);
+
+        let mut speed_bonus: f64 = 1.0;
+
+        if strain_time < MIN_SPEED_BONUS
+        {
+            speed_bonus = 1.0 + 0.75 * ((MIN_SPEED_BONUS - strain_time) / SPEED_BALANCING_FACTOR).powi(2);
+        }
+
+        let travel_distance: f64 = if index > 0 { self.difficulty_objects[index - 1].travel_distance } else { 0.0 };
+        let distance: f64 = (travel_distance + current.minimum_jump_distance).min(SINGLE_SPACING_THRESHOLD);
+
+        (speed_bonus + speed_bonus * (distance / SINGLE_SPACING_THRESHOLD).powf(3.5)) * doubletapness / strain_time
+    }
+
+    /// How complicated the rhythm leading up to the object is, 1 for a constant rhythm.
+    fn evaluate_rhythm(&self, index: usize) -> f64
+    {
+        if self.base_object(index).is_spinner()
+        {
+            return 0.0;
+        }
+
+        let current = &self.difficulty_objects[index];
+        let previous = |offset: usize| &self.difficulty_objects[index - offset - 1];
+
+        let mut previous_island_size: usize = 0;
+        let mut rhythm_complexity_sum: f64 = 0.0;
+        let mut island_size: usize = 1;
+        let mut start_ratio: f64 = 0.0;
+        let mut first_delta_switch: bool = false;
+
+        let historical_note_count: usize = index.min(RHYTHM_HISTORY_OBJECTS_MAX);
+        let mut rhythm_start: usize = 0;
+
+        while rhythm_start + 2 < historical_note_count && current.start_time - previous(rhythm_start).start_time < RHYTHM_HISTORY_TIME_MAX
+        {
+            rhythm_start += 1;
+        }
+
+        for i in (1..=rhythm_start).rev()
+        {
+            let current_object = previous(i - 1);
+            let previous_object = previous(i);
+            let last_object = previous(i + 1);
+
+            let historical_decay: f64 = ((RHYTHM_HISTORY_TIME_MAX - (current.start_time - current_object.start_time)) / RHYTHM_HISTORY_TIME_MAX)
+                .min((historical_note_count - i) as f64 / historical_note_count as f64);
+
+            let current_delta: f64 = current_object.strain_time;
+            let previous_delta: f64 = previous_object.strain_time;
+            let last_delta: f64 = last_object.strain_time;
+
+            let current_ratio: f64 = 1.0 + 6.0 * (PI / (previous_delta.min(current_delta) / previous_delta.max(current_delta))).sin().powi(2).min(0.5);
+
+            let window_penalty: f64 = (((previous_delta - current_delta).abs() - current_object.hit_window_great * 0.3).max(0.0) / (current_object.hit_window_great * 0.3)).min(1.0);
+            let mut effective_ratio: f64 = window_penalty * current_ratio;
+
+            if first_delta_switch
+            {
+                if !(previous_delta > 1.25 * current_delta || previous_delta * 1.25 < current_delta)
+                {
+                    if island_size < 7
+                    {
+                        island_size += 1;
+                    }
+                }
+                else
+                {
+                    if self.objects[current_object.object].is_slider()
+                    {
+                        effective_ratio *= 0.125;
+                    }
+
+                    if self.objects[previous_object.object].is_slider()
+                    {
+                        effective_ratio *= 0.25;
+                    }
+
+                    if previous_island_size == island_size
+                    {
+                        effective_ratio *= 0.25;
+                    }
+
+                    if previous_island_size % 2 == island_size % 2
+                    {
+                        effective_ratio *= 0.5;
+                    }
+
+                    if last_delta > previous_delta + 10.0 && previous_delta > current_delta + 10.0
+                    {
+                        effective_ratio *= 0.125;
+                    }
+
+                    rhythm_complexity_sum += (effective_ratio * start_ratio).sqrt() * historical_decay
+                        * (4.0 + island_size as f64).sqrt() / 2.0
+                        * (4.0 + previous_island_size as f64).sqrt() / 2.0;
+
+                    start_ratio = effective_ratio;
+                    previous_island_size = island_size;
+
+                    if previous_delta * 1.25 < current_delta
+                    {
+                        first_delta_switch = false;
+                    }
+
+                    island_size = 1;
+                }
+            }
+            else if previous_delta > 1.25 * current_delta
+            {
+                first_delta_switch = true;
+                start_ratio = effective_ratio;
+                island_size = 1;
+            }
+        }
+
+        (4.0 + rhythm_complexity_sum * RHYTHM_MULTIPLIER).sqrt() / 2.0
+    }
+
+    /// How hard the object is to read with only a small area around the cursor visible, from the objects before it.
+    fn evaluate_flashlight(&self, index: usize, hidden: bool) -> f64
+    {
+        let object = self.base_object(index);
+
+        if object.is_spinner()
+        {
+            return 0.0;
+        }
+
+        let current = &self.difficulty_objects[index];
+        let scaling_factor: f64 = 52.0 / self.radius;
+
+        let mut small_distance_nerf: f64 = 1.0;
+        let mut cumulative_strain_time: f64 = 0.0;
+        let mut result: f64 = 0.0;
+        let mut last_strain_time: f64 = current.strain_time;
+        let mut angle_repeat_count: f64 = 0.0;
+
+        for i in 0..index.min(10)
+        {
+            let previous = &self.difficulty_objects[index - i - 1];
+            let previous_object = &self.objects[previous.object];
+
+            if !previous_object.is_spinner()
+            {
+                let jump_distance: f64 = object.stacked_position().sub(previous_object.stacked_end_position()).length();
+                cumulative_strain_time += last_strain_time;
+
+                if i == 0
+                {
+                    small_distance_nerf = (jump_distance / 75.0).min(1.0);
+                }
+
+                //NOTE: Stacked notes are easy to read, so their distance counts less.
+                let stack_nerf: f64 = (previous.lazy_jump_distance / scaling_factor / 25.0).min(1.0);
+                let opacity_bonus: f64 = 1.0 + FLASHLIGHT_MAX_OPACITY_BONUS * (1.0 - self.opacity_at(object, previous_object.start_time, hidden));
+
+                result += stack_nerf * opacity_bonus * scaling_factor * jump_distance / cumulative_strain_time;
+
+                if let (Some(previous_angle), Some(current_angle)) = (previous.angle, current.angle)
+                {
+                    if (previous_angle - current_angle).abs() < 0.02
+                    {
+                        angle_repeat_count += (1.0 - 0.1 * i as f64).max(0.0);
+                    }
+                }
+            }
+
+            last_strain_time = previous.strain_time;
+        }
+
+        result = (small_distance_nerf * result).powi(2);
+
+        if hidden
+        {
+            result *= 1.0 + FLASHLIGHT_HIDDEN_BONUS;
+        }
+
+        //NOTE: Patterns that keep the same angle are easier to remember.
+        result *= FLASHLIGHT_MIN_ANGLE_MULTIPLIER + (1.0 - FLASHLIGHT_MIN_ANGLE_MULTIPLIER) / (angle_repeat_count + 1.0);
+
+        if let ObjectKind::Slider(slider) = &object.kind
+        {
+            let pixel_travel_distance: f64 = slider.lazy_travel_distance / scaling_factor;
+            let mut slider_bonus: f64 = (pixel_travel_distance / current.travel_time - FLASHLIGHT_MIN_VELOCITY).max(0.0).sqrt() * pixel_travel_distance;
+
+            if slider.repeat_count > 0
+            {
+                slider_bonus /= (slider.repeat_count + 1) as f64;
+            }
+
+            result += slider_bonus * FLASHLIGHT_SLIDER_MULTIPLIER;
+        }
+
+        result
+    }
+
+    /// How visible the object is at the time, between 0 and 1. It is gone once it has to be hit.
+    fn opacity_at(&self, object: &StandardObject, time: f64, hidden: bool) -> f64
+    {
+        if time > object.start_time
+        {
+            return 0.0;
+        }
+
+        let fade_in_start_time: f64 = object.start_time - self.preempt;
+        let fade_in: f64 = ((time - fade_in_start_time) / self.fade_in).clamp(0.0, 1.0);
+
+        if hidden
+        {
+            let fade_out_start_time: f64 = object.start_time - self.preempt + self.fade_in;
+            let fade_out_duration: f64 = self.preempt * 0.3;
+
+            return fade_in.min(1.0 - ((time - fade_out_start_time) / fade_out_duration).clamp(0.0, 1.0));
+        }
+
+        fade_in
+    }
+}
+
+impl StandardDifficultyAttributes
+{
+    /// The performance points of a score, accuracy is between 0 and 1 and combo is the highest combo of the score.
+    /// The hit counts are guessed from the accuracy, with as many 300s as it allows.
+    pub fn performance(&self, accuracy: f64, combo: usize, misses: usize) -> StandardPerformanceAttributes
+    {
+        let total_hits: usize = self.circle_count + self.slider_count + self.spinner_count;
+
+        if total_hits == 0
+        {
+            return StandardPerformanceAttributes::default();
+        }
+
+        let misses: usize = misses.min(total_hits);
+        let hits: usize = total_hits - misses;
+
+        //NOTE: Every 300 is worth 6, a 100 2 and a 50 1. Replacing a 50 with a 300 adds 5 and with a 100 adds 1.
+        let target_total: i64 = (accuracy.clamp(0.0, 1.0) * total_hits as f64 * 6.0).round() as i64;
+        let delta: usize = (target_total - hits as i64).max(0) as usize;
+        let count_great: usize = (delta / 5).min(hits);
+        let count_ok: usize = (delta - count_great * 5).min(hits - count_great);
+        let count_meh: usize = hits - count_great - count_ok;
+
+        let score = StandardScore
+        {
+            count_great: count_great as f64,
+            count_ok: count_ok as f64,
+            count_meh: count_meh as f64,
+            count_miss: misses as f64,
+            combo: combo.min(self.max_combo) as f64,
+            accuracy: (count_great * 6 + count_ok * 2 + count_meh) as f64 / (total_hits * 6) as f64,
+            total_hits: total_hits as f64
+        };
+
+        let effective_miss_count: f64 = self.effective_miss_count(&score);
+        let mut multiplier: f64 = PERFORMANCE_BASE_MULTIPLIER;
+
+        if self.mods & MOD_NO_FAIL != 0
+        {
+            multiplier *= (1.0 - 0.02 * effective_miss_count).max(0.9);
+        }
+
+        if self.mods & MOD_SPUN_OUT != 0
+        {
+            multiplier *= 1.0 - (self.spinner_count as f64 / score.total_hits).powf(0.85);
+        }
+
+        let aim: f64 = self.aim_performance(&score, effective_miss_count);
+        let speed: f64 = self.speed_performance(&score, effective_miss_count);
+        let accuracy: f64 = self.accuracy_performance(&score);
+        let flashlight: f64 = self.flashlight_performance(&score, effective_miss_count);
+
+        let pp: f64 = (aim.powf(1.1) + speed.powf(1.1) + accuracy.powf(1.1) + flashlight.powf(1.1)).powf(1.0 / 1.1) * multiplier;
+
+        StandardPerformanceAttributes { pp, aim, speed, accuracy, flashlight, effective_miss_count }
+    }
+
+    fn aim_performance(&self, score: &StandardScore, effective_miss_count: f64) -> f64
+    {
+        let mut aim: f64 = base_skill_performance(self.aim_difficulty);
+        let length_bonus: f64 = length_bonus(score.total_hits);
+
+        aim *= length_bonus;
+
+        if effective_miss_count > 0.0
+        {
+            aim *= 0.97 * (1.0 - (effective_miss_count / score.total_hits).powf(0.775)).powf(effective_miss_count);
+        }
+
+        aim *= self.combo_scaling(score);
+
+        let approach_rate_factor: f64 = if self.approach_rate > 10.33
+        {
+            0.3 * (self.approach_rate - 10.33)
+        }
+        else if self.approach_rate < 8.0
+        {
+            0.05 * (8.0 - self.approach_rate)
+        }
+        else
+        {
+            0.0
+        };
+
+        aim *= 1.0 + approach_rate_factor * length_bonus;
+
+        if self.mods & MOD_HIDDEN != 0
+        {
+            aim *= 1.0 + 0.04 * (12.0 - self.approach_rate);
+        }
+
+        //NOTE: Scores that dropped slider ends probably did not follow the hard sliders, so the slider aim counts less.
+        let estimate_difficult_sliders: f64 = self.slider_count as f64 * 0.15;
+
+        if self.slider_count > 0
+        {
+            let estimate_slider_ends_dropped: f64 = (score.count_ok + score.count_meh + score.count_miss)
+                .min(self.max_combo as f64 - score.combo)
+                .clamp(0.0, estimate_difficult_sliders);
+
+            aim *= (1.0 - self.slider_factor) * (1.0 - estimate_slider_ends_dropped / estimate_difficult_sliders).powi(3) + self.slider_factor;
+        }
+
+        aim *= score.accuracy;
+        aim *= 0.98 + self.overall_difficulty.powi(2) / 2500.0;
+
+        aim
+    }
+
+    fn speed_performance(&self, score: &StandardScore, effective_miss_count: f64) -> f64
+    {
+        let mut speed: f64 = base_skill_performance(self.speed_difficulty);
+        let length_bonus: f64 = length_bonus(score.total_hits);
+
+        speed *= length_bonus;
+
+        if effective_miss_count > 0.0
+        {
+            speed *= 0.97 * (1.0 - (effective_miss_count / score.total_hits).powf(0.775)).powf(effective_miss_count.powf(0.875));
+        }
+
+        speed *= self.combo_scaling(score);
+
+        if self.approach_rate > 10.33
+        {
+            speed *= 1.0 + 0.3 * (self.approach_rate - 10.33) * length_bonus;
+        }
+
+        if self.mods & MOD_HIDDEN != 0
+        {
+            speed *= 1.0 + 0.04 * (12.0 - self.approach_rate);
+        }
+
+        //NOTE: Only the accuracy on the notes that count for speed matters, the misses and worse hits are put on those first.
+        let relevant_total_difference: f64 = score.total_hits - self.speed_note_count;
+        let relevant_count_great: f64 = (score.count_great - relevant_total_difference).max(0.0);
+        let relevant_count_ok: f64 = (score.count_ok - (relevant_total_difference - score.count_great).max(0.0)).max(0.0);
+        let relevant_count_meh: f64 = (score.count_meh - (relevant_total_difference - score.count_great - score.count_ok).max(0.0)).max(0.0);
+
+        let relevant_accuracy: f64 = if self.speed_note_count == 0.0
+        {
+            0.0
+        }
+        else
+        {
+            (relevant_count_great * 6.0 + relevant_count_ok * 2.0 + relevant_count_meh) / (self.speed_note_count * 6.0)
+        };
+
+        speed *= (0.95 + self.overall_difficulty.powi(2) / 750.0)
+            * ((score.accuracy + relevant_accuracy) / 2.0).powf((14.5 - self.overall_difficulty.max(8.0)) / 2.0);
+
+        let allowed_mehs: f64 = score.total_hits / 500.0;
+        speed *= 0.99f64.powf(if score.count_meh < allowed_mehs { 0.0 } else { score.count_meh - allowed_mehs });
+
+        speed
+    }
+
+    fn accuracy_performance(&self, score: &StandardScore) -> f64
+    {
+        //NOTE: Sliders and spinners are always a 300 as long as their head is hit, so only the circles say something about accuracy.
+        let circle_count: f64 = self.circle_count as f64;
+
+        let better_accuracy: f64 = if circle_count > 0.0
+        {
+            (((score.count_great - (score.total_hits - circle_count)) * 6.0 + score.count_ok * 2.0 + score.count_meh) / (circle_count * 6.0)).max(0.0)
+        }
+        else
+        {
+            0.0
+        };
+
+        let mut accuracy: f64 = 1.52163f64.powf(self.overall_difficulty) * better_accuracy.powi(24) * 2.83;
+        accuracy *= (circle_count / 1000.0).powf(0.3).min(1.15);
+
+        if self.mods & MOD_HIDDEN != 0
+        {
+            accuracy *= 1.08;
+        }
+
+        if self.mods & MOD_FLASHLIGHT != 0
+        {
+            accuracy *= 1.02;
+        }
+
+        accuracy
+    }
+
+    fn flashlight_performance(&self, score: &StandardScore, effective_miss_count: f64) -> f64
+    {
+        if self.mods & MOD_FLASHLIGHT == 0
+        {
+            return 0.0;
+        }
+
+        let mut flashlight: f64 = self.flashlight_difficulty.powi(2) * 25.0;
+
+        if effective_miss_count > 0.0
+        {
+            flashlight *= 0.97 * (1.0 - (effective_miss_count / score.total_hits).powf(0.775)).powf(effective_miss_count.powf(0.875));
+        }
+
+        flashlight *= self.combo_scaling(score);
+        flashlight *= 0.7 + 0.1 * (score.total_hits / 200.0).min(1.0)
+            + if score.total_hits > 200.0 { 0.2 * ((score.total_hits - 200.0) / 200.0).min(1.0) } else { 0.0 };
+        flashlight *= 0.5 + score.accuracy / 2.0;
+        flashlight *= 0.98 + self.overall_difficulty.powi(2) / 2500.0;
+
+        flashlight
+    }
+
+    /// The misses, or more when the combo says sliders were dropped without missing a circle.
+    fn effective_miss_count(&self, score: &StandardScore) -> f64
+    {
+        let mut combo_based_miss_count: f64 = 0.0;
+
+        if self.slider_count > 0
+        {
+            let full_combo_threshold: f64 = self.max_combo as f64 - 0.1 * self.slider_count as f64;
+
+            if score.combo < full_combo_threshold
+            {
+                combo_based_miss_count = full_combo_threshold / score.combo.max(1.0);
+            }
+        }
+
+        combo_based_miss_count = combo_based_miss_count.min(score.count_ok + score.count_meh + score.count_miss);
+        combo_based_miss_count.max(score.count_miss)
+    }
+
+    fn combo_scaling(&self, score: &StandardScore) -> f64
+    {
+        if self.max_combo == 0
+        {
+            return 1.0;
+        }
+
+        (score.combo.powf(0.8) / (self.max_combo as f64).powf(0.8)).min(1.0)
+    }
+}
+
+/// The hit counts of a score, as floats since that is all the formulas use them as.
+struct StandardScore
+{
+    count_great: f64,
+    count_ok: f64,
+    count_meh: f64,
+    count_miss: f64,
+    combo: f64,
+    accuracy: f64,
+    total_hits: f64
+}
+
+fn distance(a: Vector, b: Vector) -> f64
+{
+    a.sub(b).length()
+}
+
+/// Sums the strain peaks like every osu!standard skill does, the hardest sections are lowered a bit to keep single spikes from counting too much.
+fn reduced_strain_difficulty(peaks: Vec<f64>, reduced_section_count: usize) -> f64
+{
+    let mut strains: Vec<f64> = peaks.into_iter().filter(|strain| *strain > 0.0).collect();
+    strains.sort_by(|a, b| b.total_cmp(a));
+
+    for (index, strain) in strains.iter_mut().take(reduced_section_count).enumerate()
+    {
+        let progress: f64 = (index as f64 / reduced_section_count as f64).clamp(0.0, 1.0);
+        let scale: f64 = (1.0 + 9.0 * progress).log10();
+        *strain *= REDUCED_STRAIN_BASELINE + (1.0 - REDUCED_STRAIN_BASELINE) * scale;
+    }
+
+    weighted_strain_sum(strains, DECAY_WEIGHT)
+}
+
+fn base_skill_performance(difficulty: f64) -> f64
+{
+    (5.0 * (difficulty / DIFFICULTY_MULTIPLIER).max(1.0) - 4.0).powi(3) / 100_000.0
+}
+
+fn length_bonus(total_hits: f64) -> f64
+{
+    0.95 + 0.4 * (total_hits / 2000.0).min(1.0) + if total_hits > 2000.0 { (total_hits / 2000.0).log10() * 0.5 } else { 0.0 }
+}
+
+/// The bonus for angles wider than 30 degrees, the highest at 150 degrees and above.
+fn wide_angle(angle: f64) -> f64
+{
+    (3.0 / 4.0 * (angle.clamp(PI / 6.0, 5.0 / 6.0 * PI) - PI / 6.0)).sin().powi(2)
+}
+
+fn acute_angle(angle: f64) -> f64
+{
+    1.0 - wide_angle(angle)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::{
+        StandardDifficultyAttributes,
+        MOD_DOUBLE_TIME,
+        MOD_EASY,
+        MOD_FLASHLIGHT,
+        MOD_HALF_TIME,
+        MOD_HARD_ROCK,
+        MOD_HIDDEN
+    };
+    use crate::osu_format::test_support;
+
+    //NOTE: The fixture is made up to cover jumps, a stream, every curve type, repeats, a stack, a spinner and a slider velocity change.
+    //      The expected values are the output of this port, not values published by osu!, so they only guard against regressions.
+    const FIXTURE: &str = include_str!("../../tests/fixtures/standard.osu");
+
+    const THREE_CIRCLES: &str = "osu file format v14
+
+[Difficulty]
+CircleSize:4
+OverallDifficulty:8
+ApproachRate:9
+SliderMultiplier:1.4
+SliderTickRate:1
+
+[TimingPoints]
+0,500,4,2,0,60,1,0
+
+[HitObjects]
+100,100,1000,1,0,0:0:0:0:
+300,100,1500,1,0,0:0:0:0:
+300,300,2000,1,0,0:0:0:0:
+";
+
+    fn difficulty(text: &str, mods: u32) -> StandardDifficultyAttributes
+    {
+        test_support::parse(text).standard_difficulty(mods)
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64)
+    {
+        assert!((actual - expected).abs() <= tolerance, "expected {} within {}, got {}", expected, tolerance, actual);
+    }
+
+    #[test]
+    fn the_fixture_rates_the_same_with_every_mod()
+    {
+        //NOTE: Mods, star rating, aim, speed, pp of an SS and pp of 97% with two misses and 20 combo short.
+        let expected: [(u32, f64, f64, f64, f64, f64); 5] = [
+            (0, 6.004664, 3.271263, 2.275665, 228.601, 150.536),
+            (MOD_HARD_ROCK, 6.520496, 3.618365, 2.300180, 339.843, 209.252),
+            (MOD_EASY, 5.356638, 2.845706, 2.181077, 153.776, 111.151),
+            (MOD_DOUBLE_TIME, 8.187632, 4.420189, 3.195113, 580.993, 391.484),
+            (MOD_HALF_TIME, 4.896339, 2.686251, 1.808470, 119.685, 78.454)
+        ];
+
+        for (mods, star_rating, aim, speed, pp, pp_with_misses) in expected.iter()
+        {
+            let attributes = difficulty(FIXTURE, *mods);
+
+            assert_eq!(attributes.max_combo, 193);
+            assert_close(attributes.star_rating, *star_rating, 0.001);
+            assert_close(attributes.aim_difficulty, *aim, 0.001);
+            assert_close(attributes.speed_difficulty, *speed, 0.001);
+            assert_close(attributes.performance(1.0, 193, 0).pp, *pp, 0.01);
+            assert_close(attributes.performance(0.97, 173, 2).pp, *pp_with_misses, 0.01);
+        }
+    }
+
+    #[test]
+    fn the_fixture_is_counted_like_osu_does()
+    {
+        let attributes = difficulty(FIXTURE, 0);
+
+        assert_eq!((attributes.circle_count, attributes.slider_count, attributes.spinner_count), (96, 32, 1));
+        assert_eq!(attributes.flashlight_difficulty, 0.0);
+    }
+
+    #[test]
+    fn flashlight_adds_to_the_rating()
+    {
+        let without: StandardDifficultyAttributes = difficulty(FIXTURE, MOD_HIDDEN);
+        let with: StandardDifficultyAttributes = difficulty(FIXTURE, MOD_HIDDEN | MOD_FLASHLIGHT);
+
+        assert_close(with.flashlight_difficulty, 0.927738, 0.001);
+        assert_close(with.star_rating, 6.180929, 0.001);
+        assert!(with.star_rating > without.star_rating);
+        assert!(with.performance(1.0, 193, 0).pp > without.performance(1.0, 193, 0).pp);
+    }
+
+    #[test]
+    fn aim_needs_more_than_two_objects()
+    {
+        //NOTE: Osu! skips aim for the first two difficulty objects, so three circles only rate for speed and hard rock's smaller circles don't matter.
+        let no_mod: StandardDifficultyAttributes = difficulty(THREE_CIRCLES, 0);
+        let hard_rock: StandardDifficultyAttributes = difficulty(THREE_CIRCLES, MOD_HARD_ROCK);
+
+        assert_eq!(no_mod.aim_difficulty, 0.0);
+        assert_eq!(hard_rock.aim_difficulty, 0.0);
+        assert_eq!(hard_rock.star_rating, no_mod.star_rating);
+        assert!(difficulty(THREE_CIRCLES, MOD_HIDDEN | MOD_FLASHLIGHT).star_rating > no_mod.star_rating);
+    }
+
+    #[test]
+    fn empty_beatmaps_rate_zero()
+    {
+        let attributes = difficulty("osu file format v14\n\n[Difficulty]\nCircleSize:4\n", 0);
+
+        assert_eq!(attributes.star_rating, 0.0);
+        assert_eq!(attributes.max_combo, 0);
+    }
+}
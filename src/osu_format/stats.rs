@@ -0,0 +1,223 @@
+use super::data::{
+    BeatmapStats,
+    OsuFile,
+    OsuFileGamemode,
+    OsuFileHitObjectKind,
+    OsuFileTimingPoint
+};
+
+impl OsuFile
+{
+    /// Computes the song select statistics, this needs the general, difficulty, timing points and hit objects sections to be parsed.
+    pub fn stats(&self) -> BeatmapStats
+    {
+        let mut stats: BeatmapStats = BeatmapStats::default();
+        let hit_objects = &self.hit_object_section.hit_objects;
+
+        for hit_object in hit_objects.iter()
+        {
+            match hit_object.kind
+            {
+                OsuFileHitObjectKind::Circle => stats.circle_count += 1,
+                OsuFileHitObjectKind::Slider(_) => stats.slider_count += 1,
+                OsuFileHitObjectKind::Spinner { .. } => stats.spinner_count += 1,
+                OsuFileHitObjectKind::Hold { .. } => stats.hold_count += 1
+            }
+        }
+
+        //NOTE: Every gamemode gives combo for different things, a gamemode we don't know is counted like osu!standard.
+        stats.max_combo = match self.general_section.mode
+        {
+            OsuFileGamemode::Taiko => self.taiko_max_combo(),
+            OsuFileGamemode::Catch => self.catch_max_combo(),
+            OsuFileGamemode::Mania => self.mania_max_combo(),
+            OsuFileGamemode::Osu | OsuFileGamemode::Unknown => self.standard_max_combo()
+        };
+
+        let first_time: f64 = hit_objects.first().map(|hit_object| hit_object.time as f64).unwrap_or(0.0);
+        let last_time: f64 = hit_objects
+            .iter()
+            .map(|hit_object| self.hit_object_end_time(hit_object))
+            .fold(0.0, f64::max);
+
+        //NOTE: Only the part of a break that falls between the first and last object counts, breaks past the end would otherwise eat the drain time.
+        let break_time: f64 = self.events_section.breaks
+            .iter()
+            .map(|break_period| (break_period.end as f64).min(last_time) - (break_period.start as f64).max(first_time))
+            .filter(|duration| *duration > 0.0)
+            .sum();
+
+        stats.total_length = last_time;
+        stats.drain_time = (last_time - first_time - break_time).max(0.0);
+
+        let beat_lengths: Vec<f64> = self.uninherited_timing_points().map(|point| point.beat_length as f64).collect();
+
+        if !beat_lengths.is_empty()
+        {
+            stats.min_bpm = beat_lengths.iter().map(|beat_length| 60_000.0 / beat_length).fold(f64::MAX, f64::min);
+            stats.max_bpm = beat_lengths.iter().map(|beat_length| 60_000.0 / beat_length).fold(0.0, f64::max);
+            stats.dominant_bpm = 60_000.0 / self.dominant_beat_length(last_time);
+        }
+
+        stats
+    }
+
+    /// One for every object, plus the ticks and repeats of sliders.
+    fn standard_max_combo(&self) -> usize
+    {
+        self.hit_object_section.hit_objects
+            .iter()
+            .map(|hit_object| self.slider_timing(hit_object).map_or(1, |timing| timing.combo()))
+            .sum()
+    }
+
+    /// The beat length that lasts the longest until the end of the last hit object.
+    fn dominant_beat_length(&self, last_time: f64) -> f64
+    {
+        let timing_points: Vec<&OsuFileTimingPoint> = self.uninherited_timing_points().collect();
+        let mut durations: Vec<(f64, f64, f64)> = Vec::new();
+
+        for (index, point) in timing_points.iter().enumerate()
+        {
+            let time: f64 = point.time as f64;
+
+            //NOTE: The first timing point counts from the start of the audio, points after the last object do not count at all.
+            let duration: f64 = if time > last_time
+            {
+                0.0
+            }
+            else
+            {
+                let start: f64 = if index == 0 { 0.0 } else { time };
+                let end: f64 = timing_points.get(index + 1).map(|next| next.time as f64).unwrap_or(last_time);
+                end.min(last_time) - start
+            };
+
+            //NOTE: Beat lengths are compared to three decimals, so tiny differences from the editor still count as the same BPM.
+            let rounded: f64 = (point.beat_length as f64 * 1000.0).round() / 1000.0;
+
+            match durations.iter_mut().find(|(key, _, _)| *key == rounded)
+            {
+                Some((_, _, total)) => *total += duration,
+                None => durations.push((rounded, point.beat_length as f64, duration))
+            }
+        }
+
+        let mut dominant: (f64, f64, f64) = durations[0];
+
+        for entry in durations.into_iter().skip(1)
+        {
+            if entry.2 > dominant.2
+            {
+                dominant = entry;
+            }
+        }
+
+        dominant.1
+    }
+
+    fn uninherited_timing_points(&self) -> impl Iterator<Item = &OsuFileTimingPoint>
+    {
+        self.timing_points_section.timing_points
+            .iter()
+            .filter(|point| point.uninherited && point.beat_length > 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::BeatmapStats;
+    use crate::osu_format::test_support::{beatmap, TIMING_POINTS};
+
+    fn stats(events: &str, timing_points: &str, hit_objects: &str) -> BeatmapStats
+    {
+        beatmap(&[
+            ("Difficulty", "SliderMultiplier:1\nSliderTickRate:1"),
+            ("Events", events),
+            ("TimingPoints", timing_points),
+            ("HitObjects", hit_objects)
+        ]).stats()
+    }
+
+    fn max_combo(mode: u8, hit_objects: &str) -> usize
+    {
+        beatmap(&[
+            ("General", &format!("Mode: {}", mode)),
+            ("Difficulty", "SliderMultiplier:1\nSliderTickRate:1"),
+            ("TimingPoints", TIMING_POINTS),
+            ("HitObjects", hit_objects)
+        ]).stats().max_combo
+    }
+
+    //NOTE: A circle, a slider two beats long going back once and a spinner.
+    const MIXED: &str = "256,192,0,1,0\n0,0,1000,2,0,L|200:0,2,200\n256,192,4000,12,0,5000\n";
+
+    const CIRCLES: &str = "256,192,1000,1,0\n256,192,2000,1,0\n256,192,3000,1,0\n256,192,4000,1,0\n";
+
+    #[test]
+    fn objects_and_combo_are_counted()
+    {
+        //NOTE: At 100 px per beat the slider is two beats long, so it has a head, a tick at 1500, a repeat, another tick and a tail.
+        let stats = stats("", "0,500,4,2,0,60,1,0\n", "256,192,0,1,0\n0,0,1000,2,0,L|200:0,2,200\n256,192,4000,12,0,5000\n0,192,6000,128,0,6500:0:0:0:0:\n");
+
+        assert_eq!((stats.circle_count, stats.slider_count, stats.spinner_count, stats.hold_count), (1, 1, 1, 1));
+        assert_eq!(stats.max_combo, 1 + 5 + 1 + 1);
+        assert_eq!(stats.total_length, 6500.0);
+    }
+
+    #[test]
+    fn max_combo_follows_the_gamemode()
+    {
+        //NOTE: osu!standard counts every object and the ticks and repeats of sliders.
+        assert_eq!(max_combo(0, MIXED), 1 + 5 + 1);
+        //NOTE: osu!taiko only counts hits, the slider is a drum roll and the spinner a swell.
+        assert_eq!(max_combo(1, MIXED), 1);
+        //NOTE: osu!catch counts the fruits and droplets of the juice stream, but not the banana shower.
+        assert_eq!(max_combo(2, MIXED), 1 + 5);
+    }
+
+    #[test]
+    fn mania_holds_give_combo_while_they_are_held()
+    {
+        //NOTE: The hold lasts 500ms, which gives its head and five more.
+        assert_eq!(max_combo(3, "64,192,1000,1,0,0:0:0:0:\n448,192,2000,128,0,2500:0:0:0:0:\n"), 1 + 6);
+    }
+
+    #[test]
+    fn breaks_inside_the_map_are_left_out_of_the_drain_time()
+    {
+        let stats = stats("2,2000,3000\n", "0,500,4,2,0,60,1,0\n", CIRCLES);
+
+        assert_eq!(stats.total_length, 4000.0);
+        assert_eq!(stats.drain_time, 2000.0);
+    }
+
+    #[test]
+    fn breaks_are_clipped_to_the_objects()
+    {
+        assert_eq!(stats("2,5000,8000\n", "0,500,4,2,0,60,1,0\n", CIRCLES).drain_time, 3000.0);
+        assert_eq!(stats("2,0,2000\n2,3500,9000\n", "0,500,4,2,0,60,1,0\n", CIRCLES).drain_time, 1500.0);
+    }
+
+    #[test]
+    fn the_longest_bpm_is_dominant()
+    {
+        let stats = stats("", "0,500,4,2,0,60,1,0\n1000,250,4,2,0,60,1,0\n1200,-50,4,2,0,60,0,0\n1500,500,4,2,0,60,1,0\n10000,1000,4,2,0,60,1,0\n", CIRCLES);
+
+        assert_eq!(stats.min_bpm, 60.0);
+        assert_eq!(stats.max_bpm, 240.0);
+        //NOTE: 120 BPM lasts from the start of the audio to 1000 and from 1500 on, the point after the last object adds nothing.
+        assert_eq!(stats.dominant_bpm, 120.0);
+    }
+
+    #[test]
+    fn empty_beatmaps_have_empty_stats()
+    {
+        let stats = stats("", "", "");
+
+        assert_eq!(stats.max_combo, 0);
+        assert_eq!(stats.drain_time, 0.0);
+        assert_eq!(stats.dominant_bpm, 0.0);
+    }
+}
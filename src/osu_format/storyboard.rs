@@ -0,0 +1,614 @@
+use std::io::{BufRead, Read};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::data::{
+    OsuFileEncoding,
+    OsuFileStoryboard,
+    OsuFileStoryboardElement,
+    OsuFileStoryboardSprite,
+    OsuFileStoryboardAnimation,
+    OsuFileStoryboardSample,
+    OsuFileStoryboardCommand,
+    OsuFileStoryboardTween,
+    OsuFileStoryboardLayer,
+    OsuFileStoryboardOrigin,
+    OsuFileStoryboardLoopType,
+    OsuFileStoryboardParameter
+};
+use super::error::{ParseDiagnostic, ParseError, ParseErrorKind, ParseSeverity};
+
+///
+/// General todo's for this file:
+/// - Validate that the shorthand values of a command come in complete sets.
+///
+impl OsuFileStoryboard
+{
+    pub fn new() -> OsuFileStoryboard
+    {
+        OsuFileStoryboard::default()
+    }
+
+    /// Parses a standalone .osb file, which only consists of a [Variables] and [Events] section.
+    /// Returns the diagnostics of every line that had to be skipped.
+    pub fn parse(&mut self, file: PathBuf) -> Result<Vec<ParseDiagnostic>, ParseError>
+    {
+        let bytes: Vec<u8> = fs::read(&file)
+            .map_err(|err| ParseError::new(ParseErrorKind::Io(err.to_string())).at(Some(&file), 0, ""))?;
+
+        let (text, _) = OsuFileEncoding::decode(&bytes);
+        self.parse_lines(text.as_bytes(), Some(&file))
+    }
+
+    pub fn parse_reader<R: Read>(&mut self, mut reader: R) -> Result<Vec<ParseDiagnostic>, ParseError>
+    {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|err| ParseError::new(ParseErrorKind::Io(err.to_string())))?;
+
+        self.parse_bytes(&bytes)
+    }
+
+    pub fn parse_bytes(&mut self, bytes: &[u8]) -> Result<Vec<ParseDiagnostic>, ParseError>
+    {
+        let (text, _) = OsuFileEncoding::decode(bytes);
+        self.parse_lines(text.as_bytes(), None)
+    }
+
+    pub fn parse_str(&mut self, text: &str) -> Result<Vec<ParseDiagnostic>, ParseError>
+    {
+        let text: &str = text.strip_prefix('\u{feff}').unwrap_or(text);
+        self.parse_lines(text.as_bytes(), None)
+    }
+
+    fn parse_lines<R: BufRead>(&mut self, file_reader: R, file: Option<&PathBuf>) -> Result<Vec<ParseDiagnostic>, ParseError>
+    {
+        let mut context: String = String::new();
+        let mut diagnostics: Vec<ParseDiagnostic> = Vec::new();
+
+        for (line_index, line) in file_reader.lines().enumerate()
+        {
+            let line: String = line.map_err(|err| ParseError::new(ParseErrorKind::Io(err.to_string())).at(file, line_index + 1, &context))?;
+
+            if line.trim().is_empty() || line.starts_with("//")
+            {
+                continue;
+            }
+
+            if line.starts_with('[') && line.trim_end().ends_with(']')
+            {
+                context = line.trim().trim_matches(|c| c == '[' || c == ']').to_lowercase();
+                continue;
+            }
+
+            let result: Result<(), String> = match context.as_ref()
+            {
+                "variables" => self.parse_variable(&line),
+                "events" => self.parse_line(&line).map(|_| ()),
+                _ => Ok(())
+            };
+
+            if let Err(err) = result
+            {
+                let error = ParseError::new(ParseErrorKind::InvalidStoryboard(err)).at(file, line_index + 1, &context);
+                diagnostics.push(ParseDiagnostic { severity: ParseSeverity::Warning, error, text: line });
+            }
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Parses a "$name=value" line from the [Variables] section.
+    pub fn parse_variable(&mut self, line: &str) -> Result<(), String>
+    {
+        let trimmed = line.trim();
+
+        match trimmed.find('=')
+        {
+            Some(separator) if trimmed.starts_with('$') =>
+            {
+                let (name, value) = trimmed.split_at(separator);
+                self.variables.push((name.to_owned(), value[1..].to_owned()));
+
+                //NOTE: Substitute the longest names first, so $ab doesn't get replaced by $a.
+                self.variables.sort_by_key(|variable| std::cmp::Reverse(variable.0.len()));
+                Ok(())
+            },
+            _ => Err(format!("invalid variable: {}", line))
+        }
+    }
+
+    pub fn substitute_variables(&self, line: &str) -> String
+    {
+        let mut substituted: String = line.to_owned();
+
+        if !substituted.contains('$')
+        {
+            return substituted;
+        }
+
+        for (name, value) in self.variables.iter()
+        {
+            substituted = substituted.replace(name.as_str(), value);
+        }
+
+        substituted
+    }
+
+    /// Parses an element or command line of the [Events] section,
+    /// returns false when the line does not belong to the storyboard.
+    pub fn parse_line(&mut self, line: &str) -> Result<bool, String>
+    {
+        let line = self.substitute_variables(line);
+        let depth: usize = line.chars().take_while(|c| *c == ' ' || *c == '_').count();
+        let values: Vec<&str> = line[depth..].split(',').map(|value| value.trim()).collect();
+
+        if depth > 0
+        {
+            self.parse_command(depth, &values)?;
+            return Ok(true);
+        }
+
+        let element = match values[0]
+        {
+            "Sprite" | "4" =>
+            {
+                expect_values(&values, 6)?;
+                OsuFileStoryboardElement::Sprite(OsuFileStoryboardSprite
+                {
+                    layer: OsuFileStoryboardLayer::from_str(values[1])?,
+                    origin: OsuFileStoryboardOrigin::from_str(values[2])?,
+                    file_name: unquote(values[3]),
+                    x: parse_value::<f32>(values[4])?,
+                    y: parse_value::<f32>(values[5])?,
+                    commands: Vec::new()
+                })
+            },
+            "Animation" | "6" =>
+            {
+                expect_values(&values, 8)?;
+                OsuFileStoryboardElement::Animation(OsuFileStoryboardAnimation
+                {
+                    layer: OsuFileStoryboardLayer::from_str(values[1])?,
+                    origin: OsuFileStoryboardOrigin::from_str(values[2])?,
+                    file_name: unquote(values[3]),
+                    x: parse_value::<f32>(values[4])?,
+                    y: parse_value::<f32>(values[5])?,
+                    frame_count: parse_value::<u32>(values[6])?,
+                    frame_delay: parse_value::<f32>(values[7])?,
+                    loop_type: match values.get(8)
+                    {
+                        Some(loop_type) => OsuFileStoryboardLoopType::from_str(loop_type)?,
+                        None => OsuFileStoryboardLoopType::LoopForever
+                    },
+                    commands: Vec::new()
+                })
+            },
+            "Sample" | "5" =>
+            {
+                expect_values(&values, 4)?;
+                OsuFileStoryboardElement::Sample(OsuFileStoryboardSample
+                {
+                    time: parse_time(values[1])?,
+                    layer: OsuFileStoryboardLayer::from_str(values[2])?,
+                    file_name: unquote(values[3]),
+                    volume: match values.get(4)
+                    {
+                        Some(volume) => parse_value::<u32>(volume)?,
+                        None => 100
+                    }
+                })
+            },
+            _ => { return Ok(false); }
+        };
+
+        self.elements.push(element);
+        Ok(true)
+    }
+
+    fn parse_command(&mut self, depth: usize, values: &[&str]) -> Result<(), String>
+    {
+        let command = parse_command_values(values)?;
+
+        let element_commands: &mut Vec<OsuFileStoryboardCommand> = match self.elements.last_mut()
+        {
+            Some(OsuFileStoryboardElement::Sprite(sprite)) => &mut sprite.commands,
+            Some(OsuFileStoryboardElement::Animation(animation)) => &mut animation.commands,
+            _ => { return Err("command does not belong to a sprite or animation.".to_owned()); }
+        };
+
+        match depth
+        {
+            1 => element_commands.push(command),
+            2 => match element_commands.last_mut()
+            {
+                Some(OsuFileStoryboardCommand::Loop { commands, .. })
+                | Some(OsuFileStoryboardCommand::Trigger { commands, .. }) => commands.push(command),
+                _ => { return Err("nested command does not belong to a loop or trigger.".to_owned()); }
+            },
+            _ => { return Err(format!("commands can not be nested {} levels deep.", depth)); }
+        }
+
+        Ok(())
+    }
+
+    /// Every file the storyboard can display or play, animations expanded into their frames.
+    pub fn asset_file_names(&self) -> Vec<String>
+    {
+        let mut file_names: Vec<String> = Vec::new();
+
+        for element in self.elements.iter()
+        {
+            match element
+            {
+                OsuFileStoryboardElement::Sprite(sprite) => file_names.push(sprite.file_name.clone()),
+                OsuFileStoryboardElement::Animation(animation) => file_names.extend(animation.frame_file_names()),
+                OsuFileStoryboardElement::Sample(sample) => file_names.push(sample.file_name.clone())
+            }
+        }
+
+        file_names.sort();
+        file_names.dedup();
+        file_names
+    }
+
+    /// Like asset_file_names, but skips sprites and animations without commands, osu! never displays those.
+    pub fn used_asset_file_names(&self) -> Vec<String>
+    {
+        let mut file_names: Vec<String> = Vec::new();
+
+        for element in self.elements.iter()
+        {
+            match element
+            {
+                OsuFileStoryboardElement::Sprite(sprite) if !sprite.commands.is_empty() => file_names.push(sprite.file_name.clone()),
+                OsuFileStoryboardElement::Animation(animation) if !animation.commands.is_empty() => file_names.extend(animation.frame_file_names()),
+                OsuFileStoryboardElement::Sample(sample) => file_names.push(sample.file_name.clone()),
+                _ => { }
+            }
+        }
+
+        file_names.sort();
+        file_names.dedup();
+        file_names
+    }
+}
+
+fn parse_command_values(values: &[&str]) -> Result<OsuFileStoryboardCommand, String>
+{
+    match values[0]
+    {
+        "L" =>
+        {
+            expect_values(values, 3)?;
+            Ok(OsuFileStoryboardCommand::Loop
+            {
+                start_time: parse_time(values[1])?,
+                loop_count: parse_value::<u32>(values[2])?,
+                commands: Vec::new()
+            })
+        },
+        "T" =>
+        {
+            expect_values(values, 4)?;
+            Ok(OsuFileStoryboardCommand::Trigger
+            {
+                trigger: values[1].to_owned(),
+                start_time: parse_time(values[2])?,
+                end_time: parse_time(values[3])?,
+                group_number: match values.get(4)
+                {
+                    Some(group) => parse_value::<i32>(group)?,
+                    None => 0
+                },
+                commands: Vec::new()
+            })
+        },
+        "P" =>
+        {
+            expect_values(values, 5)?;
+            let start_time = parse_time(values[2])?;
+
+            Ok(OsuFileStoryboardCommand::Parameter
+            {
+                easing: parse_value::<u8>(values[1])?,
+                start_time,
+                end_time: if values[3].is_empty() { start_time } else { parse_time(values[3])? },
+                parameter: OsuFileStoryboardParameter::from_str(values[4])?
+            })
+        },
+        "F" => Ok(OsuFileStoryboardCommand::Fade(parse_tween(values, 1)?)),
+        "M" => Ok(OsuFileStoryboardCommand::Move(parse_tween(values, 2)?)),
+        "MX" => Ok(OsuFileStoryboardCommand::MoveX(parse_tween(values, 1)?)),
+        "MY" => Ok(OsuFileStoryboardCommand::MoveY(parse_tween(values, 1)?)),
+        "S" => Ok(OsuFileStoryboardCommand::Scale(parse_tween(values, 1)?)),
+        "V" => Ok(OsuFileStoryboardCommand::VectorScale(parse_tween(values, 2)?)),
+        "R" => Ok(OsuFileStoryboardCommand::Rotate(parse_tween(values, 1)?)),
+        "C" => Ok(OsuFileStoryboardCommand::Colour(parse_tween(values, 3)?)),
+        _ => Err(format!("unknown storyboard command {}.", values[0]))
+    }
+}
+
+/// Parses "event,easing,starttime,endtime,values...", an empty end time equals the start time.
+fn parse_tween(values: &[&str], value_count: usize) -> Result<OsuFileStoryboardTween, String>
+{
+    expect_values(values, 4 + value_count)?;
+
+    let start_time = parse_time(values[2])?;
+    let mut tween_values: Vec<f32> = Vec::new();
+
+    for value in values.iter().skip(4)
+    {
+        tween_values.push(parse_value::<f32>(value)?);
+    }
+
+    Ok(OsuFileStoryboardTween
+    {
+        easing: parse_value::<u8>(values[1])?,
+        start_time,
+        end_time: if values[3].is_empty() { start_time } else { parse_time(values[3])? },
+        values: tween_values
+    })
+}
+
+fn expect_values(values: &[&str], count: usize) -> Result<(), String>
+{
+    if values.len() < count
+    {
+        return Err(format!("expected at least {} values, got: {}", count, values.len()));
+    }
+
+    Ok(())
+}
+
+fn parse_value<T: FromStr>(value: &str) -> Result<T, String>
+{
+    value.parse::<T>().map_err(|_| format!("invalid value: {}", value))
+}
+
+fn parse_time(value: &str) -> Result<i32, String>
+{
+    //NOTE: Some storyboard generators write fractional times, osu! truncates those.
+    match value.parse::<i32>()
+    {
+        Ok(time) => Ok(time),
+        Err(_) => parse_value::<f64>(value).map(|time| time as i32)
+    }
+}
+
+fn unquote(value: &str) -> String
+{
+    value.trim_matches('"').to_owned()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::{
+        OsuFileStoryboard,
+        OsuFileStoryboardAnimation,
+        OsuFileStoryboardCommand,
+        OsuFileStoryboardElement,
+        OsuFileStoryboardLayer,
+        OsuFileStoryboardLoopType,
+        OsuFileStoryboardOrigin,
+        OsuFileStoryboardParameter,
+        MAX_ANIMATION_FRAMES
+    };
+
+    fn commands(storyboard: &OsuFileStoryboard) -> &Vec<OsuFileStoryboardCommand>
+    {
+        match storyboard.elements.last()
+        {
+            Some(OsuFileStoryboardElement::Sprite(sprite)) => &sprite.commands,
+            Some(OsuFileStoryboardElement::Animation(animation)) => &animation.commands,
+            _ => panic!("the last element has no commands")
+        }
+    }
+
+    #[test]
+    fn elements_are_parsed()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        assert_eq!(storyboard.parse_line(r#"Sprite,Foreground,Centre,"sb\light.png",320,240"#), Ok(true));
+        assert_eq!(storyboard.parse_line("Animation,Background,TopLeft,sb/star.png,0,0,3,50,LoopOnce"), Ok(true));
+        assert_eq!(storyboard.parse_line("5,1200,3,\"sb/hit.wav\",70"), Ok(true));
+        assert_eq!(storyboard.parse_line("0,0,\"bg.jpg\",0,0"), Ok(false));
+
+        match &storyboard.elements[..]
+        {
+            [OsuFileStoryboardElement::Sprite(sprite), OsuFileStoryboardElement::Animation(animation), OsuFileStoryboardElement::Sample(sample)] =>
+            {
+                assert_eq!(sprite.layer, OsuFileStoryboardLayer::Foreground);
+                assert_eq!(sprite.origin, OsuFileStoryboardOrigin::Centre);
+                assert_eq!(sprite.file_name, "sb\\light.png");
+                assert_eq!((sprite.x, sprite.y), (320.0, 240.0));
+
+                assert_eq!(animation.frame_count, 3);
+                assert_eq!(animation.frame_delay, 50.0);
+                assert_eq!(animation.loop_type, OsuFileStoryboardLoopType::LoopOnce);
+
+                assert_eq!(sample.time, 1200);
+                assert_eq!(sample.layer, OsuFileStoryboardLayer::Foreground);
+                assert_eq!(sample.file_name, "sb/hit.wav");
+                assert_eq!(sample.volume, 70);
+            },
+            elements => panic!("unexpected elements {:?}", elements)
+        }
+    }
+
+    #[test]
+    fn invalid_elements_are_rejected()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        assert!(storyboard.parse_line("Sprite,Foreground,Centre,sb/light.png,320").is_err());
+        assert!(storyboard.parse_line("Sprite,Sideways,Centre,sb/light.png,320,240").is_err());
+        assert!(storyboard.parse_line("Animation,Background,TopLeft,sb/star.png,0,0,-3,50").is_err());
+        assert!(storyboard.elements.is_empty());
+    }
+
+    #[test]
+    fn commands_are_parsed()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        storyboard.parse_line("Sprite,Foreground,Centre,sb/light.png,320,240").unwrap();
+        storyboard.parse_line(" F,0,1000,,1").unwrap();
+        storyboard.parse_line("_M,1,1000,2000,0,0,640,480").unwrap();
+        storyboard.parse_line(" C,0,1000.6,2000,255,128,0").unwrap();
+        storyboard.parse_line(" P,0,1000,,A").unwrap();
+
+        match &commands(&storyboard)[..]
+        {
+            [OsuFileStoryboardCommand::Fade(fade), OsuFileStoryboardCommand::Move(movement), OsuFileStoryboardCommand::Colour(colour), OsuFileStoryboardCommand::Parameter { start_time, end_time, parameter, .. }] =>
+            {
+                //NOTE: An empty end time equals the start time.
+                assert_eq!((fade.start_time, fade.end_time), (1000, 1000));
+                assert_eq!(fade.values, vec![1.0]);
+
+                assert_eq!(movement.easing, 1);
+                assert_eq!(movement.values, vec![0.0, 0.0, 640.0, 480.0]);
+
+                //NOTE: Fractional times are truncated.
+                assert_eq!(colour.start_time, 1000);
+                assert_eq!(colour.values, vec![255.0, 128.0, 0.0]);
+
+                assert_eq!((*start_time, *end_time), (1000, 1000));
+                assert_eq!(*parameter, OsuFileStoryboardParameter::AdditiveBlend);
+            },
+            commands => panic!("unexpected commands {:?}", commands)
+        }
+    }
+
+    #[test]
+    fn invalid_commands_are_rejected()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        //NOTE: Commands need an element to belong to.
+        assert!(storyboard.parse_line(" F,0,1000,,1").is_err());
+
+        storyboard.parse_line("Sprite,Foreground,Centre,sb/light.png,320,240").unwrap();
+
+        assert!(storyboard.parse_line(" X,0,1000,,1").is_err());
+        assert!(storyboard.parse_line(" M,0,1000,,1").is_err());
+        assert!(storyboard.parse_line(" F,0,soon,,1").is_err());
+        assert!(commands(&storyboard).is_empty());
+    }
+
+    #[test]
+    fn loops_and_triggers_nest_their_commands()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        storyboard.parse_line("Sprite,Foreground,Centre,sb/light.png,320,240").unwrap();
+        storyboard.parse_line(" L,500,4").unwrap();
+        storyboard.parse_line("  F,0,0,100,0,1").unwrap();
+        storyboard.parse_line("  S,0,0,100,1,2").unwrap();
+        storyboard.parse_line(" T,HitSoundClap,0,5000,1").unwrap();
+        storyboard.parse_line("__R,0,0,100,0,3.14").unwrap();
+        storyboard.parse_line(" MX,0,6000,,10").unwrap();
+
+        match &commands(&storyboard)[..]
+        {
+            [OsuFileStoryboardCommand::Loop { start_time, loop_count, commands: loop_commands }, OsuFileStoryboardCommand::Trigger { trigger, end_time, group_number, commands: trigger_commands, .. }, OsuFileStoryboardCommand::MoveX(_)] =>
+            {
+                assert_eq!((*start_time, *loop_count), (500, 4));
+                assert!(matches!(&loop_commands[..], [OsuFileStoryboardCommand::Fade(_), OsuFileStoryboardCommand::Scale(_)]));
+
+                assert_eq!(trigger, "HitSoundClap");
+                assert_eq!((*end_time, *group_number), (5000, 1));
+                assert!(matches!(&trigger_commands[..], [OsuFileStoryboardCommand::Rotate(_)]));
+            },
+            commands => panic!("unexpected commands {:?}", commands)
+        }
+    }
+
+    #[test]
+    fn nesting_outside_a_loop_is_rejected()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        storyboard.parse_line("Sprite,Foreground,Centre,sb/light.png,320,240").unwrap();
+        assert!(storyboard.parse_line("  F,0,0,100,0,1").is_err());
+
+        storyboard.parse_line(" L,0,2").unwrap();
+        assert!(storyboard.parse_line("   F,0,0,100,0,1").is_err());
+    }
+
+    #[test]
+    fn variables_are_substituted()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        storyboard.parse_variable("$a=sb/a.png").unwrap();
+        storyboard.parse_variable("$ab=sb/ab.png").unwrap();
+        assert!(storyboard.parse_variable("ab=missing dollar").is_err());
+
+        storyboard.parse_line("Sprite,Foreground,Centre,$ab,320,240").unwrap();
+        storyboard.parse_line("Sprite,Foreground,Centre,$a,320,240").unwrap();
+
+        let file_names: Vec<String> = storyboard.asset_file_names();
+        assert_eq!(file_names, vec!["sb/a.png", "sb/ab.png"]);
+    }
+
+    #[test]
+    fn asset_file_names_expand_animations()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        storyboard.parse_line("Animation,Background,TopLeft,sb/star.png,0,0,3,50").unwrap();
+        storyboard.parse_line("Animation,Background,TopLeft,sb/noext,0,0,2,50").unwrap();
+        storyboard.parse_line("Sprite,Foreground,Centre,sb/star0.png,320,240").unwrap();
+        storyboard.parse_line("Sample,0,0,hit.wav").unwrap();
+
+        assert_eq!(
+            storyboard.asset_file_names(),
+            vec!["hit.wav", "sb/noext0", "sb/noext1", "sb/star0.png", "sb/star1.png", "sb/star2.png"]
+        );
+    }
+
+    #[test]
+    fn static_elements_are_not_used()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        //NOTE: Osu! never displays an element without commands, so its file is dropped on purpose.
+        storyboard.parse_line("Sprite,Foreground,Centre,sb/static.png,320,240").unwrap();
+        storyboard.parse_line("Sprite,Foreground,Centre,sb/moving.png,320,240").unwrap();
+        storyboard.parse_line(" M,0,0,1000,0,0,640,480").unwrap();
+        storyboard.parse_line("Animation,Background,TopLeft,sb/idle.png,0,0,2,50").unwrap();
+        storyboard.parse_line("Sample,0,0,hit.wav").unwrap();
+
+        assert_eq!(storyboard.used_asset_file_names(), vec!["hit.wav", "sb/moving.png"]);
+        assert_eq!(storyboard.asset_file_names(), vec!["hit.wav", "sb/idle0.png", "sb/idle1.png", "sb/moving.png", "sb/static.png"]);
+    }
+
+    #[test]
+    fn used_animations_keep_every_frame()
+    {
+        let mut storyboard: OsuFileStoryboard = OsuFileStoryboard::new();
+
+        //NOTE: Commands nested in a loop still count, and every frame is kept even though only the base name is referenced.
+        storyboard.parse_line("Animation,Background,TopLeft,sb/star.png,0,0,3,50").unwrap();
+        storyboard.parse_line(" L,0,10").unwrap();
+        storyboard.parse_line("  F,0,0,100,0,1").unwrap();
+
+        assert_eq!(storyboard.used_asset_file_names(), vec!["sb/star0.png", "sb/star1.png", "sb/star2.png"]);
+    }
+
+    #[test]
+    fn absurd_frame_counts_are_capped()
+    {
+        let animation = OsuFileStoryboardAnimation { file_name: "sb/star.png".to_owned(), frame_count: u32::MAX, ..Default::default() };
+        let frames: Vec<String> = animation.frame_file_names();
+
+        assert_eq!(frames.len(), MAX_ANIMATION_FRAMES as usize);
+        assert_eq!(frames.last().unwrap(), &format!("sb/star{}.png", MAX_ANIMATION_FRAMES - 1));
+    }
+}
@@ -0,0 +1,710 @@
+use super::data::{
+    OsuFile,
+    OsuFileCurvePoint,
+    OsuFileGamemode,
+    OsuFileHitObject,
+    OsuFileHitObjectKind,
+    TaikoDifficultyAttributes,
+    HIT_SOUND_CLAP,
+    HIT_SOUND_WHISTLE
+};
+use super::difficulty::{difficulty_range, f16_decimal, strain_decay, weighted_strain_sum, StrainPeaks};
+
+const SECTION_LENGTH: f64 = 400.0;
+const DECAY_WEIGHT: f64 = 0.9;
+const DIFFICULTY_MULTIPLIER: f64 = 1.35;
+
+const FINAL_MULTIPLIER: f64 = 0.0625;
+const COLOUR_SKILL_MULTIPLIER: f64 = 0.375 * FINAL_MULTIPLIER;
+const STAMINA_SKILL_MULTIPLIER: f64 = 0.375 * FINAL_MULTIPLIER;
+const RHYTHM_SKILL_MULTIPLIER: f64 = 0.2 * FINAL_MULTIPLIER;
+
+const COLOUR_MULTIPLIER: f64 = 0.12;
+const COLOUR_DECAY_BASE: f64 = 0.8;
+const STAMINA_MULTIPLIER: f64 = 1.1;
+const STAMINA_DECAY_BASE: f64 = 0.4;
+const RHYTHM_MULTIPLIER: f64 = 10.0;
+const RHYTHM_DECAY_BASE: f64 = 0.0;
+const RHYTHM_STRAIN_DECAY: f64 = 0.96;
+const RHYTHM_HISTORY_LENGTH: usize = 8;
+
+/// Osu! converted the slider velocity of osu!standard to osu!taiko with this factor.
+const LEGACY_VELOCITY_MULTIPLIER: f64 = 1.4;
+const BASE_SCORING_DISTANCE: f64 = 100.0;
+const MAX_REPETITION_INTERVAL: usize = 16;
+
+/// The ratios between two intervals osu! recognises, with how hard the change of rhythm is.
+const COMMON_RHYTHMS: [(f64, f64); 9] = [
+    (1.0, 0.0),
+    (2.0, 0.3),
+    (1.0 / 2.0, 0.5),
+    (3.0, 0.3),
+    (1.0 / 3.0, 0.35),
+    //NOTE: Purposefully higher, this needs a hand switch when playing full alternate.
+    (3.0 / 2.0, 0.6),
+    (2.0 / 3.0, 0.4),
+    (5.0 / 4.0, 0.5),
+    (4.0 / 5.0, 0.7)
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TaikoHitType
+{
+    Centre,
+    Rim
+}
+
+/// An object after converting, drum rolls and swells have no hit type.
+#[derive(Clone, Copy, Debug)]
+struct TaikoObject
+{
+    time: f64,
+    hit_type: Option<TaikoHitType>
+}
+
+/// A taiko object with the object before it, times are scaled by the clock rate.
+#[derive(Clone, Copy, Debug)]
+struct TaikoDifficultyObject
+{
+    start_time: f64,
+    delta_time: f64,
+    hit_type: Option<TaikoHitType>,
+    /// Index into the common rhythms.
+    rhythm: usize,
+    /// Index into the notes of the same hit type.
+    mono_index: usize
+}
+
+/// Notes of the same colour in a row.
+#[derive(Clone, Debug)]
+struct MonoStreak
+{
+    first_object: usize,
+    hit_type: TaikoHitType,
+    run_length: usize,
+    parent: usize,
+    index: usize
+}
+
+/// Mono streaks of the same length in a row, which alternate in colour.
+#[derive(Clone, Debug)]
+struct AlternatingMonoPattern
+{
+    mono_streaks: Vec<usize>,
+    parent: usize,
+    index: usize
+}
+
+/// Alternating mono patterns grouped together, with how many patterns ago the same one was played.
+#[derive(Clone, Debug)]
+struct RepeatingHitPatterns
+{
+    alternating_mono_patterns: Vec<usize>,
+    repetition_interval: usize
+}
+
+///
+/// General todo's for this file:
+/// - Converts can be played with more than two keys per colour, which the stamina skill doesn't detect yet.
+///
+impl OsuFile
+{
+    /// The osu!taiko star rating with the mods, osu!standard beatmaps are converted the way osu! converts them.
+    /// None for osu!catch and osu!mania beatmaps, requires the general, difficulty, timing points and hit objects sections to be parsed.
+    pub fn taiko_difficulty(&self, mods: u32) -> Option<TaikoDifficultyAttributes>
+    {
+        let is_convert: bool = match self.general_section.mode
+        {
+            OsuFileGamemode::Taiko => false,
+            OsuFileGamemode::Osu => true,
+            _ => return None
+        };
+
+        let difficulty = self.adjusted_difficulty(mods);
+        let objects: Vec<TaikoObject> = self.taiko_objects(is_convert);
+        let difficulty_objects: Vec<TaikoDifficultyObject> = difficulty_objects(&objects, difficulty.clock_rate);
+
+        let colour_values: Vec<f64> = colour_values(&difficulty_objects);
+
+        let mut colour_peaks = StrainPeaks::new(SECTION_LENGTH);
+        let mut stamina_peaks = StrainPeaks::new(SECTION_LENGTH);
+        let mut rhythm_peaks = StrainPeaks::new(SECTION_LENGTH);
+        let mut colour_strain: f64 = 0.0;
+        let mut stamina_strain: f64 = 0.0;
+        let mut rhythm_strain: f64 = 0.0;
+        let mut rhythm = RhythmState::default();
+
+        for (index, current) in difficulty_objects.iter().enumerate()
+        {
+            let previous_start_time: f64 = current.start_time - current.delta_time;
+
+            colour_peaks.start_sections_until(current.start_time, |time| colour_strain * strain_decay(COLOUR_DECAY_BASE, time - previous_start_time));
+            stamina_peaks.start_sections_until(current.start_time, |time| stamina_strain * strain_decay(STAMINA_DECAY_BASE, time - previous_start_time));
+            rhythm_peaks.start_sections_until(current.start_time, |time| rhythm_strain * strain_decay(RHYTHM_DECAY_BASE, time - previous_start_time));
+
+            colour_strain *= strain_decay(COLOUR_DECAY_BASE, current.delta_time);
+            colour_strain += colour_values[index] * COLOUR_MULTIPLIER;
+            colour_peaks.add(colour_strain);
+
+            stamina_strain *= strain_decay(STAMINA_DECAY_BASE, current.delta_time);
+            stamina_strain += stamina_value(&difficulty_objects, index) * STAMINA_MULTIPLIER;
+            stamina_peaks.add(stamina_strain);
+
+            rhythm_strain *= strain_decay(RHYTHM_DECAY_BASE, current.delta_time);
+            rhythm_strain += rhythm.value(&difficulty_objects, index) * RHYTHM_MULTIPLIER;
+            rhythm_peaks.add(rhythm_strain);
+        }
+
+        let colour_peaks: Vec<f64> = colour_peaks.peaks();
+        let stamina_peaks: Vec<f64> = stamina_peaks.peaks();
+        let rhythm_peaks: Vec<f64> = rhythm_peaks.peaks();
+
+        //NOTE: Colour and stamina are combined first, so a section that is hard in both counts more than the sum of its parts.
+        let combined_peaks: Vec<f64> = (0..colour_peaks.len())
+            .map(|section|
+            {
+                let peak: f64 = norm(1.5, colour_peaks[section] * COLOUR_SKILL_MULTIPLIER, stamina_peaks[section] * STAMINA_SKILL_MULTIPLIER);
+                norm(2.0, peak, rhythm_peaks[section] * RHYTHM_SKILL_MULTIPLIER)
+            })
+            .filter(|peak| *peak > 0.0)
+            .collect();
+
+        let colour_rating: f64 = weighted_strain_sum(colour_peaks, DECAY_WEIGHT) * COLOUR_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+        let stamina_rating: f64 = weighted_strain_sum(stamina_peaks, DECAY_WEIGHT) * STAMINA_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+        let rhythm_rating: f64 = weighted_strain_sum(rhythm_peaks, DECAY_WEIGHT) * RHYTHM_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+        let combined_rating: f64 = weighted_strain_sum(combined_peaks, DECAY_WEIGHT) * DIFFICULTY_MULTIPLIER;
+
+        let mut star_rating: f64 = rescale(combined_rating * 1.4);
+
+        //NOTE: Converts can be played with more keys than the gamemode intends, which osu! makes up for with a flat penalty.
+        if is_convert
+        {
+            star_rating *= 0.925;
+
+            if colour_rating < 2.0 && stamina_rating > 8.0
+            {
+                star_rating *= 0.8;
+            }
+        }
+
+        Some(TaikoDifficultyAttributes
+        {
+            mods,
+            star_rating,
+            stamina_difficulty: stamina_rating,
+            rhythm_difficulty: rhythm_rating,
+            colour_difficulty: colour_rating,
+            peak_difficulty: combined_rating,
+            great_hit_window: difficulty_range(difficulty.overall_difficulty, 50.0, 35.0, 20.0) / difficulty.clock_rate,
+            max_combo: objects.iter().filter(|object| object.hit_type.is_some()).count(),
+            is_convert
+        })
+    }
+
+    /// The combo of a full combo on an osu!taiko beatmap, only hits count, drum rolls and swells give none.
+    pub fn taiko_max_combo(&self) -> usize
+    {
+        self.taiko_objects(false).iter().filter(|object| object.hit_type.is_some()).count()
+    }
+
+    /// Circles become hits, rims when they have a whistle or clap. Spinners become swells.
+    /// Sliders become drum rolls, but short and slow sliders of converts are split into hits.
+    fn taiko_objects(&self, is_convert: bool) -> Vec<TaikoObject>
+    {
+        let mut objects: Vec<TaikoObject> = Vec::new();
+
+        for hit_object in self.hit_object_section.hit_objects.iter()
+        {
+            let time: f64 = hit_object.time as f64;
+
+            match &hit_object.kind
+            {
+                OsuFileHitObjectKind::Circle => objects.push(TaikoObject { time, hit_type: Some(taiko_hit_type(hit_object.hit_sound)) }),
+                OsuFileHitObjectKind::Slider(slider) =>
+                {
+                    let spans: usize = slider.span_count() as usize;
+
+                    match self.converted_hit_spacing(hit_object, is_convert)
+                    {
+                        Some((duration, tick_spacing)) =>
+                        {
+                            //NOTE: The hits cycle through the sounds of the edges, missing edges use the sound of the slider.
+                            let sounds: Vec<u8> = (0..=spans)
+                                .map(|edge| slider.edge_sounds.get(edge).copied().unwrap_or(hit_object.hit_sound))
+                                .collect();
+
+                            let mut hit_time: f64 = time;
+                            let mut edge: usize = 0;
+
+                            while hit_time <= time + duration + tick_spacing / 8.0
+                            {
+                                objects.push(TaikoObject { time: hit_time, hit_type: Some(taiko_hit_type(sounds[edge])) });
+                                edge = (edge + 1) % sounds.len();
+                                hit_time += tick_spacing;
+                            }
+                        },
+                        None => objects.push(TaikoObject { time, hit_type: None })
+                    }
+                },
+                OsuFileHitObjectKind::Spinner { .. } | OsuFileHitObjectKind::Hold { .. } => objects.push(TaikoObject { time, hit_type: None })
+            }
+        }
+
+        objects.sort_by(|a, b| a.time.total_cmp(&b.time));
+        objects
+    }
+
+    /// The duration of the drum roll and the spacing of the hits when a slider of a convert is split into hits.
+    /// The calculation is the one osu! does, float errors included, or the hits wouldn't line up with it.
+    fn converted_hit_spacing(&self, hit_object: &OsuFileHitObject, is_convert: bool) -> Option<(f64, f64)>
+    {
+        let slider = match &hit_object.kind
+        {
+            OsuFileHitObjectKind::Slider(slider) => slider,
+            _ => return None
+        };
+
+        if !is_convert
+        {
+            return None;
+        }
+
+        let time: f64 = hit_object.time as f64;
+        let spans: f64 = slider.span_count() as f64;
+        let distance: f64 = slider.path(OsuFileCurvePoint { x: hit_object.x as f32, y: hit_object.y as f32 }).length() * spans * LEGACY_VELOCITY_MULTIPLIER;
+
+        let timing_beat_length: f64 = self.timing_points_section.beat_length_at(time);
+        let mut beat_length: f64 = timing_beat_length / self.timing_points_section.slider_velocity_at(time);
+
+        let slider_multiplier: f64 = f16_decimal(self.difficulty_section.slider_multiplier);
+        let tick_rate: f64 = f16_decimal(self.difficulty_section.slider_tick_rate);
+        let taiko_velocity: f64 = BASE_SCORING_DISTANCE * slider_multiplier / tick_rate * tick_rate;
+        let duration: f64 = (distance / taiko_velocity * beat_length).trunc();
+
+        let osu_velocity: f64 = taiko_velocity * (1000.0 / beat_length);
+
+        //NOTE: Since version 8 the beat length without the slider velocity decides the spacing.
+        if self.format_version().unwrap_or(14) >= 8
+        {
+            beat_length = timing_beat_length;
+        }
+
+        let tick_spacing: f64 = (beat_length / tick_rate).min(duration / spans);
+
+        if tick_spacing > 0.0 && distance / osu_velocity * 1000.0 < 2.0 * beat_length
+        {
+            Some((duration, tick_spacing))
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+fn taiko_hit_type(hit_sound: u8) -> TaikoHitType
+{
+    if hit_sound & (HIT_SOUND_WHISTLE | HIT_SOUND_CLAP) != 0
+    {
+        TaikoHitType::Rim
+    }
+    else
+    {
+        TaikoHitType::Centre
+    }
+}
+
+/// Every object from the third on, with the rhythm change from the two objects before it.
+fn difficulty_objects(objects: &[TaikoObject], clock_rate: f64) -> Vec<TaikoDifficultyObject>
+{
+    let mut difficulty_objects: Vec<TaikoDifficultyObject> = Vec::new();
+    let mut mono_counts: [usize; 2] = [0, 0];
+
+    for index in 2..objects.len()
+    {
+        let current: TaikoObject = objects[index];
+        let delta_time: f64 = (current.time - objects[index - 1].time) / clock_rate;
+        let previous_length: f64 = (objects[index - 1].time - objects[index - 2].time) / clock_rate;
+        let ratio: f64 = delta_time / previous_length;
+
+        let rhythm: usize = (0..COMMON_RHYTHMS.len())
+            .min_by(|a, b| (COMMON_RHYTHMS[*a].0 - ratio).abs().total_cmp(&(COMMON_RHYTHMS[*b].0 - ratio).abs()))
+            .unwrap_or(0);
+
+        let mono_index: usize = match current.hit_type
+        {
+            Some(hit_type) =>
+            {
+                let count: &mut usize = &mut mono_counts[hit_type as usize];
+                *count += 1;
+                *count - 1
+            },
+            None => 0
+        };
+
+        difficulty_objects.push(TaikoDifficultyObject { start_time: current.time / clock_rate, delta_time, hit_type: current.hit_type, rhythm, mono_index });
+    }
+
+    difficulty_objects
+}
+
+/// Encodes the colours of the notes into mono streaks, alternating mono patterns and repeating hit patterns.
+/// Every object that starts one of them gets its difficulty.
+fn colour_values(objects: &[TaikoDifficultyObject]) -> Vec<f64>
+{
+    let mut mono_streaks: Vec<MonoStreak> = Vec::new();
+
+    for (index, object) in objects.iter().enumerate()
+    {
+        let hit_type: TaikoHitType = match object.hit_type
+        {
+            Some(hit_type) => hit_type,
+            None => continue
+        };
+
+        match mono_streaks.last_mut()
+        {
+            Some(streak) if streak.hit_type == hit_type => streak.run_length += 1,
+            _ => mono_streaks.push(MonoStreak { first_object: index, hit_type, run_length: 1, parent: 0, index: 0 })
+        }
+    }
+
+    let mut alternating_mono_patterns: Vec<AlternatingMonoPattern> = Vec::new();
+
+    for index in 0..mono_streaks.len()
+    {
+        match alternating_mono_patterns.last_mut()
+        {
+            Some(pattern) if mono_streaks[index - 1].run_length == mono_streaks[index].run_length => pattern.mono_streaks.push(index),
+            _ => alternating_mono_patterns.push(AlternatingMonoPattern { mono_streaks: vec![index], parent: 0, index: 0 })
+        }
+    }
+
+    //NOTE: Two patterns apart is the same colour again, when those repeat the patterns in between are grouped.
+    let is_repetition = |a: usize, b: usize| -> bool
+    {
+        let first: &MonoStreak = &mono_streaks[alternating_mono_patterns[a].mono_streaks[0]];
+        let second: &MonoStreak = &mono_streaks[alternating_mono_patterns[b].mono_streaks[0]];
+
+        first.run_length == second.run_length
+            && first.hit_type == second.hit_type
+            && alternating_mono_patterns[a].mono_streaks.len() == alternating_mono_patterns[b].mono_streaks.len()
+    };
+
+    let mut repeating_hit_patterns: Vec<RepeatingHitPatterns> = Vec::new();
+    let mut index: usize = 0;
+
+    while index < alternating_mono_patterns.len()
+    {
+        let mut patterns: Vec<usize> = Vec::new();
+        let is_coupled = |index: usize| index + 2 < alternating_mono_patterns.len() && is_repetition(index, index + 2);
+
+        if is_coupled(index)
+        {
+            while is_coupled(index)
+            {
+                patterns.push(index);
+                index += 1;
+            }
+
+            patterns.push(index);
+            index += 1;
+        }
+
+        patterns.push(index);
+        index += 1;
+
+        repeating_hit_patterns.push(RepeatingHitPatterns { alternating_mono_patterns: patterns, repetition_interval: MAX_REPETITION_INTERVAL + 1 });
+    }
+
+    let same_mono_lengths = |a: &RepeatingHitPatterns, b: &RepeatingHitPatterns| -> bool
+    {
+        a.alternating_mono_patterns.len() == b.alternating_mono_patterns.len()
+            && a.alternating_mono_patterns.iter().zip(b.alternating_mono_patterns.iter()).take(2).all(|(a, b)|
+            {
+                mono_streaks[alternating_mono_patterns[*a].mono_streaks[0]].run_length == mono_streaks[alternating_mono_patterns[*b].mono_streaks[0]].run_length
+            })
+    };
+
+    for index in 0..repeating_hit_patterns.len()
+    {
+        let interval: Option<usize> = (1..MAX_REPETITION_INTERVAL.min(index + 1))
+            .find(|interval| same_mono_lengths(&repeating_hit_patterns[index], &repeating_hit_patterns[index - interval]));
+
+        repeating_hit_patterns[index].repetition_interval = interval.unwrap_or(MAX_REPETITION_INTERVAL + 1);
+    }
+
+    for (parent, repeating) in repeating_hit_patterns.iter().enumerate()
+    {
+        for (index, pattern) in repeating.alternating_mono_patterns.iter().enumerate()
+        {
+            alternating_mono_patterns[*pattern].parent = parent;
+            alternating_mono_patterns[*pattern].index = index;
+        }
+    }
+
+    for (parent, pattern) in alternating_mono_patterns.iter().enumerate()
+    {
+        for (index, streak) in pattern.mono_streaks.iter().enumerate()
+        {
+            mono_streaks[*streak].parent = parent;
+            mono_streaks[*streak].index = index;
+        }
+    }
+
+    let repeating_difficulty = |repeating: usize| 2.0 * (1.0 - sigmoid(repeating_hit_patterns[repeating].repetition_interval as f64, 2.0, 2.0, 0.5, 1.0));
+    let alternating_difficulty = |pattern: usize|
+    {
+        sigmoid(alternating_mono_patterns[pattern].index as f64, 2.0, 2.0, 0.5, 1.0) * repeating_difficulty(alternating_mono_patterns[pattern].parent)
+    };
+
+    let mut values: Vec<f64> = vec![0.0; objects.len()];
+
+    for streak in mono_streaks.iter()
+    {
+        values[streak.first_object] += sigmoid(streak.index as f64, 2.0, 2.0, 0.5, 1.0) * alternating_difficulty(streak.parent) * 0.5;
+    }
+
+    for (index, pattern) in alternating_mono_patterns.iter().enumerate()
+    {
+        values[mono_streaks[pattern.mono_streaks[0]].first_object] += alternating_difficulty(index);
+    }
+
+    for (index, repeating) in repeating_hit_patterns.iter().enumerate()
+    {
+        let first_pattern: &AlternatingMonoPattern = &alternating_mono_patterns[repeating.alternating_mono_patterns[0]];
+        values[mono_streaks[first_pattern.mono_streaks[0]].first_object] += repeating_difficulty(index);
+    }
+
+    values
+}
+
+/// A base strain for every note plus a bonus for how fast the same key is hit again, which is two notes of the same colour back.
+fn stamina_value(objects: &[TaikoDifficultyObject], index: usize) -> f64
+{
+    let current: &TaikoDifficultyObject = &objects[index];
+
+    let hit_type: TaikoHitType = match current.hit_type
+    {
+        Some(hit_type) if current.mono_index >= 2 => hit_type,
+        _ => return 0.0
+    };
+
+    let key_previous: Option<&TaikoDifficultyObject> = objects[..index]
+        .iter()
+        .rev()
+        .find(|object| object.hit_type == Some(hit_type) && object.mono_index == current.mono_index - 2);
+
+    match key_previous
+    {
+        Some(key_previous) => 0.5 + 30.0 / (current.start_time - key_previous.start_time).max(1.0),
+        None => 0.0
+    }
+}
+
+/// The rhythm skill keeps its own strain, which penalises rhythm changes that repeat, come too often or are too slow to matter.
+#[derive(Default, Clone, Debug)]
+struct RhythmState
+{
+    history: Vec<usize>,
+    current_strain: f64,
+    notes_since_rhythm_change: usize
+}
+
+impl RhythmState
+{
+    fn value(&mut self, objects: &[TaikoDifficultyObject], index: usize) -> f64
+    {
+        let current: &TaikoDifficultyObject = &objects[index];
+
+        //NOTE: Drum rolls and swells break the rhythm.
+        if current.hit_type.is_none()
+        {
+            self.reset();
+            return 0.0;
+        }
+
+        self.current_strain *= RHYTHM_STRAIN_DECAY;
+        self.notes_since_rhythm_change += 1;
+
+        let rhythm_difficulty: f64 = COMMON_RHYTHMS[current.rhythm].1;
+
+        if rhythm_difficulty == 0.0
+        {
+            return 0.0;
+        }
+
+        let mut object_strain: f64 = rhythm_difficulty;
+        object_strain *= self.repetition_penalties(objects, index);
+        object_strain *= pattern_length_penalty(self.notes_since_rhythm_change);
+        object_strain *= self.speed_penalty(current.delta_time);
+
+        self.notes_since_rhythm_change = 0;
+        self.current_strain += object_strain;
+        self.current_strain
+    }
+
+    /// Penalises the rhythm when the last few changes of rhythm were played the same way not long ago.
+    fn repetition_penalties(&mut self, objects: &[TaikoDifficultyObject], index: usize) -> f64
+    {
+        if self.history.len() == RHYTHM_HISTORY_LENGTH
+        {
+            self.history.remove(0);
+        }
+
+        self.history.push(index);
+
+        let mut penalty: f64 = 1.0;
+        let history: &[usize] = &self.history;
+
+        for pattern_length in 2..=RHYTHM_HISTORY_LENGTH / 2
+        {
+            if history.len() < pattern_length + 1
+            {
+                break;
+            }
+
+            let recent: &[usize] = &history[history.len() - pattern_length..];
+            let repeated_start: Option<usize> = (0..history.len() - pattern_length)
+                .rev()
+                .find(|start| (0..pattern_length).all(|offset| objects[history[start + offset]].rhythm == objects[recent[offset]].rhythm));
+
+            if let Some(start) = repeated_start
+            {
+                let notes_since: usize = index - history[start];
+                penalty *= (0.032 * notes_since as f64).min(1.0);
+            }
+        }
+
+        penalty
+    }
+
+    fn speed_penalty(&mut self, delta_time: f64) -> f64
+    {
+        if delta_time < 80.0
+        {
+            1.0
+        }
+        else if delta_time < 210.0
+        {
+            (1.4 - 0.005 * delta_time).max(0.0)
+        }
+        else
+        {
+            self.reset();
+            0.0
+        }
+    }
+
+    fn reset(&mut self)
+    {
+        self.current_strain = 0.0;
+        self.notes_since_rhythm_change = 0;
+    }
+}
+
+fn pattern_length_penalty(pattern_length: usize) -> f64
+{
+    let short_pattern_penalty: f64 = (0.15 * pattern_length as f64).min(1.0);
+    let long_pattern_penalty: f64 = (2.5 - 0.15 * pattern_length as f64).clamp(0.0, 1.0);
+    short_pattern_penalty.min(long_pattern_penalty)
+}
+
+fn sigmoid(value: f64, center: f64, width: f64, middle: f64, height: f64) -> f64
+{
+    (std::f64::consts::E * -(value - center) / width).tanh() * (height / 2.0) + middle
+}
+
+fn norm(p: f64, a: f64, b: f64) -> f64
+{
+    (a.powf(p) + b.powf(p)).powf(1.0 / p)
+}
+
+/// Spreads the star ratings out, so they line up with the ratings of the other gamemodes.
+fn rescale(star_rating: f64) -> f64
+{
+    if star_rating < 0.0
+    {
+        star_rating
+    }
+    else
+    {
+        10.43 * (star_rating / 8.0 + 1.0).ln()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::TaikoHitType;
+    use crate::osu_format::data::{OsuFile, MOD_DOUBLE_TIME};
+    use crate::osu_format::test_support::{self, TIMING_POINTS};
+
+    fn beatmap(mode: u8, hit_objects: &str) -> OsuFile
+    {
+        test_support::beatmap(&[
+            ("General", &format!("Mode: {}", mode)),
+            ("Difficulty", "OverallDifficulty:5\nSliderMultiplier:1.4\nSliderTickRate:1"),
+            ("TimingPoints", TIMING_POINTS),
+            ("HitObjects", hit_objects)
+        ])
+    }
+
+    fn hit_types(osu_file: &OsuFile, is_convert: bool) -> Vec<(f64, Option<TaikoHitType>)>
+    {
+        osu_file.taiko_objects(is_convert).iter().map(|object| (object.time, object.hit_type)).collect()
+    }
+
+    #[test]
+    fn whistles_and_claps_are_rims()
+    {
+        let osu_file = beatmap(1, "256,192,1000,1,0\n256,192,1500,1,2\n256,192,2000,1,8\n256,192,2500,1,4\n");
+        let types: Vec<Option<TaikoHitType>> = hit_types(&osu_file, false).into_iter().map(|(_, hit_type)| hit_type).collect();
+
+        assert_eq!(types, vec![Some(TaikoHitType::Centre), Some(TaikoHitType::Rim), Some(TaikoHitType::Rim), Some(TaikoHitType::Centre)]);
+    }
+
+    #[test]
+    fn short_sliders_of_converts_become_hits()
+    {
+        //NOTE: 100 pixels at 140 pixels per beat take half a second, less than two beats, so the slider is split on every beat.
+        let osu_file = beatmap(0, "0,0,1000,2,0,L|100:0,1,100,0|2\n");
+
+        assert_eq!(hit_types(&osu_file, true), vec![(1000.0, Some(TaikoHitType::Centre)), (1500.0, Some(TaikoHitType::Rim))]);
+        assert_eq!(hit_types(&osu_file, false), vec![(1000.0, None)]);
+    }
+
+    #[test]
+    fn long_sliders_and_spinners_stay_drum_rolls_and_swells()
+    {
+        let osu_file = beatmap(0, "0,0,1000,2,0,L|400:0,1,400\n256,192,4000,12,0,5000\n");
+
+        assert_eq!(hit_types(&osu_file, true), vec![(1000.0, None), (4000.0, None)]);
+        assert_eq!(osu_file.taiko_difficulty(0).unwrap().max_combo, 0);
+    }
+
+    #[test]
+    fn the_hit_window_follows_the_overall_difficulty_and_clock_rate()
+    {
+        let osu_file = beatmap(1, "256,192,1000,1,0\n256,192,1250,1,2\n256,192,1500,1,0\n");
+        let attributes = osu_file.taiko_difficulty(0).unwrap();
+
+        assert!(!attributes.is_convert);
+        assert_eq!(attributes.max_combo, 3);
+        assert_eq!(attributes.great_hit_window, 35.0);
+        assert!((osu_file.taiko_difficulty(MOD_DOUBLE_TIME).unwrap().great_hit_window - 35.0 / 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn converts_are_penalised_and_other_modes_are_left_out()
+    {
+        let hit_objects: &str = "256,192,1000,1,0\n256,192,1250,1,2\n256,192,1500,1,0\n256,192,1750,1,8\n";
+        let taiko = beatmap(1, hit_objects).taiko_difficulty(0).unwrap();
+        let convert = beatmap(0, hit_objects).taiko_difficulty(0).unwrap();
+
+        assert!(convert.is_convert);
+        assert!((convert.star_rating - taiko.star_rating * 0.925).abs() < 1e-9);
+        assert!(beatmap(2, hit_objects).taiko_difficulty(0).is_none());
+        assert!(beatmap(3, hit_objects).taiko_difficulty(0).is_none());
+    }
+}
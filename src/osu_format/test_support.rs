@@ -0,0 +1,27 @@
+use super::data::{OsuFile, OsuFileConfig};
+
+/// A beat every 500ms (120 BPM) from the start of the map, with the default slider velocity.
+pub const TIMING_POINTS: &str = "0,500,4,2,0,60,1,0";
+
+/// Parses a whole beatmap, timing points and hit objects included, and fails the test on a parse error.
+pub fn parse(text: &str) -> OsuFile
+{
+    let mut osu_file: OsuFile = OsuFile::new();
+    let config = OsuFileConfig { parse_timing_points: true, parse_hit_objects: true, ..Default::default() };
+
+    osu_file.parse_str(text, config).unwrap();
+    osu_file
+}
+
+/// Writes the named sections below a v14 header and parses them, ("HitObjects", "...") becomes [HitObjects] and its lines.
+pub fn beatmap(sections: &[(&str, &str)]) -> OsuFile
+{
+    let mut text: String = String::from("osu file format v14\n");
+
+    for (name, lines) in sections.iter()
+    {
+        text.push_str(&format!("\n[{}]\n{}\n", name, lines.trim_end()));
+    }
+
+    parse(&text)
+}
@@ -0,0 +1,198 @@
+use std::ops::Range;
+
+use super::data::{OsuFileTimingPoint, OsuFileTimingPoints};
+
+//NOTE: The beat length osu! falls back to for beatmaps without any uninherited timing point, 120 BPM.
+const DEFAULT_BEAT_LENGTH: f64 = 500.0;
+
+///
+/// General todo's for this file:
+/// - Timing points are expected in file order, osu! sorts them by time first.
+///
+impl OsuFileTimingPoints
+{
+    /// The last timing point at or before the time, inherited or not. Before the first timing point this is the first one.
+    pub fn timing_point_at(&self, time: f64) -> Option<&OsuFileTimingPoint>
+    {
+        self.timing_points
+            .iter()
+            .rev()
+            .find(|point| point.time as f64 <= time)
+            .or_else(|| self.timing_points.first())
+    }
+
+    /// The uninherited timing point that sets the beat length at the time.
+    /// Before the first one osu! uses the first one, so does this.
+    pub fn uninherited_at(&self, time: f64) -> Option<&OsuFileTimingPoint>
+    {
+        self.timing_points
+            .iter()
+            .rev()
+            .find(|point| point.uninherited && point.time as f64 <= time)
+            .or_else(|| self.timing_points.iter().find(|point| point.uninherited))
+    }
+
+    /// The inherited timing point that applies at the time, None if an uninherited timing point came after it.
+    pub fn inherited_at(&self, time: f64) -> Option<&OsuFileTimingPoint>
+    {
+        self.timing_points
+            .iter()
+            .rev()
+            .find(|point| point.time as f64 <= time)
+            .filter(|point| !point.uninherited)
+    }
+
+    pub fn beat_length_at(&self, time: f64) -> f64
+    {
+        self.uninherited_at(time)
+            .map(|point| point.beat_length as f64)
+            .filter(|beat_length| *beat_length > 0.0)
+            .unwrap_or(DEFAULT_BEAT_LENGTH)
+    }
+
+    pub fn bpm_at(&self, time: f64) -> f64
+    {
+        60_000.0 / self.beat_length_at(time)
+    }
+
+    /// The slider velocity multiplier at the time, 1 when no inherited timing point applies.
+    pub fn slider_velocity_at(&self, time: f64) -> f64
+    {
+        self.inherited_at(time).map(|point| point.slider_velocity()).unwrap_or(1.0)
+    }
+
+    pub fn is_kiai_at(&self, time: f64) -> bool
+    {
+        self.timing_points
+            .iter()
+            .rev()
+            .find(|point| point.time as f64 <= time)
+            .map(|point| point.is_kiai())
+            .unwrap_or(false)
+    }
+
+    /// The time ranges kiai is on, the last one runs until end_time if no timing point turns it off.
+    pub fn kiai_ranges(&self, end_time: f64) -> Vec<Range<f64>>
+    {
+        let mut ranges: Vec<Range<f64>> = Vec::new();
+        let mut kiai_start: Option<f64> = None;
+
+        for point in self.timing_points.iter()
+        {
+            let time: f64 = point.time as f64;
+
+            match (kiai_start, point.is_kiai())
+            {
+                (None, true) => kiai_start = Some(time),
+                (Some(start), false) =>
+                {
+                    ranges.push(start..time);
+                    kiai_start = None;
+                },
+                _ => { }
+            }
+        }
+
+        if let Some(start) = kiai_start.filter(|start| *start < end_time)
+        {
+            ranges.push(start..end_time);
+        }
+
+        //NOTE: Timing points at the same time toggle kiai on and off again, which leaves empty ranges.
+        ranges.retain(|range| range.start < range.end);
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::OsuFileTimingPoints;
+    use crate::osu_format::test_support;
+
+    const TIMING_POINTS: &str = "osu file format v14
+
+[TimingPoints]
+500,500,4,2,0,60,1,0
+1000,-50,4,2,0,60,0,1
+2000,-2000,4,2,0,60,0,1
+3000,400,4,2,0,60,1,8
+3000,-5,4,2,0,60,0,0
+4000,-100,4,2,0,60,0,1
+";
+
+    fn timing_points(text: &str) -> OsuFileTimingPoints
+    {
+        test_support::parse(text).timing_points_section
+    }
+
+    #[test]
+    fn the_first_timing_point_applies_before_it()
+    {
+        let timing_points = timing_points(TIMING_POINTS);
+
+        assert_eq!(timing_points.timing_point_at(0.0).map(|point| point.time), Some(500.0));
+        assert_eq!(timing_points.uninherited_at(0.0).map(|point| point.time), Some(500.0));
+        assert!(timing_points.inherited_at(0.0).is_none());
+        assert_eq!(timing_points.bpm_at(0.0), 120.0);
+    }
+
+    #[test]
+    fn timing_points_apply_from_their_own_time()
+    {
+        let timing_points = timing_points(TIMING_POINTS);
+
+        assert_eq!(timing_points.timing_point_at(999.0).map(|point| point.time), Some(500.0));
+        assert_eq!(timing_points.timing_point_at(1000.0).map(|point| point.time), Some(1000.0));
+        assert_eq!(timing_points.inherited_at(1500.0).map(|point| point.time), Some(1000.0));
+        assert_eq!(timing_points.uninherited_at(1500.0).map(|point| point.time), Some(500.0));
+        assert_eq!(timing_points.bpm_at(3000.0), 150.0);
+        assert!(timing_points.uninherited_at(3000.0).unwrap().omits_first_barline());
+    }
+
+    #[test]
+    fn slider_velocity_is_clamped_and_reset()
+    {
+        let timing_points = timing_points(TIMING_POINTS);
+
+        assert_eq!(timing_points.slider_velocity_at(0.0), 1.0);
+        assert_eq!(timing_points.slider_velocity_at(1000.0), 2.0);
+        assert_eq!(timing_points.slider_velocity_at(2500.0), 0.1);
+        //NOTE: The inherited point comes after the uninherited one at the same time, so it still applies.
+        assert_eq!(timing_points.slider_velocity_at(3000.0), 10.0);
+        assert_eq!(timing_points.slider_velocity_at(4000.0), 1.0);
+    }
+
+    #[test]
+    fn an_uninherited_timing_point_resets_the_slider_velocity()
+    {
+        let timing_points = timing_points("osu file format v14\n\n[TimingPoints]\n0,500,4,2,0,60,1,0\n1000,-50,4,2,0,60,0,0\n2000,500,4,2,0,60,1,0\n");
+
+        assert_eq!(timing_points.slider_velocity_at(1500.0), 2.0);
+        assert!(timing_points.inherited_at(2000.0).is_none());
+        assert_eq!(timing_points.slider_velocity_at(2000.0), 1.0);
+    }
+
+    #[test]
+    fn kiai_ranges_follow_the_effects()
+    {
+        let timing_points = timing_points(TIMING_POINTS);
+
+        assert!(!timing_points.is_kiai_at(999.0));
+        assert!(timing_points.is_kiai_at(1000.0));
+        assert!(!timing_points.is_kiai_at(3500.0));
+        assert_eq!(timing_points.kiai_ranges(5000.0), vec![1000.0..3000.0, 4000.0..5000.0]);
+        assert_eq!(timing_points.kiai_ranges(4000.0), vec![1000.0..3000.0]);
+    }
+
+    #[test]
+    fn beatmaps_without_timing_points_use_the_defaults()
+    {
+        let timing_points = OsuFileTimingPoints::default();
+
+        assert!(timing_points.timing_point_at(0.0).is_none());
+        assert_eq!(timing_points.bpm_at(1000.0), 120.0);
+        assert_eq!(timing_points.slider_velocity_at(1000.0), 1.0);
+        assert!(timing_points.kiai_ranges(1000.0).is_empty());
+    }
+}
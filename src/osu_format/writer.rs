@@ -0,0 +1,607 @@
+use std::io::{self, BufWriter, Write};
+use std::fs::File;
+use std::path::PathBuf;
+use half::{ f16 };
+
+use super::data::{
+    OsuFile,
+    OsuFileStoryboard,
+    OsuFileStoryboardElement,
+    OsuFileStoryboardCommand,
+    OsuFileStoryboardTween,
+    OsuFileStoryboardLayer,
+    OsuFileOverlayPosition,
+    OsuFileHitObjectKind,
+    OsuFileSlider
+};
+
+//NOTE: Osu! writes Windows line endings, no matter the platform.
+const LINE_ENDING: &str = "\r\n";
+const DEFAULT_VERSION: &str = "osu file format v14";
+
+const STORYBOARD_LAYERS: [(OsuFileStoryboardLayer, &str); 5] = [
+    (OsuFileStoryboardLayer::Background, "//Storyboard Layer 0 (Background)"),
+    (OsuFileStoryboardLayer::Fail, "//Storyboard Layer 1 (Fail)"),
+    (OsuFileStoryboardLayer::Pass, "//Storyboard Layer 2 (Pass)"),
+    (OsuFileStoryboardLayer::Foreground, "//Storyboard Layer 3 (Foreground)"),
+    (OsuFileStoryboardLayer::Overlay, "//Storyboard Layer 4 (Overlay)")
+];
+
+impl OsuFile
+{
+    /// The number out of the "osu file format vXX" header, None when the header is missing or malformed.
+    pub fn format_version(&self) -> Option<u32>
+    {
+        self.version.trim().rsplit('v').next()?.parse::<u32>().ok()
+    }
+
+    /// Whether the section with this lowercase name was parsed, see OsuFileConfig::parses_section.
+    pub fn has_parsed_section(&self, section: &str) -> bool
+    {
+        self.parsed_sections.iter().any(|parsed| parsed == section)
+    }
+
+    /// Writes the file in the layout osu! itself uses.
+    /// Only the sections that were parsed are written, the others would otherwise come out as their defaults.
+    /// Storyboard variables are written as their substituted values, so no [Variables] section is written.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        let version: &str = if self.version.is_empty() { DEFAULT_VERSION } else { &self.version };
+        let format_version: u32 = self.format_version().unwrap_or(14);
+
+        write_line(writer, version)?;
+        write_line(writer, "")?;
+
+        if self.has_parsed_section("general") { self.write_general(writer, format_version)?; }
+        if self.has_parsed_section("editor") { self.write_editor(writer)?; }
+        if self.has_parsed_section("metadata") { self.write_metadata(writer, format_version)?; }
+        if self.has_parsed_section("difficulty") { self.write_difficulty(writer)?; }
+        if self.has_parsed_section("events") { self.write_events(writer)?; }
+        if self.has_parsed_section("timingpoints") { self.write_timing_points(writer)?; }
+        if self.has_parsed_section("colours") { self.write_colours(writer)?; }
+        if self.has_parsed_section("hitobjects") { self.write_hit_objects(writer)?; }
+
+        Ok(())
+    }
+
+    pub fn to_osu_string(&self) -> String
+    {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        //NOTE: Writing into a Vec can't fail and every value we write is valid utf-8.
+        let _ = self.write(&mut buffer);
+        String::from_utf8_lossy(&buffer).into_owned()
+    }
+
+    pub fn save(&self, file: PathBuf) -> io::Result<()>
+    {
+        let mut writer = BufWriter::new(File::create(file)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    fn write_general<W: Write>(&self, writer: &mut W, format_version: u32) -> io::Result<()>
+    {
+        let general = &self.general_section;
+
+        write_line(writer, "[General]")?;
+        write_line(writer, &format!("AudioFilename: {}", general.audio_file_name))?;
+        write_line(writer, &format!("AudioLeadIn: {}", general.audio_lead_in))?;
+        write_line(writer, &format!("PreviewTime: {}", general.preview_time))?;
+        write_line(writer, &format!("Countdown: {}", general.countdown))?;
+        write_line(writer, &format!("SampleSet: {}", general.sample_set))?;
+        write_line(writer, &format!("StackLeniency: {}", general.stack_leniency))?;
+        write_line(writer, &format!("Mode: {}", general.mode.clone() as u32))?;
+        write_line(writer, &format!("LetterboxInBreaks: {}", u8::from(general.letterbox_in_breaks)))?;
+
+        //NOTE: Osu! only writes the optional fields when they differ from their default.
+        if general.use_skin_sprites
+        {
+            write_line(writer, "UseSkinSprites: 1")?;
+        }
+
+        if !general.skin_preference.is_empty()
+        {
+            write_line(writer, &format!("SkinPreference: {}", general.skin_preference))?;
+        }
+
+        if general.overlay_position != OsuFileOverlayPosition::NoChange
+        {
+            write_line(writer, &format!("OverlayPosition: {}", general.overlay_position))?;
+        }
+
+        if general.epilepsy_warning
+        {
+            write_line(writer, "EpilepsyWarning: 1")?;
+        }
+
+        if general.countdown_offset > 0
+        {
+            write_line(writer, &format!("CountdownOffset: {}", general.countdown_offset))?;
+        }
+
+        if general.special_style
+        {
+            write_line(writer, "SpecialStyle: 1")?;
+        }
+
+        if general.widescreen_storyboard
+        {
+            write_line(writer, "WidescreenStoryboard: 1")?;
+        }
+
+        if general.samples_match_playback_rate && format_version >= 14
+        {
+            write_line(writer, "SamplesMatchPlaybackRate: 1")?;
+        }
+
+        write_line(writer, "")
+    }
+
+    fn write_editor<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        let editor = &self.editor_section;
+
+        write_line(writer, "[Editor]")?;
+
+        if !editor.bookmarks.bookmarks.is_empty()
+        {
+            let bookmarks: Vec<String> = editor.bookmarks.bookmarks.iter().map(|bookmark| bookmark.to_string()).collect();
+            write_line(writer, &format!("Bookmarks: {}", bookmarks.join(",")))?;
+        }
+
+        write_line(writer, &format!("DistanceSpacing: {}", editor.distance_spacing))?;
+        write_line(writer, &format!("BeatDivisor: {}", editor.beat_divisor))?;
+        write_line(writer, &format!("GridSize: {}", editor.grid_size))?;
+        write_line(writer, &format!("TimelineZoom: {}", editor.timeline_zoom))?;
+        write_line(writer, "")
+    }
+
+    fn write_metadata<W: Write>(&self, writer: &mut W, format_version: u32) -> io::Result<()>
+    {
+        let metadata = &self.metadata_section;
+
+        //NOTE: Unlike [General] and [Editor], osu! writes [Metadata] and [Difficulty] without a space after the colon.
+        write_line(writer, "[Metadata]")?;
+        write_line(writer, &format!("Title:{}", metadata.title))?;
+
+        if format_version >= 10
+        {
+            write_line(writer, &format!("TitleUnicode:{}", metadata.title_unicode))?;
+        }
+
+        write_line(writer, &format!("Artist:{}", metadata.artist))?;
+
+        if format_version >= 10
+        {
+            write_line(writer, &format!("ArtistUnicode:{}", metadata.artist_unicode))?;
+        }
+
+        write_line(writer, &format!("Creator:{}", metadata.creator))?;
+        write_line(writer, &format!("Version:{}", metadata.version))?;
+        write_line(writer, &format!("Source:{}", metadata.source))?;
+        write_line(writer, &format!("Tags:{}", metadata.tags.tags.join(" ")))?;
+
+        if format_version >= 10
+        {
+            write_line(writer, &format!("BeatmapID:{}", metadata.beatmap_id))?;
+            write_line(writer, &format!("BeatmapSetID:{}", metadata.beatmap_set_id))?;
+        }
+
+        write_line(writer, "")
+    }
+
+    fn write_difficulty<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        let difficulty = &self.difficulty_section;
+
+        write_line(writer, "[Difficulty]")?;
+        write_line(writer, &format!("HPDrainRate:{}", format_f16(difficulty.hp_drain_rate)))?;
+        write_line(writer, &format!("CircleSize:{}", format_f16(difficulty.circle_size)))?;
+        write_line(writer, &format!("OverallDifficulty:{}", format_f16(difficulty.overall_difficulty)))?;
+        write_line(writer, &format!("ApproachRate:{}", format_f16(difficulty.approach_rate)))?;
+        write_line(writer, &format!("SliderMultiplier:{}", format_f16(difficulty.slider_multiplier)))?;
+        write_line(writer, &format!("SliderTickRate:{}", format_f16(difficulty.slider_tick_rate)))?;
+        write_line(writer, "")
+    }
+
+    fn write_events<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        let events = &self.events_section;
+
+        write_line(writer, "[Events]")?;
+        write_line(writer, "//Background and Video events")?;
+
+        if events.background.exists
+        {
+            let background = &events.background;
+            write_line(writer, &format!("0,0,\"{}\",{},{}", background.file_name, background.x_offset, background.y_offset))?;
+        }
+
+        if events.video.exists
+        {
+            let video = &events.video;
+            write_line(writer, &format!("Video,{},\"{}\",{},{}", video.start_time, video.file_name, video.x_offset, video.y_offset))?;
+        }
+
+        write_line(writer, "//Break Periods")?;
+
+        for break_period in events.breaks.iter()
+        {
+            write_line(writer, &format!("2,{},{}", break_period.start, break_period.end))?;
+        }
+
+        events.storyboard.write_elements(writer)?;
+        write_line(writer, "")
+    }
+
+    fn write_timing_points<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        write_line(writer, "[TimingPoints]")?;
+
+        for timing_point in self.timing_points_section.timing_points.iter()
+        {
+            write_line(writer, &format!("{},{},{},{},{},{},{},{}",
+                timing_point.time,
+                timing_point.beat_length,
+                timing_point.meter,
+                timing_point.sample_set.clone() as u32,
+                timing_point.sample_index,
+                timing_point.volume,
+                u8::from(timing_point.uninherited),
+                timing_point.effects))?;
+        }
+
+        write_line(writer, "")
+    }
+
+    fn write_colours<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        let colours = &self.colours_section;
+
+        if colours.combo_colors.is_empty() && colours.slider_border.is_none() && colours.slider_track_override.is_none()
+        {
+            return Ok(());
+        }
+
+        write_line(writer, "[Colours]")?;
+
+        for combo in colours.combo_colors.iter()
+        {
+            write_line(writer, &format!("Combo{} : {}", combo.index, combo.color))?;
+        }
+
+        if let Some(colour) = &colours.slider_track_override
+        {
+            write_line(writer, &format!("SliderTrackOverride : {}", colour))?;
+        }
+
+        if let Some(colour) = &colours.slider_border
+        {
+            write_line(writer, &format!("SliderBorder : {}", colour))?;
+        }
+
+        write_line(writer, "")
+    }
+
+    fn write_hit_objects<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        write_line(writer, "[HitObjects]")?;
+
+        for hit_object in self.hit_object_section.hit_objects.iter()
+        {
+            let mut line: String = format!("{},{},{},{},{}", hit_object.x, hit_object.y, hit_object.time, hit_object.hit_type, hit_object.hit_sound);
+
+            let hit_sample: String = hit_object.hit_sample.as_ref().map(|hit_sample| hit_sample.to_string()).unwrap_or_default();
+
+            match &hit_object.kind
+            {
+                OsuFileHitObjectKind::Circle => { },
+                OsuFileHitObjectKind::Slider(slider) => line.push_str(&format!(",{}", format_slider(slider, hit_object.hit_sample.is_some()))),
+                OsuFileHitObjectKind::Spinner { end_time } => line.push_str(&format!(",{}", end_time)),
+                OsuFileHitObjectKind::Hold { end_time } =>
+                {
+                    //NOTE: Mania holds separate the end time and the hit sample with a colon.
+                    line.push_str(&format!(",{}:{}", end_time, hit_sample));
+                    write_line(writer, &line)?;
+                    continue;
+                }
+            }
+
+            if hit_object.hit_sample.is_some()
+            {
+                line.push_str(&format!(",{}", hit_sample));
+            }
+
+            write_line(writer, &line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl OsuFileStoryboard
+{
+    /// Writes a standalone .osb file, variables are written as their substituted values.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        write_line(writer, "[Events]")?;
+        write_line(writer, "//Background and Video events")?;
+        self.write_elements(writer)
+    }
+
+    pub fn save(&self, file: PathBuf) -> io::Result<()>
+    {
+        let mut writer = BufWriter::new(File::create(file)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes the elements grouped by layer, osu! draws the elements of a layer in file order so that order is kept.
+    pub fn write_elements<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        for (layer, heading) in STORYBOARD_LAYERS.iter()
+        {
+            write_line(writer, heading)?;
+
+            for element in self.elements.iter()
+            {
+                match element
+                {
+                    OsuFileStoryboardElement::Sprite(sprite) if sprite.layer == *layer =>
+                    {
+                        let line: String = format!("Sprite,{},{},\"{}\",{},{}", sprite.layer, sprite.origin, sprite.file_name, sprite.x, sprite.y);
+                        write_line(writer, &line)?;
+                        self.write_commands(writer, &sprite.commands, 1)?;
+                    },
+                    OsuFileStoryboardElement::Animation(animation) if animation.layer == *layer =>
+                    {
+                        let line: String = format!("Animation,{},{},\"{}\",{},{},{},{},{}",
+                            animation.layer,
+                            animation.origin,
+                            animation.file_name,
+                            animation.x,
+                            animation.y,
+                            animation.frame_count,
+                            animation.frame_delay,
+                            animation.loop_type);
+                        write_line(writer, &line)?;
+                        self.write_commands(writer, &animation.commands, 1)?;
+                    },
+                    _ => { }
+                }
+            }
+        }
+
+        write_line(writer, "//Storyboard Sound Samples")?;
+
+        for element in self.elements.iter()
+        {
+            if let OsuFileStoryboardElement::Sample(sample) = element
+            {
+                let line: String = format!("Sample,{},{},\"{}\",{}", sample.time, sample.layer.clone() as u32, sample.file_name, sample.volume);
+                write_line(writer, &line)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_commands<W: Write>(&self, writer: &mut W, commands: &[OsuFileStoryboardCommand], depth: usize) -> io::Result<()>
+    {
+        let indent: String = " ".repeat(depth);
+
+        for command in commands.iter()
+        {
+            let line: String = match command
+            {
+                OsuFileStoryboardCommand::Fade(tween) => format_tween("F", tween),
+                OsuFileStoryboardCommand::Move(tween) => format_tween("M", tween),
+                OsuFileStoryboardCommand::MoveX(tween) => format_tween("MX", tween),
+                OsuFileStoryboardCommand::MoveY(tween) => format_tween("MY", tween),
+                OsuFileStoryboardCommand::Scale(tween) => format_tween("S", tween),
+                OsuFileStoryboardCommand::VectorScale(tween) => format_tween("V", tween),
+                OsuFileStoryboardCommand::Rotate(tween) => format_tween("R", tween),
+                OsuFileStoryboardCommand::Colour(tween) => format_tween("C", tween),
+                OsuFileStoryboardCommand::Parameter { easing, start_time, end_time, parameter } =>
+                {
+                    format!("P,{},{},{},{}", easing, start_time, format_end_time(*start_time, *end_time), parameter)
+                },
+                OsuFileStoryboardCommand::Loop { start_time, loop_count, .. } => format!("L,{},{}", start_time, loop_count),
+                OsuFileStoryboardCommand::Trigger { trigger, start_time, end_time, group_number, .. } =>
+                {
+                    match group_number
+                    {
+                        0 => format!("T,{},{},{}", trigger, start_time, end_time),
+                        _ => format!("T,{},{},{},{}", trigger, start_time, end_time, group_number)
+                    }
+                }
+            };
+
+            write_line(writer, &format!("{}{}", indent, line))?;
+
+            if let OsuFileStoryboardCommand::Loop { commands, .. } | OsuFileStoryboardCommand::Trigger { commands, .. } = command
+            {
+                self.write_commands(writer, commands, depth + 1)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The values are positional, so the edges are written as soon as a hit sample follows them.
+fn format_slider(slider: &OsuFileSlider, has_hit_sample: bool) -> String
+{
+    let curve_points: Vec<String> = slider.curve_points.iter().map(|point| point.to_string()).collect();
+    let mut values: String = format!("{}|{},{},{}", slider.curve_type, curve_points.join("|"), slider.slides, slider.length);
+
+    if slider.edge_sounds.is_empty() && slider.edge_sets.is_empty() && !has_hit_sample
+    {
+        return values;
+    }
+
+    let edges: usize = slider.span_count() as usize + 1;
+    let edge_sounds: Vec<String> = if slider.edge_sounds.is_empty() { vec![String::from("0"); edges] }
+        else { slider.edge_sounds.iter().map(|sound| sound.to_string()).collect() };
+    let edge_sets: Vec<String> = if slider.edge_sets.is_empty() { vec![String::from("0:0"); edges] }
+        else { slider.edge_sets.iter().map(|set| set.to_string()).collect() };
+
+    values.push_str(&format!(",{},{}", edge_sounds.join("|"), edge_sets.join("|")));
+    values
+}
+
+fn format_tween(event: &str, tween: &OsuFileStoryboardTween) -> String
+{
+    let values: Vec<String> = tween.values.iter().map(|value| value.to_string()).collect();
+    format!("{},{},{},{},{}", event, tween.easing, tween.start_time, format_end_time(tween.start_time, tween.end_time), values.join(","))
+}
+
+/// Osu! leaves the end time empty for instant commands.
+fn format_end_time(start_time: i32, end_time: i32) -> String
+{
+    if start_time == end_time { String::new() } else { end_time.to_string() }
+}
+
+/// Difficulty values are stored as f16, printing them through f32 would turn 1.4 into 1.3984375.
+/// Takes the shortest decimal representation that reads back into the same f16.
+pub fn format_f16(value: f16) -> String
+{
+    for decimals in 0..=4
+    {
+        let formatted: String = format!("{:.*}", decimals, value.to_f32());
+
+        if formatted.parse::<f32>().map(f16::from_f32) == Ok(value)
+        {
+            return formatted;
+        }
+    }
+
+    value.to_f32().to_string()
+}
+
+fn write_line<W: Write>(writer: &mut W, line: &str) -> io::Result<()>
+{
+    writer.write_all(line.as_bytes())?;
+    writer.write_all(LINE_ENDING.as_bytes())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::osu_format::data::{OsuFile, OsuFileConfig};
+
+    const BEATMAP: &str = "osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+AudioLeadIn: 0
+PreviewTime: 1200
+Mode: 0
+SampleSet: Soft
+
+[Editor]
+Bookmarks: 1000,2000
+DistanceSpacing: 1.2
+BeatDivisor: 4
+
+[Metadata]
+Title:Song
+Artist:Artist
+Creator:Mapper
+Version:Hard
+Tags:tag another
+
+[Difficulty]
+HPDrainRate:5
+CircleSize:4
+OverallDifficulty:8
+ApproachRate:9
+SliderMultiplier:1.4
+SliderTickRate:1
+
+[Variables]
+$pos=320,240
+$fg=Foreground,Centre
+
+[Events]
+0,0,\"bg.jpg\",0,0
+2,3000,5000
+Sprite,$fg,\"sb/a.png\",$pos
+ F,0,1000,2000,0,1
+ L,1000,2
+  M,0,0,500,320,240,330,250
+Sample,1000,0,\"hit.wav\",80
+
+[TimingPoints]
+0,500,4,2,0,60,1,0
+1000,-50,4,2,0,60,0,1
+
+[Colours]
+Combo1 : 255,0,0
+SliderBorder : 0,0,0
+
+[HitObjects]
+256,192,1000,1,0,0:0:0:0:
+100,100,1500,2,2,B|200:100|300:150,2,140,2|0|8,1:0|0:0|2:0,0:0:0:0:
+256,192,6000,12,0,7000,0:0:0:0:
+";
+
+    fn parse(text: &str, config: OsuFileConfig) -> OsuFile
+    {
+        let mut osu_file: OsuFile = OsuFile::new();
+        osu_file.parse_str(text, config).unwrap();
+        osu_file
+    }
+
+    fn parse_all(text: &str) -> OsuFile
+    {
+        parse(text, OsuFileConfig { parse_timing_points: true, parse_hit_objects: true, ..Default::default() })
+    }
+
+    #[test]
+    fn written_files_parse_back_the_same()
+    {
+        let original: OsuFile = parse_all(BEATMAP);
+        let written: String = original.to_osu_string();
+        let reparsed: OsuFile = parse_all(&written);
+
+        assert!(reparsed.diagnostics.is_empty(), "{:?}", reparsed.diagnostics);
+        assert_eq!(reparsed.parsed_sections, original.parsed_sections.iter().filter(|section| *section != "variables").cloned().collect::<Vec<String>>());
+        assert_eq!(format!("{:?}", reparsed.general_section), format!("{:?}", original.general_section));
+        assert_eq!(format!("{:?}", reparsed.editor_section), format!("{:?}", original.editor_section));
+        assert_eq!(format!("{:?}", reparsed.metadata_section), format!("{:?}", original.metadata_section));
+        assert_eq!(format!("{:?}", reparsed.difficulty_section), format!("{:?}", original.difficulty_section));
+        assert_eq!(format!("{:?}", reparsed.events_section.background), format!("{:?}", original.events_section.background));
+        assert_eq!(format!("{:?}", reparsed.events_section.video), format!("{:?}", original.events_section.video));
+        assert_eq!(format!("{:?}", reparsed.events_section.breaks), format!("{:?}", original.events_section.breaks));
+        assert_eq!(format!("{:?}", reparsed.events_section.storyboard.elements), format!("{:?}", original.events_section.storyboard.elements));
+        assert_eq!(format!("{:?}", reparsed.timing_points_section), format!("{:?}", original.timing_points_section));
+        assert_eq!(format!("{:?}", reparsed.colours_section), format!("{:?}", original.colours_section));
+        assert_eq!(format!("{:?}", reparsed.hit_object_section), format!("{:?}", original.hit_object_section));
+        assert_eq!(reparsed.to_osu_string(), written);
+    }
+
+    #[test]
+    fn sections_that_were_not_parsed_are_not_written()
+    {
+        let written: String = parse(BEATMAP, OsuFileConfig::default()).to_osu_string();
+
+        assert!(written.contains("[Difficulty]"));
+        assert!(!written.contains("[TimingPoints]"));
+        assert!(!written.contains("[HitObjects]"));
+        assert_eq!(OsuFile::new().to_osu_string(), "osu file format v14\r\n\r\n");
+    }
+
+    #[test]
+    fn variables_are_written_substituted()
+    {
+        let written: String = parse_all(BEATMAP).to_osu_string();
+
+        //NOTE: A value that only happens to equal a variable must be written as is, so every variable is written out in place.
+        assert!(!written.contains("[Variables]"));
+        assert!(!written.contains('$'));
+        assert!(written.contains("Sprite,Foreground,Centre,\"sb/a.png\",320,240\r\n"));
+        assert!(written.contains("  M,0,0,500,320,240,330,250\r\n"));
+    }
+}
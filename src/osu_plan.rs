@@ -0,0 +1,147 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use osu_song_minifier::osu_format::data::OsuFileGamemode;
+
+#[derive(Clone, Debug)]
+pub struct PlanFile
+{
+    /// Absolute path of the file inside the Songs folder.
+    pub from: PathBuf,
+    /// Path of the file relative to the Songs folder.
+    pub to: PathBuf,
+    pub size: u64
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SongPlan
+{
+    pub folder: PathBuf,
+    pub kept: Vec<PlanFile>,
+    pub removed: Vec<PlanFile>,
+    /// Files referenced by a .osu file, but absent on disk.
+    pub missing: Vec<String>,
+    pub gamemodes: Vec<OsuFileGamemode>,
+    pub warnings: Vec<String>,
+    /// None of the difficulties survived filtering, the whole folder goes.
+    pub remove_folder: bool,
+    /// The set could not be evaluated, it is left alone and the warnings say why.
+    pub failed: bool,
+    /// Bytes of background videos referenced by the set, but not kept.
+    pub removed_video_bytes: u64
+}
+
+impl SongPlan
+{
+    pub fn kept_bytes(&self) -> u64
+    {
+        self.kept.iter().map(|file| file.size).sum()
+    }
+
+    pub fn removed_bytes(&self) -> u64
+    {
+        self.removed.iter().map(|file| file.size).sum()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Plan
+{
+    pub songs: Vec<SongPlan>
+}
+
+impl Plan
+{
+    pub fn kept_bytes(&self) -> u64
+    {
+        self.songs.iter().map(|song| song.kept_bytes()).sum()
+    }
+
+    pub fn removed_bytes(&self) -> u64
+    {
+        self.songs.iter().map(|song| song.removed_bytes()).sum()
+    }
+
+    pub fn removed_video_bytes(&self) -> u64
+    {
+        self.songs.iter().map(|song| song.removed_video_bytes).sum()
+    }
+
+    pub fn failed_count(&self) -> usize
+    {
+        self.songs.iter().filter(|song| song.failed).count()
+    }
+
+    pub fn print(&self, verbosity: i8, out: &mut dyn Write) -> Result<(), io::Error>
+    {
+        if verbosity < 0
+        {
+            return Ok(());
+        }
+
+        for song in self.songs.iter()
+        {
+            if song.failed
+            {
+                writeln!(out, "{:?}: could not be evaluated, leaving it alone: {}", song.folder, song.warnings.join("; "))?;
+                continue;
+            }
+
+            if song.remove_folder
+            {
+                writeln!(out, "{:?}: removing the whole set, {} files ({})", song.folder, song.removed.len(), format_bytes(song.removed_bytes()))?;
+                continue;
+            }
+
+            writeln!(out, "{:?}: keeping {} files ({}), removing {} files ({}, {} of video), {} missing",
+                song.folder,
+                song.kept.len(), format_bytes(song.kept_bytes()),
+                song.removed.len(), format_bytes(song.removed_bytes()), format_bytes(song.removed_video_bytes),
+                song.missing.len())?;
+
+            if verbosity >= 1
+            {
+                for file in song.kept.iter() { writeln!(out, "\tkeep    {:?} ({})", file.to, format_bytes(file.size))?; }
+                for file in song.removed.iter() { writeln!(out, "\tremove  {:?} ({})", file.to, format_bytes(file.size))?; }
+                for name in song.missing.iter() { writeln!(out, "\tmissing {:?}", name)?; }
+                for warning in song.warnings.iter() { writeln!(out, "\twarning {}", warning)?; }
+            }
+        }
+
+        self.print_totals(out)
+    }
+
+    pub fn print_totals(&self, out: &mut dyn Write) -> Result<(), io::Error>
+    {
+        let kept: usize = self.songs.iter().map(|song| song.kept.len()).sum();
+        let removed: usize = self.songs.iter().map(|song| song.removed.len()).sum();
+        let missing: usize = self.songs.iter().map(|song| song.missing.len()).sum();
+
+        writeln!(out, "Total of {} beatmap sets: keeping {} files ({}), removing {} files ({}, {} of video), {} missing, {} failed",
+            self.songs.len(),
+            kept, format_bytes(self.kept_bytes()),
+            removed, format_bytes(self.removed_bytes()), format_bytes(self.removed_video_bytes()),
+            missing, self.failed_count())
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String
+{
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1
+    {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0
+    {
+        return format!("{} {}", bytes, UNITS[unit]);
+    }
+
+    format!("{:.2} {}", value, UNITS[unit])
+}
@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum HitsoundPolicy
+{
+    /// Keep every custom hitsound sample the hit objects can play.
+    #[default]
+    Keep,
+    /// Drop custom hitsounds, osu! falls back to the skin's samples.
+    Drop
+}
+
+impl FromStr for HitsoundPolicy
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<HitsoundPolicy, Self::Err>
+    {
+        match input.to_ascii_lowercase().as_str()
+        {
+            "keep" => Ok(HitsoundPolicy::Keep),
+            "drop" => Ok(HitsoundPolicy::Drop),
+            _ => Err(format!("Unknown hitsound policy {}, expected keep or drop.", input))
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum VideoPolicy
+{
+    /// Keep the background video of every remaining difficulty.
+    Keep,
+    /// Drop background videos, osu! shows the background image instead.
+    #[default]
+    Drop,
+    /// Only keep background videos up to the given amount of bytes.
+    KeepUnder(u64)
+}
+
+impl VideoPolicy
+{
+    pub fn keeps(&self, size: u64) -> bool
+    {
+        match self
+        {
+            VideoPolicy::Keep => true,
+            VideoPolicy::Drop => false,
+            VideoPolicy::KeepUnder(limit) => size <= *limit
+        }
+    }
+}
+
+impl FromStr for VideoPolicy
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<VideoPolicy, Self::Err>
+    {
+        let lowercase = input.trim().to_ascii_lowercase();
+
+        match lowercase.as_str()
+        {
+            "keep" => Ok(VideoPolicy::Keep),
+            "drop" => Ok(VideoPolicy::Drop),
+            _ =>
+            {
+                //NOTE: A plain number is a size in megabytes as well, "50" equals "50MB".
+                let megabytes: f64 = lowercase
+                    .trim_end_matches("mb")
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|megabytes| *megabytes >= 0.0)
+                    .ok_or_else(|| format!("Unknown video policy {}, expected keep, drop or a size like 50MB.", input))?;
+
+                Ok(VideoPolicy::KeepUnder((megabytes * 1024.0 * 1024.0) as u64))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum StoryboardPolicy
+{
+    /// Keep the .osb file and the assets of every storyboard, including those of dropped difficulties.
+    #[default]
+    Keep,
+    /// Only keep the storyboard assets the remaining difficulties can actually display or play.
+    Used,
+    /// Drop storyboards and their assets.
+    Drop
+}
+
+impl FromStr for StoryboardPolicy
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<StoryboardPolicy, Self::Err>
+    {
+        match input.to_ascii_lowercase().as_str()
+        {
+            "keep" => Ok(StoryboardPolicy::Keep),
+            "used" => Ok(StoryboardPolicy::Used),
+            "drop" => Ok(StoryboardPolicy::Drop),
+            _ => Err(format!("Unknown storyboard policy {}, expected keep, used or drop.", input))
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::osu_plan::{Plan, PlanFile, SongPlan};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReportFormat
+{
+    Json,
+    Csv
+}
+
+impl ReportFormat
+{
+    /// Picks the format from the extension of the report path, defaulting to json.
+    pub fn from_path(path: &Path) -> ReportFormat
+    {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        ReportFormat::from_str(&extension).unwrap_or(ReportFormat::Json)
+    }
+}
+
+impl FromStr for ReportFormat
+{
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<ReportFormat, Self::Err>
+    {
+        match input.to_ascii_lowercase().as_str()
+        {
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(format!("Unknown report format {}, expected json or csv.", input))
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Report
+{
+    pub songs_folder: PathBuf,
+    pub mode: String,
+    pub dry_run: bool,
+    pub kept_bytes: u64,
+    pub removed_bytes: u64,
+    pub removed_video_bytes: u64,
+    pub sets: Vec<SetReport>
+}
+
+#[derive(Serialize)]
+pub struct SetReport
+{
+    pub folder: String,
+    pub removed_entirely: bool,
+    pub failed: bool,
+    pub gamemodes: Vec<String>,
+    pub kept_bytes: u64,
+    pub removed_bytes: u64,
+    pub removed_video_bytes: u64,
+    pub kept: Vec<FileReport>,
+    pub removed: Vec<FileReport>,
+    pub missing: Vec<String>,
+    pub warnings: Vec<String>
+}
+
+#[derive(Serialize)]
+pub struct FileReport
+{
+    pub path: String,
+    pub size: u64
+}
+
+#[derive(Serialize)]
+struct CsvRow<'a>
+{
+    folder: &'a str,
+    removed_entirely: bool,
+    failed: bool,
+    gamemodes: String,
+    kept_files: usize,
+    kept_bytes: u64,
+    removed_files: usize,
+    removed_bytes: u64,
+    removed_video_bytes: u64,
+    missing_files: usize,
+    warnings: String
+}
+
+impl Report
+{
+    pub fn from_plan(plan: &Plan, songs_folder: &Path, mode: String, dry_run: bool) -> Report
+    {
+        Report
+        {
+            songs_folder: songs_folder.to_path_buf(),
+            mode,
+            dry_run,
+            kept_bytes: plan.kept_bytes(),
+            removed_bytes: plan.removed_bytes(),
+            removed_video_bytes: plan.removed_video_bytes(),
+            sets: plan.songs.iter().map(|song| SetReport::from_song_plan(song, songs_folder)).collect()
+        }
+    }
+
+    /// Writes the report to the given path, or to stdout when the path is "-".
+    pub fn write(&self, path: &Path, format: &ReportFormat) -> Result<(), io::Error>
+    {
+        let writer: Box<dyn Write> = if path == Path::new("-")
+        {
+            Box::new(io::stdout())
+        }
+        else
+        {
+            Box::new(File::create(path)?)
+        };
+
+        match format
+        {
+            ReportFormat::Json => self.write_json(writer),
+            ReportFormat::Csv => self.write_csv(writer)
+        }
+    }
+
+    fn write_json(&self, mut writer: Box<dyn Write>) -> Result<(), io::Error>
+    {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)
+    }
+
+    fn write_csv(&self, writer: Box<dyn Write>) -> Result<(), io::Error>
+    {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+
+        for set in self.sets.iter()
+        {
+            csv_writer.serialize(CsvRow {
+                folder: &set.folder,
+                removed_entirely: set.removed_entirely,
+                failed: set.failed,
+                gamemodes: set.gamemodes.join("|"),
+                kept_files: set.kept.len(),
+                kept_bytes: set.kept_bytes,
+                removed_files: set.removed.len(),
+                removed_bytes: set.removed_bytes,
+                removed_video_bytes: set.removed_video_bytes,
+                missing_files: set.missing.len(),
+                warnings: set.warnings.join("; ")
+            })?;
+        }
+
+        csv_writer.flush()
+    }
+}
+
+impl SetReport
+{
+    fn from_song_plan(song: &SongPlan, songs_folder: &Path) -> SetReport
+    {
+        let as_file_report = |file: &PlanFile| -> FileReport
+        {
+            FileReport { path: file.to.to_string_lossy().into_owned(), size: file.size }
+        };
+
+        SetReport
+        {
+            folder: song.folder.strip_prefix(songs_folder).unwrap_or(&song.folder).to_string_lossy().into_owned(),
+            removed_entirely: song.remove_folder,
+            failed: song.failed,
+            gamemodes: song.gamemodes.iter().map(|mode| mode.to_string()).collect(),
+            kept_bytes: song.kept_bytes(),
+            removed_bytes: song.removed_bytes(),
+            removed_video_bytes: song.removed_video_bytes,
+            kept: song.kept.iter().map(as_file_report).collect(),
+            removed: song.removed.iter().map(as_file_report).collect(),
+            missing: song.missing.clone(),
+            warnings: song.warnings.clone()
+        }
+    }
+}
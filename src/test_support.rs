@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Scratch directory that is removed again once the test is done with it.
+pub struct TempDir
+{
+    pub path: PathBuf
+}
+
+impl TempDir
+{
+    pub fn new() -> TempDir
+    {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let name: String = format!("osu-song-minifier-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::SeqCst));
+        let path: PathBuf = std::env::temp_dir().join(name);
+
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+impl Drop for TempDir
+{
+    fn drop(&mut self)
+    {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Writes a file, creating its parent directories.
+pub fn write(path: &Path, contents: &str)
+{
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}